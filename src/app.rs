@@ -1,6 +1,10 @@
-use crate::cli::{Cli, Commands};
-use ffdash::{config, engine, ui};
+use crate::cli::{
+    Cli, Commands, EventsFormat, HiddenPolicy, HistoryStatusArg, ProfileAction, ProfileFileFormat,
+    ScanFilterArgs,
+};
+use ffdash::{config, engine, stats, ui};
 use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 fn default_profile_name() -> String {
     config::Config::load()
@@ -9,25 +13,104 @@ fn default_profile_name() -> String {
 }
 
 pub fn run(cli: Cli) {
+    if let Ok(config) = config::Config::load() {
+        engine::probe::configure(config.probe);
+    }
+
     // Handle subcommands first
     if let Some(command) = cli.command {
         match command {
             Commands::CheckFfmpeg => handle_check_ffmpeg(),
             Commands::CheckVaapi { test_encode } => handle_check_vaapi(test_encode),
-            Commands::Probe { file } => handle_probe(file),
+            Commands::Probe { file, json } => handle_probe(file, json),
+            Commands::Calibrate {
+                path,
+                profile,
+                target,
+                save,
+                root,
+                json,
+                filter,
+            } => handle_calibrate(path, profile, target, save, root, json, filter),
+            Commands::Vmaf {
+                source,
+                encoded,
+                full,
+                window_duration,
+                budget,
+                subsample,
+                percentile,
+                json,
+            } => handle_vmaf(
+                source,
+                encoded,
+                full,
+                window_duration,
+                budget,
+                subsample,
+                percentile,
+                json,
+            ),
             Commands::Scan {
                 directory,
                 overwrite,
-            } => handle_scan(directory, overwrite),
+                filter,
+            } => handle_scan(directory, overwrite, filter),
             Commands::DryRun {
                 directory,
                 overwrite,
-            } => handle_dry_run(directory, overwrite),
+                profile,
+                output_dir,
+                pattern,
+                container,
+                hw,
+                json,
+                filter,
+            } => handle_dry_run(
+                directory, overwrite, profile, output_dir, pattern, container, hw, json, filter,
+            ),
             Commands::EncodeOne {
                 directory,
                 overwrite,
-            } => handle_encode_one(directory, overwrite),
+                filter,
+            } => handle_encode_one(directory, overwrite, filter),
+            Commands::Encode {
+                directory,
+                workers,
+                profile,
+                overwrite,
+                watch,
+                events,
+                events_file,
+                filter,
+            } => handle_encode(
+                directory,
+                workers,
+                profile,
+                overwrite,
+                watch,
+                events,
+                events_file,
+                filter,
+            ),
+            Commands::Status { directory, json } => handle_status(directory, json),
+            Commands::Stats {
+                json,
+                csv,
+                reset,
+                since,
+            } => handle_stats(json, csv, reset, since),
+            Commands::History {
+                path,
+                since,
+                until,
+                status,
+                profile,
+                limit,
+                json,
+            } => handle_history(path, since, until, status, profile, limit, json),
             Commands::InitConfig => handle_init_config(),
+            Commands::Profile { action } => handle_profile(action),
             #[cfg(feature = "dev-tools")]
             Commands::SmokeTest {
                 profiles,
@@ -46,7 +129,10 @@ pub fn run(cli: Cli) {
     }
 
     // Determine startup behavior from CLI flags and config
-    let config = config::Config::load().unwrap_or_default();
+    let mut config = config::Config::load().unwrap_or_default();
+    config.scan = scan_filter(&config, cli.filter);
+    job_policy(&config);
+    check_pattern(&config.defaults.filename_pattern);
 
     let autostart = if cli.autostart {
         Some(true)
@@ -64,13 +150,160 @@ pub fn run(cli: Cli) {
         None // Use config default
     };
 
+    // The TUI owns stdout, so its event side channel has to go to a file or FIFO
+    if cli.events.is_some() && cli.events_file.is_none() {
+        eprintln!("Error: --events in the TUI requires --events-file (stdout is the terminal)");
+        process::exit(1);
+    }
+    let event_sink = open_event_sink(cli.events, cli.events_file);
+
+    let mut paths = cli.paths;
+    if let Some(list) = &cli.from_list {
+        match engine::read_path_list(list) {
+            Ok(listed) => paths.extend(listed),
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                process::exit(1);
+            }
+        }
+    }
+    if let Some(missing) = paths.iter().find(|path| !path.exists()) {
+        eprintln!("Error: {} does not exist", missing.display());
+        process::exit(1);
+    }
+    // The watched directory is queued like any other path
+    let watch = cli.watch.map(|dir| std::path::absolute(&dir).unwrap_or(dir));
+    if let Some(dir) = &watch {
+        if !dir.is_dir() {
+            eprintln!("Error: --watch needs a directory: {}", dir.display());
+            process::exit(1);
+        }
+        if !paths.iter().any(|path| std::path::absolute(path).is_ok_and(|path| &path == dir)) {
+            paths.push(dir.clone());
+        }
+    }
+    let session = cli.session.unwrap_or_else(|| engine::DEFAULT_SESSION.to_string());
+    if let Err(e) = engine::session_dir(&session) {
+        eprintln!("Error: {:#}", e);
+        process::exit(1);
+    }
+
     // Launch TUI (default behavior)
-    if let Err(e) = ui::run_ui_with_options(cli.directory, autostart, scan_on_launch, &config) {
+    if let Err(e) = ui::run_ui_with_options(
+        paths,
+        session,
+        watch,
+        autostart,
+        scan_on_launch,
+        &config,
+        event_sink,
+    ) {
         eprintln!("Error running UI: {}", e);
         process::exit(1);
     }
 }
 
+/// Merge the `[scan]` config section with per-command overrides
+fn scan_filter(config: &config::Config, args: ScanFilterArgs) -> engine::ScanFilter {
+    let mut filter = config.scan.clone();
+    filter.include.extend(args.include);
+    filter.exclude.extend(args.exclude);
+    if args.min_size_mb.is_some() {
+        filter.min_size_mb = args.min_size_mb;
+    }
+    if args.max_size_mb.is_some() {
+        filter.max_size_mb = args.max_size_mb;
+    }
+    if args.min_duration.is_some() {
+        filter.min_duration_secs = args.min_duration;
+    }
+    if args.max_depth.is_some() {
+        filter.max_depth = args.max_depth;
+    }
+    filter.sniff |= args.sniff;
+    match args.hidden {
+        Some(HiddenPolicy::Include) => filter.hidden = engine::HiddenFiles::Include,
+        Some(HiddenPolicy::Skip) => filter.hidden = engine::HiddenFiles::Skip,
+        None => {}
+    }
+    filter
+}
+
+/// The `[skip]` / `[[rules]]` policy from config, exiting if a rule names a missing profile
+fn job_policy(config: &config::Config) -> engine::JobPolicy {
+    let policy = config.job_policy();
+    if let Err(e) = policy.validate() {
+        eprintln!("Error: invalid [[rules]] in config: {:#}", e);
+        process::exit(1);
+    }
+    policy
+}
+
+/// Lock a queue root for this process, exiting if another instance is using it
+fn lock_queue(root: &std::path::Path) -> engine::QueueLock {
+    match engine::QueueLock::acquire(root) {
+        Ok(lock) => lock,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Set by Ctrl+C during `encode --watch`
+static STOP_WATCHING: AtomicBool = AtomicBool::new(false);
+
+/// Make Ctrl+C stop `encode --watch` through its normal shutdown (queue saved, lock
+/// released) instead of killing the process. A second Ctrl+C exits right away.
+#[cfg(unix)]
+fn stop_watching_on_ctrl_c() {
+    extern "C" fn on_sigint(_: libc::c_int) {
+        if STOP_WATCHING.swap(true, Ordering::SeqCst) {
+            unsafe { libc::_exit(130) };
+        }
+    }
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_sigint as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+#[cfg(not(unix))]
+fn stop_watching_on_ctrl_c() {}
+
+/// Exit with an error if a filename pattern uses an unknown token, and warn about
+/// tokens with side effects
+fn check_pattern(pattern: &str) {
+    if let Err(e) = engine::validate_pattern(pattern) {
+        eprintln!("Error: {:#}", e);
+        process::exit(1);
+    }
+    if let Some(warning) = engine::pattern_warning(pattern) {
+        eprintln!("Warning: {}", warning);
+    }
+}
+
+/// Open the requested event stream (stdout when no file is given), exiting on failure
+fn open_event_sink(
+    format: Option<EventsFormat>,
+    file: Option<std::path::PathBuf>,
+) -> Option<engine::events::EventSink> {
+    match format? {
+        EventsFormat::Json => match file {
+            Some(path) => match engine::events::EventSink::open(&path) {
+                Ok(sink) => Some(sink),
+                Err(e) => {
+                    eprintln!("Error: {:#}", e);
+                    process::exit(1);
+                }
+            },
+            None => Some(engine::events::EventSink::stdout()),
+        },
+    }
+}
+
 fn handle_check_ffmpeg() {
     match engine::ffmpeg_version() {
         Ok(version) => {
@@ -182,48 +415,168 @@ fn handle_check_vaapi(test_encode: bool) {
     }
 }
 
-fn handle_probe(file: std::path::PathBuf) {
-    match engine::probe_duration(&file) {
-        Ok(duration) => {
-            println!("Duration: {:.2} seconds", duration);
-        }
+fn handle_probe(file: std::path::PathBuf, json: bool) {
+    use engine::probe::StreamKind;
+
+    let info = match engine::probe::probe_media_info(&file) {
+        Ok(info) => info,
         Err(e) => {
-            eprintln!("Error: {:#}", e);
+            eprintln!("Error: {}", e);
             process::exit(1);
         }
+    };
+
+    if json {
+        match serde_json::to_string_pretty(&info) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Error: failed to serialize probe result: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    match info.container.duration {
+        Some(duration) => println!("Duration: {:.2} seconds", duration),
+        None => println!("Duration: unknown"),
+    }
+    println!(
+        "Container: {}{}",
+        info.container.format_name,
+        info.container
+            .bitrate_bps
+            .map(|b| format!(", {} kb/s", b / 1000))
+            .unwrap_or_default()
+    );
+
+    for stream in &info.streams {
+        let mut details: Vec<String> = Vec::new();
+        match stream.kind {
+            StreamKind::Video => {
+                if let (Some(w), Some(h)) = (stream.width, stream.height) {
+                    details.push(format!("{}x{}", w, h));
+                }
+                if let Some(fps) = stream.frame_rate.filter(|_| !stream.is_attached_picture()) {
+                    details.push(format!("{:.3} fps", fps));
+                }
+                if let Some(depth) = stream.bit_depth {
+                    details.push(format!("{}-bit", depth));
+                }
+                if let Some(hdr) = &stream.hdr {
+                    let kind = if hdr.dolby_vision { "Dolby Vision" } else { "HDR" };
+                    details.push(format!("{} ({})", kind, hdr.transfer));
+                }
+                if stream.is_attached_picture() {
+                    details.push("attached picture".to_string());
+                }
+            }
+            StreamKind::Audio => {
+                if let Some(layout) = &stream.channel_layout {
+                    details.push(layout.clone());
+                } else if let Some(channels) = stream.channels {
+                    details.push(format!("{} ch", channels));
+                }
+                if let Some(rate) = stream.sample_rate {
+                    details.push(format!("{} Hz", rate));
+                }
+            }
+            StreamKind::Attachment => {
+                if let Some(filename) = &stream.filename {
+                    details.push(filename.clone());
+                }
+            }
+            _ => {}
+        }
+        if let Some(bitrate) = stream.bitrate_bps {
+            details.push(format!("{} kb/s", bitrate / 1000));
+        }
+        if let Some(lang) = &stream.language {
+            details.push(format!("[{}]", lang));
+        }
+        if let Some(title) = &stream.title {
+            details.push(format!("\"{}\"", title));
+        }
+        if stream.disposition.default {
+            details.push("default".to_string());
+        }
+        if stream.disposition.forced {
+            details.push("forced".to_string());
+        }
+
+        println!(
+            "  #{} {:?}: {}{}",
+            stream.index,
+            stream.kind,
+            stream.codec_name.as_deref().unwrap_or("unknown"),
+            if details.is_empty() {
+                String::new()
+            } else {
+                format!(", {}", details.join(", "))
+            }
+        );
+    }
+
+    if !info.chapters.is_empty() {
+        println!("Chapters: {}", info.chapters.len());
     }
 }
 
-fn handle_scan(directory: Option<std::path::PathBuf>, overwrite: bool) {
+fn handle_scan(directory: Option<std::path::PathBuf>, overwrite: bool, filter: ScanFilterArgs) {
     let dir = directory.unwrap_or_else(|| {
         std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."))
     });
     println!("Scanning directory: {}", dir.display());
+    let config = config::Config::load().unwrap_or_default();
+    let filter = scan_filter(&config, filter);
 
-    match engine::scan(&dir) {
-        Ok(files) => {
+    match engine::scan_with_filter(&dir, &filter) {
+        Ok(engine::ScanResult { files, excluded }) => {
             let profile = default_profile_name();
             let custom_output_dir: Option<&str> = None;
             let custom_pattern: Option<&str> = None;
             let custom_container: Option<&str> = None;
 
-            let jobs = engine::build_job_queue(
+            let jobs = engine::build_job_queue_with_policy(
                 files,
                 &profile,
                 overwrite,
                 custom_output_dir,
                 custom_pattern,
                 custom_container,
+                &job_policy(&config).with_scan_roots(vec![dir.clone()]),
             );
 
             for job in &jobs {
+                let rule = job
+                    .profile_rule
+                    .as_ref()
+                    .map(|rule| format!(" [rule '{}': {}]", rule, job.profile))
+                    .unwrap_or_default();
+                let skipped = match (&job.status, &job.last_error) {
+                    (engine::JobStatus::Skipped, Some(reason)) => format!(" (skipped: {})", reason),
+                    (engine::JobStatus::Skipped, None) => " (skipped: output exists)".to_string(),
+                    _ => String::new(),
+                };
                 println!(
-                    "- {} -> {}",
+                    "- {} -> {}{}{}",
                     job.input_path.display(),
-                    job.output_path.display()
+                    job.output_path.display(),
+                    rule,
+                    skipped
                 );
             }
             println!("Total jobs: {}", jobs.len());
+
+            if !excluded.is_empty() {
+                println!();
+                println!("Excluded:");
+                for (path, reason) in &excluded {
+                    let slash = if path.is_dir() { "/" } else { "" };
+                    println!("- {}{} ({})", path.display(), slash, reason);
+                }
+                println!("Total excluded: {}", excluded.len());
+            }
         }
         Err(e) => {
             eprintln!("Error scanning directory: {:#}", e);
@@ -394,46 +747,250 @@ fn load_profile_for_validation(name: &str) -> anyhow::Result<engine::core::Profi
     Err(anyhow!("Profile '{}' not found", name))
 }
 
-fn handle_dry_run(directory: Option<std::path::PathBuf>, overwrite: bool) {
+/// Resolve a `--profile` argument (or the configured default), exiting if it doesn't exist
+fn resolve_cli_profile(
+    profile: Option<String>,
+    config: &config::Config,
+) -> (String, engine::Profile) {
+    let profile_name = profile.unwrap_or_else(|| config.defaults.profile.clone());
+    match engine::Profile::find(&profile_name) {
+        Some(profile) => (profile_name, profile),
+        None => {
+            eprintln!("Error: profile '{}' not found", profile_name);
+            process::exit(1);
+        }
+    }
+}
+
+/// CLI overrides for where and how output files are named
+#[derive(Default)]
+struct OutputOverrides {
+    output_dir: Option<String>,
+    pattern: Option<String>,
+    container: Option<String>,
+}
+
+/// Build a job queue with the output settings the TUI applies for `profile`
+/// (profile output dir + container, global filename pattern), unless overridden.
+/// Files matching a `[[rules]]` entry in `policy` get that rule's profile and container instead.
+fn build_profile_job_queue(
+    files: Vec<std::path::PathBuf>,
+    profile_name: &str,
+    profile: &engine::Profile,
+    overwrite: bool,
+    overrides: &OutputOverrides,
+    config: &config::Config,
+    policy: &engine::JobPolicy,
+) -> Vec<engine::VideoJob> {
+    let output_dir = overrides
+        .output_dir
+        .as_deref()
+        .unwrap_or(profile.output_dir.as_str());
+    let custom_output_dir = match output_dir {
+        "" | "." => None,
+        output_dir => Some(output_dir),
+    };
+    let pattern = overrides
+        .pattern
+        .as_deref()
+        .unwrap_or(config.defaults.filename_pattern.as_str());
+    check_pattern(pattern);
+    let container = overrides
+        .container
+        .as_deref()
+        .unwrap_or(profile.container.as_str());
+
+    engine::build_job_queue_with_policy(
+        files,
+        profile_name,
+        overwrite,
+        custom_output_dir,
+        Some(pattern),
+        Some(container),
+        policy,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_dry_run(
+    directory: Option<std::path::PathBuf>,
+    overwrite: bool,
+    profile: Option<String>,
+    output_dir: Option<String>,
+    pattern: Option<String>,
+    container: Option<String>,
+    hw: bool,
+    json: bool,
+    filter: ScanFilterArgs,
+) {
+    use serde::Serialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct DryRunCommand {
+        program: String,
+        args: Vec<String>,
+        env: BTreeMap<String, String>,
+        shell: String,
+    }
+
+    #[derive(Serialize)]
+    struct DryRunJob {
+        input: std::path::PathBuf,
+        output: std::path::PathBuf,
+        profile: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        rule: Option<String>,
+        status: engine::JobStatus,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        auto_vmaf_target: Option<f32>,
+        commands: Vec<DryRunCommand>,
+    }
+
     let dir = directory.unwrap_or_else(|| {
         std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."))
     });
-    println!("Dry run: building ffmpeg commands for {}", dir.display());
-
-    match engine::scan(&dir) {
-        Ok(files) => {
-            let profile = default_profile_name();
-            let custom_output_dir: Option<&str> = None;
-            let custom_pattern: Option<&str> = None;
-            let custom_container: Option<&str> = None;
+    let config = config::Config::load().unwrap_or_default();
+    let (profile_name, mut profile) = resolve_cli_profile(profile, &config);
+    if hw {
+        profile.use_hardware_encoding = true;
+    }
 
-            let jobs = engine::build_job_queue(
-                files,
-                &profile,
-                overwrite,
-                custom_output_dir,
-                custom_pattern,
-                custom_container,
-            );
-            for job in &jobs {
-                let cmd = engine::build_ffmpeg_cmd(job, None);
-                println!("{:?}", cmd);
-            }
-        }
+    let filter = scan_filter(&config, filter);
+    let files = match engine::scan_with_filter(&dir, &filter) {
+        Ok(result) => result.files,
         Err(e) => {
             eprintln!("Error scanning directory: {:#}", e);
             process::exit(1);
         }
+    };
+
+    let overrides = OutputOverrides {
+        output_dir,
+        pattern,
+        container,
+    };
+    let jobs = build_profile_job_queue(
+        files,
+        &profile_name,
+        &profile,
+        overwrite || config.defaults.overwrite,
+        &overrides,
+        &config,
+        &job_policy(&config).with_scan_roots(vec![dir.clone()]),
+    );
+
+    // Auto-VMAF picks the final quality at encode time; the commands show the baseline
+    let auto_vmaf_target = profile.vmaf_enabled.then_some(profile.vmaf_target);
+
+    if json {
+        let report: Vec<DryRunJob> = jobs
+            .iter()
+            .map(|job| {
+                let rule_profile = engine::rule_profile(job);
+                let job_profile = rule_profile.as_ref().unwrap_or(&profile);
+                let commands = if job.status == engine::JobStatus::Pending {
+                    engine::build_ffmpeg_cmds_with_profile(job, None, Some(job_profile))
+                        .iter()
+                        .map(|cmd| DryRunCommand {
+                            program: cmd.get_program().to_string_lossy().into_owned(),
+                            args: cmd
+                                .get_args()
+                                .map(|a| a.to_string_lossy().into_owned())
+                                .collect(),
+                            env: cmd
+                                .get_envs()
+                                .filter_map(|(k, v)| {
+                                    v.map(|v| {
+                                        (
+                                            k.to_string_lossy().into_owned(),
+                                            v.to_string_lossy().into_owned(),
+                                        )
+                                    })
+                                })
+                                .collect(),
+                            shell: engine::format_command(cmd),
+                        })
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+                DryRunJob {
+                    input: job.input_path.clone(),
+                    output: job.output_path.clone(),
+                    profile: job.profile.clone(),
+                    rule: job.profile_rule.clone(),
+                    status: job.status.clone(),
+                    auto_vmaf_target,
+                    commands,
+                }
+            })
+            .collect();
+
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Error: failed to serialize dry run: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    println!(
+        "# Dry run: building ffmpeg commands for {} with '{}'",
+        dir.display(),
+        profile_name
+    );
+    if let Some(target) = auto_vmaf_target {
+        println!(
+            "# Auto-VMAF is enabled (target {:.1}): quality is calibrated per file before encoding, commands show the baseline quality",
+            target
+        );
+    }
+
+    for job in &jobs {
+        println!();
+        println!(
+            "# {} -> {}",
+            job.input_path.display(),
+            job.output_path.display()
+        );
+        if let Some(rule) = &job.profile_rule {
+            println!("# rule '{}' -> profile '{}'", rule, job.profile);
+        }
+        if job.status != engine::JobStatus::Pending {
+            match &job.last_error {
+                Some(reason) => println!("# skipped: {}", reason),
+                None => println!("# skipped: output exists (use --overwrite to re-encode)"),
+            }
+            continue;
+        }
+        let rule_profile = engine::rule_profile(job);
+        println!(
+            "{}",
+            engine::format_ffmpeg_cmd_with_profile(
+                job,
+                None,
+                Some(rule_profile.as_ref().unwrap_or(&profile))
+            )
+        );
     }
 }
 
-fn handle_encode_one(directory: Option<std::path::PathBuf>, overwrite: bool) {
+fn handle_encode_one(
+    directory: Option<std::path::PathBuf>,
+    overwrite: bool,
+    filter: ScanFilterArgs,
+) {
     let dir = directory.unwrap_or_else(|| {
         std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."))
     });
+    let config = config::Config::load().unwrap_or_default();
+    let filter = scan_filter(&config, filter);
 
-    match engine::scan(&dir) {
-        Ok(files) => {
+    match engine::scan_with_filter(&dir, &filter) {
+        Ok(engine::ScanResult { files, .. }) => {
             if files.is_empty() {
                 eprintln!("No video files found in {}", dir.display());
                 process::exit(0);
@@ -458,6 +1015,7 @@ fn handle_encode_one(directory: Option<std::path::PathBuf>, overwrite: bool) {
                     Ok(_) => println!("Encoded: {}", first_job.output_path.display()),
                     Err(e) => eprintln!("Encoding failed: {:#}", e),
                 }
+                append_history(first_job);
             }
         }
         Err(e) => {
@@ -467,27 +1025,1288 @@ fn handle_encode_one(directory: Option<std::path::PathBuf>, overwrite: bool) {
     }
 }
 
-fn handle_init_config() {
-    match config::Config::load() {
-        Ok(cfg) => {
-            match config::Config::config_path() {
-                Ok(path) => println!("Config loaded successfully from {}", path.display()),
-                Err(e) => println!("Config loaded, but config path unknown: {:#}", e),
+#[allow(clippy::too_many_arguments)]
+fn handle_encode(
+    directory: Option<std::path::PathBuf>,
+    workers: Option<usize>,
+    profile: Option<String>,
+    overwrite: bool,
+    watch: bool,
+    events: Option<EventsFormat>,
+    events_file: Option<std::path::PathBuf>,
+    filter: ScanFilterArgs,
+) {
+    let code = run_encode(
+        directory,
+        workers,
+        profile,
+        overwrite,
+        watch,
+        events,
+        events_file,
+        filter,
+    );
+    // Only now that the queue lock has been released
+    if code != 0 {
+        process::exit(code);
+    }
+}
+
+/// `handle_encode` returning its exit code, so the queue lock is dropped before exiting
+#[allow(clippy::too_many_arguments)]
+fn run_encode(
+    directory: Option<std::path::PathBuf>,
+    workers: Option<usize>,
+    profile: Option<String>,
+    overwrite: bool,
+    watch: bool,
+    events: Option<EventsFormat>,
+    events_file: Option<std::path::PathBuf>,
+    filter: ScanFilterArgs,
+) -> i32 {
+    use engine::worker::WorkerMessage;
+
+    // Keep stdout clean for the event stream when it is written there
+    let events_on_stdout = events.is_some() && events_file.is_none();
+    let say = |line: String| {
+        if events_on_stdout {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    };
+    let mut event_sink = open_event_sink(events, events_file);
+
+    let dir = directory.unwrap_or_else(|| {
+        std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."))
+    });
+    // Watched files are compared by absolute path
+    let dir = if watch {
+        std::path::absolute(&dir).unwrap_or(dir)
+    } else {
+        dir
+    };
+    let config = config::Config::load().unwrap_or_default();
+
+    let (profile_name, profile) = resolve_cli_profile(profile, &config);
+    let filter = scan_filter(&config, filter);
+    let policy = job_policy(&config).with_scan_roots(vec![dir.clone()]);
+
+    // Nothing below exits the process while the lock is held
+    let _lock = lock_queue(&dir);
+    let files = match engine::scan_with_filter(&dir, &filter) {
+        Ok(result) => result.files,
+        Err(e) => {
+            eprintln!("Error scanning directory: {:#}", e);
+            return 1;
+        }
+    };
+    if files.is_empty() && !watch {
+        say(format!("No video files found in {}", dir.display()));
+        return 0;
+    }
+
+    let overwrite = overwrite || config.defaults.overwrite;
+    let jobs = build_profile_job_queue(
+        files,
+        &profile_name,
+        &profile,
+        overwrite,
+        &OutputOverrides::default(),
+        &config,
+        &policy,
+    );
+
+    let total = jobs.len();
+    let pending = jobs
+        .iter()
+        .filter(|j| j.status == engine::JobStatus::Pending)
+        .count();
+    let workers = workers.unwrap_or(config.defaults.max_workers as usize).max(1);
+
+    say(format!(
+        "Encoding {} of {} jobs in {} with '{}' ({} worker(s))",
+        pending,
+        total,
+        dir.display(),
+        profile_name,
+        workers
+    ));
+
+    let mut state = engine::EncState::new_with_profile(
+        jobs,
+        profile_name.clone(),
+        dir.clone(),
+        Some(profile.clone()),
+    );
+    let restored = state.restore_calibrations(&dir);
+    if restored > 0 {
+        say(format!("Reusing {} stored calibration(s)", restored));
+    }
+    let resumed = state.restore_segments(&dir);
+    if resumed > 0 {
+        say(format!("Resuming {} segmented encode(s)", resumed));
+    }
+
+    let mut lifetime = stats::LifetimeStats::load().unwrap_or_default();
+
+    let on_message = |msg: &WorkerMessage, state: &engine::EncState| {
+        let find = |id| state.jobs.iter().find(|j| j.id == id);
+        if let Some(sink) = event_sink.as_mut() {
+            let _ = sink.emit(msg, msg.job_id().and_then(find));
+        }
+        record_lifetime_stats(&mut lifetime, msg, state);
+        record_history(msg, state);
+        match msg {
+            WorkerMessage::JobStarted { job_id } => {
+                if let Some(job) = find(*job_id) {
+                    say(format!("▶ Started: {}", job.input_path.display()));
+                }
             }
-            println!("{:#?}", cfg);
+            WorkerMessage::JobCompleted { job_id } => {
+                if let Some(job) = find(*job_id) {
+                    say(format!("✓ Completed: {}", job.output_path.display()));
+                }
+            }
+            WorkerMessage::JobFailed { job_id, error } => {
+                if let Some(job) = find(*job_id) {
+                    eprintln!("✗ Failed: {}\n{}", job.input_path.display(), error);
+                }
+            }
+            _ => {}
+        }
+    };
+
+    let result = if watch {
+        let mut watcher =
+            engine::watch::Watcher::polling(&dir, filter.clone(), config.watch.settle());
+        for job in &state.jobs {
+            watcher.ignore_job(job);
+        }
+        say(format!(
+            "Watching {} for new files (Ctrl+C to stop)",
+            dir.display()
+        ));
+        stop_watching_on_ctrl_c();
+
+        let build = |path| {
+            // One file in, one job out
+            build_profile_job_queue(
+                vec![path],
+                &profile_name,
+                &profile,
+                overwrite,
+                &OutputOverrides::default(),
+                &config,
+                &policy,
+            )
+            .remove(0)
+        };
+        let next_jobs = |queued: &[engine::VideoJob]| {
+            if STOP_WATCHING.load(Ordering::SeqCst) {
+                say("Stopped watching".to_string());
+                return None;
+            }
+            let mut jobs = watcher.poll_jobs(build).unwrap_or_default();
+            let mut claims = engine::OutputClaims::with_jobs(policy.collision, queued);
+            for job in &mut jobs {
+                claims.claim(job);
+                say(format!("+ Queued: {}", job.input_path.display()));
+            }
+            Some(jobs)
+        };
+        engine::batch::run_batch_watch(
+            &mut state,
+            &dir,
+            workers,
+            None,
+            config.watch.interval(),
+            next_jobs,
+            on_message,
+        )
+    } else {
+        engine::batch::run_batch(&mut state, &dir, workers, None, on_message)
+    };
+
+    match result {
+        Ok(summary) => {
+            say(format!(
+                "Done: {} | Failed: {} | Skipped: {}",
+                summary.done, summary.failed, summary.skipped
+            ));
+            i32::from(summary.has_failures())
         }
         Err(e) => {
-            println!("Config missing or invalid: {:#}", e);
-            println!("Creating default config...");
+            eprintln!("Error: {:#}", e);
+            1
+        }
+    }
+}
 
-            let cfg = config::Config::default();
-            if let Err(err) = cfg.save() {
-                eprintln!("Failed to save default config: {:#}", err);
-                process::exit(1);
-            } else {
-                match config::Config::config_path() {
-                    Ok(path) => println!("Default config saved to {}", path.display()),
-                    Err(e) => println!("Default config saved (path unknown): {:#}", e),
+fn handle_calibrate(
+    path: std::path::PathBuf,
+    profile: Option<String>,
+    target: Option<f32>,
+    save: bool,
+    save_root: Option<std::path::PathBuf>,
+    json: bool,
+    filter: ScanFilterArgs,
+) {
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct CalibrationReport {
+        input: std::path::PathBuf,
+        profile: String,
+        target: f32,
+        quality_param: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        quality: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        vmaf: Option<f32>,
+        attempts: u8,
+        hit_floor: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    }
+
+    let config = config::Config::load().unwrap_or_default();
+    let (profile_name, mut profile) = resolve_cli_profile(profile, &config);
+    profile.vmaf_enabled = true;
+    if let Some(target) = target {
+        if !(0.0..=100.0).contains(&target) {
+            eprintln!("Error: --target must be between 0 and 100");
+            process::exit(1);
+        }
+        profile.vmaf_target = target;
+    }
+
+    if !engine::vmaf::is_vmaf_compatible(&profile) {
+        eprintln!(
+            "Error: profile '{}' uses bitrate rate control; calibration needs CQ/CQP mode",
+            profile_name
+        );
+        process::exit(1);
+    }
+    if !engine::vmaf::vmaf_filter_available() {
+        eprintln!("Error: ffmpeg does not have the libvmaf filter");
+        process::exit(1);
+    }
+
+    let (root, files) = if path.is_file() {
+        let root = path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| std::path::PathBuf::from("."));
+        (root, vec![path.clone()])
+    } else {
+        match engine::scan_with_filter(&path, &scan_filter(&config, filter)) {
+            Ok(result) => (path.clone(), result.files),
+            Err(e) => {
+                eprintln!("Error scanning directory: {:#}", e);
+                process::exit(1);
+            }
+        }
+    };
+    let root = save_root.unwrap_or(root);
+    // Calibrations are saved into the queue state, so no other instance may own it
+    let lock = save.then(|| lock_queue(&root));
+    if files.is_empty() {
+        eprintln!("No video files found in {}", path.display());
+        return;
+    }
+
+    // Every file is calibrated with the chosen profile, so `[[rules]]` don't apply here
+    let policy = engine::JobPolicy {
+        rules: Vec::new(),
+        ..config.job_policy()
+    };
+    let mut jobs = build_profile_job_queue(
+        files,
+        &profile_name,
+        &profile,
+        config.defaults.overwrite,
+        &OutputOverrides::default(),
+        &config,
+        &policy,
+    );
+    let hw_config = profile
+        .use_hardware_encoding
+        .then(|| engine::hw_config_from_profile(&profile));
+    let quality_param = if profile.use_hardware_encoding {
+        "global_quality"
+    } else {
+        "crf"
+    };
+
+    let mut reports = Vec::with_capacity(jobs.len());
+    for job in &mut jobs {
+        eprintln!("Calibrating: {}", job.input_path.display());
+        let mut last_step = 0;
+        let mut progress = |job: &engine::VideoJob, _: &engine::ProgressParser| {
+            if let Some(total) = job.calibrating_total_steps {
+                if job.calibrating_completed_steps != last_step {
+                    last_step = job.calibrating_completed_steps;
+                    eprintln!("  window {}/{}", last_step, total);
+                }
+            }
+        };
+
+        let status_before = job.status.clone();
+        let mut report = CalibrationReport {
+            input: job.input_path.clone(),
+            profile: profile_name.clone(),
+            target: profile.vmaf_target,
+            quality_param,
+            quality: None,
+            vmaf: None,
+            attempts: 0,
+            hit_floor: false,
+            error: None,
+        };
+        match engine::vmaf::calibrate_quality(job, &profile, hw_config.as_ref(), &mut progress) {
+            Ok(result) => {
+                job.vmaf_target = Some(profile.vmaf_target);
+                job.vmaf_result = Some(result.measured_vmaf);
+                job.calibrated_quality = Some(result.quality);
+                job.calibrated_hw = Some(profile.use_hardware_encoding);
+                report.quality = Some(result.quality);
+                report.vmaf = Some(result.measured_vmaf);
+                report.attempts = result.attempts;
+                report.hit_floor = result.hit_floor;
+            }
+            Err(e) => report.error = Some(format!("{:#}", e)),
+        }
+        // calibrate_quality leaves the job looking mid-run; keep the saved queue clean
+        job.status = status_before;
+        job.progress_pct = 0.0;
+        job.calibrating_total_steps = None;
+        job.calibrating_completed_steps = 0;
+        job.vmaf_partial_scores.clear();
+        reports.push(report);
+    }
+
+    if save {
+        let calibrated: Vec<engine::VideoJob> = jobs
+            .iter()
+            .filter(|j| j.calibrated_quality.is_some())
+            .cloned()
+            .collect();
+        let state = if engine::EncState::exists(&root) {
+            engine::EncState::load_read_only(&root).map(|mut state| {
+                for job in calibrated {
+                    let existing = state
+                        .jobs
+                        .iter_mut()
+                        .find(|j| j.input_path == job.input_path);
+                    match existing {
+                        // A quality found for another profile means nothing to this job
+                        Some(existing) if existing.profile != job.profile => eprintln!(
+                            "Not saved for {}: it is queued with profile '{}'",
+                            job.input_path.display(),
+                            existing.profile
+                        ),
+                        Some(existing) => {
+                            existing.vmaf_target = job.vmaf_target;
+                            existing.vmaf_result = job.vmaf_result;
+                            existing.calibrated_quality = job.calibrated_quality;
+                            existing.calibrated_hw = job.calibrated_hw;
+                        }
+                        None => state.jobs.push(job),
+                    }
+                }
+                state
+            })
+        } else {
+            Ok(engine::EncState::new_with_profile(
+                jobs.clone(),
+                profile_name.clone(),
+                root.clone(),
+                Some(profile.clone()),
+            ))
+        };
+        let saved = state.and_then(|state| state.save(&root));
+        // Released before any exit below, which would skip its Drop
+        drop(lock);
+        match saved {
+            Ok(()) => eprintln!("Saved calibration to {}", root.join(".enc_state").display()),
+            Err(e) => {
+                eprintln!("Error: failed to save .enc_state: {:#}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    if json {
+        match serde_json::to_string_pretty(&reports) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Error: failed to serialize calibration results: {}", e);
+                process::exit(1);
+            }
+        }
+    } else {
+        for report in &reports {
+            match (report.quality, report.vmaf) {
+                (Some(quality), Some(vmaf)) => println!(
+                    "{}: {} {} -> VMAF {:.2} ({} attempt{}){}",
+                    report.input.display(),
+                    quality_param,
+                    quality,
+                    vmaf,
+                    report.attempts,
+                    if report.attempts == 1 { "" } else { "s" },
+                    if report.hit_floor {
+                        " - target not reached at quality floor"
+                    } else {
+                        ""
+                    }
+                ),
+                _ => println!(
+                    "{}: failed: {}",
+                    report.input.display(),
+                    report.error.as_deref().unwrap_or("unknown error")
+                ),
+            }
+        }
+    }
+
+    if reports.iter().any(|r| r.error.is_some()) {
+        process::exit(1);
+    }
+}
+
+/// Keep the Stats screen's lifetime totals in step with headless runs
+fn record_lifetime_stats(
+    lifetime: &mut stats::LifetimeStats,
+    msg: &engine::worker::WorkerMessage,
+    state: &engine::EncState,
+) {
+    use engine::worker::WorkerMessage;
+
+    match msg {
+        WorkerMessage::JobCompleted { job_id } => {
+            let Some(job) = state.jobs.iter().find(|j| j.id == *job_id) else {
+                return;
+            };
+            let size = |path: &std::path::Path| std::fs::metadata(path).map(|m| m.len());
+            if let (Ok(input_size), Ok(output_size)) =
+                (size(&job.input_path), size(&job.output_path))
+            {
+                let encode_time = job
+                    .started_at
+                    .map(|t| t.elapsed().as_secs_f64())
+                    .unwrap_or(0.0);
+                lifetime.record_completed(input_size, output_size, encode_time);
+                let _ = lifetime.save();
+            }
+        }
+        WorkerMessage::JobFailed { .. } => {
+            lifetime.record_failed();
+            let _ = lifetime.save();
+        }
+        _ => {}
+    }
+}
+
+/// Append finished and failed jobs from headless runs to the encode history
+fn record_history(msg: &engine::worker::WorkerMessage, state: &engine::EncState) {
+    use engine::worker::WorkerMessage;
+
+    if let WorkerMessage::JobCompleted { job_id } | WorkerMessage::JobFailed { job_id, .. } = msg {
+        if let Some(job) = state.jobs.iter().find(|j| j.id == *job_id) {
+            append_history(job);
+        }
+    }
+}
+
+fn append_history(job: &engine::VideoJob) {
+    if let Err(e) = engine::history::record_job(job) {
+        eprintln!("Warning: could not record history: {:#}", e);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_vmaf(
+    source: std::path::PathBuf,
+    encoded: std::path::PathBuf,
+    full: bool,
+    window_duration: u32,
+    budget: u32,
+    subsample: Option<u32>,
+    percentile: f32,
+    json: bool,
+) {
+    use engine::vmaf::VmafSampling;
+
+    for path in [&source, &encoded] {
+        if !path.is_file() {
+            eprintln!("Error: {} is not a file", path.display());
+            process::exit(1);
+        }
+    }
+    if !(0.0..=100.0).contains(&percentile) {
+        eprintln!("Error: --percentile must be between 0 and 100");
+        process::exit(1);
+    }
+
+    let (sampling, default_subsample) = if full {
+        (VmafSampling::Full, 1)
+    } else {
+        (
+            VmafSampling::Windows {
+                window_duration: window_duration.max(1),
+                budget_sec: budget.max(1),
+            },
+            30,
+        )
+    };
+    let n_subsample = subsample.unwrap_or(default_subsample).max(1);
+
+    let report = match engine::vmaf::score_encode(
+        &source,
+        &encoded,
+        sampling,
+        n_subsample,
+        percentile,
+        |idx, total| eprintln!("Scoring window {}/{}...", idx + 1, total),
+    ) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    };
+
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Error: failed to serialize VMAF report: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    println!("Mean VMAF: {:.2}", report.mean);
+    println!("Min VMAF: {:.2}", report.min);
+    println!("P{} VMAF: {:.2}", report.percentile, report.percentile_score);
+    println!(
+        "Frames scored: {} (every {} frame{})",
+        report.frames,
+        report.n_subsample,
+        if report.n_subsample == 1 { "" } else { "s" }
+    );
+    println!(
+        "Normalized to: {}p{} ({})",
+        report.height,
+        report
+            .fps
+            .map(|fps| format!(" @ {:.3} fps", fps))
+            .unwrap_or_default(),
+        report.model.trim_start_matches("version=")
+    );
+
+    if !full {
+        println!();
+        println!("Windows:");
+        for window in &report.windows {
+            println!(
+                "  {:>8.1}s +{:.0}s  mean {:.2}  min {:.2}",
+                window.start, window.duration, window.mean, window.min
+            );
+        }
+    }
+}
+
+fn format_duration_secs(seconds: u64) -> String {
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+
+    if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+fn handle_stats(json: bool, csv: bool, reset: bool, since: Option<String>) {
+    use serde::Serialize;
+    use stats::{LifetimeStats, StatsBucket, format_bytes, format_duration};
+
+    #[derive(Serialize)]
+    struct PeriodRow<'a> {
+        period: &'a str,
+        #[serde(flatten)]
+        bucket: &'a StatsBucket,
+        saved_bytes: i64,
+    }
+
+    #[derive(Serialize)]
+    struct StatsReport<'a> {
+        lifetime: &'a LifetimeStats,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        since: Option<String>,
+        period: StatsBucket,
+        months: Vec<PeriodRow<'a>>,
+    }
+
+    if reset {
+        match LifetimeStats::default().save() {
+            Ok(()) => println!("Statistics reset"),
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let since = since.map(|value| match parse_since_date(&value) {
+        Some(date) => date,
+        None => {
+            eprintln!(
+                "Error: invalid --since date '{}' (use YYYY-MM-DD or YYYY-MM)",
+                value
+            );
+            process::exit(1);
+        }
+    });
+
+    let lifetime = match LifetimeStats::load() {
+        Ok(stats) => stats,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    };
+
+    if csv {
+        println!(
+            "date,jobs_completed,jobs_failed,input_bytes,output_bytes,saved_bytes,encode_time_secs"
+        );
+        for (day, bucket) in lifetime.days_since(since) {
+            println!(
+                "{},{},{},{},{},{},{:.1}",
+                day,
+                bucket.jobs_completed,
+                bucket.jobs_failed,
+                bucket.input_bytes,
+                bucket.output_bytes,
+                bucket.saved_bytes(),
+                bucket.encode_time_secs
+            );
+        }
+        return;
+    }
+
+    let period = lifetime.total_since(since);
+    let monthly = lifetime.monthly_since(since);
+    let months: Vec<PeriodRow> = monthly
+        .iter()
+        .map(|(month, bucket)| PeriodRow {
+            period: month,
+            bucket,
+            saved_bytes: bucket.saved_bytes(),
+        })
+        .collect();
+
+    if json {
+        let report = StatsReport {
+            lifetime: &lifetime,
+            since: since.map(|d| d.format("%Y-%m-%d").to_string()),
+            period,
+            months,
+        };
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Error: failed to serialize stats: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let format_saved = |saved: i64| {
+        if saved >= 0 {
+            format!("{} saved", format_bytes(saved as u64))
+        } else {
+            format!("{} larger", format_bytes(saved.unsigned_abs()))
+        }
+    };
+
+    println!("Lifetime:");
+    println!(
+        "  Jobs: {} completed, {} failed",
+        lifetime.total_jobs_completed, lifetime.total_jobs_failed
+    );
+    println!(
+        "  Size: {} -> {} ({}, {:.2}x)",
+        lifetime.format_input_size(),
+        lifetime.format_output_size(),
+        format_saved(lifetime.total_input_bytes as i64 - lifetime.total_output_bytes as i64),
+        lifetime.compression_ratio()
+    );
+    println!("  Encode time: {}", lifetime.format_encode_time());
+
+    if let Some(date) = since {
+        println!();
+        println!("Since {}:", date.format("%Y-%m-%d"));
+        println!(
+            "  Jobs: {} completed, {} failed",
+            period.jobs_completed, period.jobs_failed
+        );
+        println!(
+            "  Size: {} -> {} ({})",
+            format_bytes(period.input_bytes),
+            format_bytes(period.output_bytes),
+            format_saved(period.saved_bytes())
+        );
+        println!(
+            "  Encode time: {}",
+            format_duration(period.encode_time_secs)
+        );
+    }
+
+    if !months.is_empty() {
+        println!();
+        println!("By month:");
+        for row in &months {
+            println!(
+                "  {}  {:>4} jobs  {}  {}",
+                row.period,
+                row.bucket.jobs_completed,
+                format_saved(row.saved_bytes),
+                format_duration(row.bucket.encode_time_secs)
+            );
+        }
+    }
+}
+
+/// Parse `--since` as a day (YYYY-MM-DD) or the first day of a month (YYYY-MM)
+fn parse_since_date(value: &str) -> Option<chrono::NaiveDate> {
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .or_else(|_| chrono::NaiveDate::parse_from_str(&format!("{}-01", value), "%Y-%m-%d"))
+        .ok()
+}
+
+/// Parse `--until` as a day (YYYY-MM-DD) or the last day of a month (YYYY-MM)
+fn parse_until_date(value: &str) -> Option<chrono::NaiveDate> {
+    if let Ok(day) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(day);
+    }
+    let first = parse_since_date(value)?;
+    let next_month = first.checked_add_months(chrono::Months::new(1))?;
+    next_month.pred_opt()
+}
+
+fn handle_history(
+    path: Option<std::path::PathBuf>,
+    since: Option<String>,
+    until: Option<String>,
+    status: Option<HistoryStatusArg>,
+    profile: Option<String>,
+    limit: Option<usize>,
+    json: bool,
+) {
+    use engine::history::{self, HistoryQuery, HistoryStatus};
+    use stats::{format_bytes, format_duration};
+
+    let date = |flag: &str, value: Option<String>, parse: fn(&str) -> Option<chrono::NaiveDate>| {
+        value.map(|value| match parse(&value) {
+            Some(date) => date,
+            None => {
+                eprintln!(
+                    "Error: invalid --{} date '{}' (use YYYY-MM-DD or YYYY-MM)",
+                    flag, value
+                );
+                process::exit(1);
+            }
+        })
+    };
+
+    let query = HistoryQuery {
+        path: path.map(|p| std::path::absolute(&p).unwrap_or(p)),
+        since: date("since", since, parse_since_date),
+        until: date("until", until, parse_until_date),
+        status: status.map(|s| match s {
+            HistoryStatusArg::Done => HistoryStatus::Done,
+            HistoryStatusArg::Failed => HistoryStatus::Failed,
+        }),
+        profile,
+    };
+
+    let records = match history::history_path().and_then(|p| history::read_from(&p)) {
+        Ok(records) => records,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    };
+    let mut matching: Vec<_> = records.iter().filter(|r| query.matches(r)).collect();
+    if let Some(limit) = limit {
+        matching.drain(..matching.len().saturating_sub(limit));
+    }
+
+    if json {
+        for record in &matching {
+            match serde_json::to_string(record) {
+                Ok(line) => println!("{}", line),
+                Err(e) => {
+                    eprintln!("Error: failed to serialize history: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        return;
+    }
+
+    if matching.is_empty() {
+        println!("No matching jobs in the history");
+        return;
+    }
+
+    for record in &matching {
+        let when = chrono::DateTime::parse_from_rfc3339(&record.finished_at)
+            .map(|t| {
+                t.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_else(|_| record.finished_at.clone());
+        let mark = match record.status {
+            HistoryStatus::Done => "✓",
+            HistoryStatus::Failed => "✗",
+        };
+        println!("{}  {} {}", when, mark, record.input_path.display());
+
+        let mut details = vec![record.profile.clone()];
+        if let Some(encoder) = &record.encoder {
+            match &record.fallback_from {
+                Some(from) => details.push(format!("{} (fallback from {})", encoder, from)),
+                None => details.push(encoder.clone()),
+            }
+        }
+        match (record.input_bytes, record.output_bytes) {
+            (Some(input), Some(output)) => details.push(format!(
+                "{} -> {}",
+                format_bytes(input),
+                format_bytes(output)
+            )),
+            (Some(input), None) => details.push(format_bytes(input)),
+            _ => {}
+        }
+        details.push(format_duration(record.timings.total_secs));
+        if let Some(vmaf) = record.vmaf_result {
+            details.push(format!("VMAF {:.2}", vmaf));
+        }
+        println!("    {}", details.join(" | "));
+
+        if let Some(error) = record.error.as_deref().and_then(|e| e.lines().last()) {
+            println!("    {}", error.trim());
+        }
+    }
+
+    let failed = matching
+        .iter()
+        .filter(|r| r.status == HistoryStatus::Failed)
+        .count();
+    println!();
+    println!(
+        "{} job(s): {} done, {} failed",
+        matching.len(),
+        matching.len() - failed,
+        failed
+    );
+}
+
+fn handle_status(directory: Option<std::path::PathBuf>, json: bool) {
+    use engine::JobStatus;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct StatusCounts {
+        pending: usize,
+        calibrating: usize,
+        running: usize,
+        done: usize,
+        failed: usize,
+        skipped: usize,
+    }
+
+    #[derive(Serialize)]
+    struct FailedJob<'a> {
+        input: &'a std::path::Path,
+        last_error: Option<&'a str>,
+    }
+
+    #[derive(Serialize)]
+    struct SkippedJob<'a> {
+        input: &'a std::path::Path,
+        reason: Option<&'a str>,
+    }
+
+    #[derive(Serialize)]
+    struct VmafJob<'a> {
+        input: &'a std::path::Path,
+        status: &'a JobStatus,
+        vmaf_result: f32,
+        vmaf_target: Option<f32>,
+        calibrated_quality: Option<u32>,
+    }
+
+    #[derive(Serialize)]
+    struct StatusReport<'a> {
+        root: &'a std::path::Path,
+        profile: &'a str,
+        total: usize,
+        counts: StatusCounts,
+        estimated_remaining_secs: Option<u64>,
+        failed: Vec<FailedJob<'a>>,
+        skipped: Vec<SkippedJob<'a>>,
+        vmaf: Vec<VmafJob<'a>>,
+    }
+
+    let dir = directory.unwrap_or_else(|| {
+        std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."))
+    });
+    if !engine::EncState::exists(&dir) {
+        eprintln!("Error: no saved queue (.enc_state) in {}", dir.display());
+        process::exit(1);
+    }
+    let state = match engine::EncState::load_read_only(&dir) {
+        Ok(state) => state,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            process::exit(1);
+        }
+    };
+
+    let max_workers = config::Config::load()
+        .map(|c| c.defaults.max_workers as usize)
+        .unwrap_or(1);
+    let count = |status: JobStatus| state.jobs.iter().filter(|j| j.status == status).count();
+    let profile = state
+        .profile_config
+        .as_ref()
+        .map(|p| p.name.as_str())
+        .unwrap_or(state.selected_profile.as_str());
+
+    let report = StatusReport {
+        root: &dir,
+        profile,
+        total: state.jobs.len(),
+        counts: StatusCounts {
+            pending: count(JobStatus::Pending),
+            calibrating: count(JobStatus::Calibrating),
+            running: count(JobStatus::Running),
+            done: count(JobStatus::Done),
+            failed: count(JobStatus::Failed),
+            skipped: count(JobStatus::Skipped),
+        },
+        estimated_remaining_secs: state
+            .estimated_remaining_secs(max_workers)
+            .map(|secs| secs.round() as u64),
+        failed: state
+            .jobs
+            .iter()
+            .filter(|j| j.status == JobStatus::Failed)
+            .map(|j| FailedJob {
+                input: &j.input_path,
+                last_error: j.last_error.as_deref(),
+            })
+            .collect(),
+        skipped: state
+            .jobs
+            .iter()
+            .filter(|j| j.status == JobStatus::Skipped)
+            .map(|j| SkippedJob {
+                input: &j.input_path,
+                reason: j.last_error.as_deref(),
+            })
+            .collect(),
+        vmaf: state
+            .jobs
+            .iter()
+            .filter_map(|j| {
+                j.vmaf_result.map(|vmaf_result| VmafJob {
+                    input: &j.input_path,
+                    status: &j.status,
+                    vmaf_result,
+                    vmaf_target: j.vmaf_target,
+                    calibrated_quality: j.calibrated_quality,
+                })
+            })
+            .collect(),
+    };
+
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Error: failed to serialize status: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let name = |path: &std::path::Path| {
+        path.file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string())
+    };
+
+    println!(
+        "Queue: {} ({} jobs, profile '{}')",
+        dir.display(),
+        report.total,
+        report.profile
+    );
+    let c = &report.counts;
+    println!(
+        "Pending: {} | Calibrating: {} | Running: {} | Done: {} | Failed: {} | Skipped: {}",
+        c.pending, c.calibrating, c.running, c.done, c.failed, c.skipped
+    );
+    match report.estimated_remaining_secs {
+        Some(secs) => println!(
+            "Estimated remaining: {} ({} worker(s))",
+            format_duration_secs(secs),
+            max_workers.max(1)
+        ),
+        None => println!("Estimated remaining: —"),
+    }
+
+    if !report.vmaf.is_empty() {
+        println!("\nVMAF:");
+        for job in &report.vmaf {
+            let target = job
+                .vmaf_target
+                .map(|t| format!(" (target {:.1})", t))
+                .unwrap_or_default();
+            let quality = job
+                .calibrated_quality
+                .map(|q| format!(", quality {}", q))
+                .unwrap_or_default();
+            println!(
+                "  {}: {:.2}{}{}",
+                name(job.input),
+                job.vmaf_result,
+                target,
+                quality
+            );
+        }
+    }
+
+    if !report.skipped.is_empty() {
+        println!("\nSkipped:");
+        for job in &report.skipped {
+            match job.reason {
+                Some(reason) => println!("  {}: {}", name(job.input), reason),
+                None => println!("  {}", name(job.input)),
+            }
+        }
+    }
+
+    if !report.failed.is_empty() {
+        println!("\nFailed:");
+        for job in &report.failed {
+            println!("  {}", name(job.input));
+            for line in job.last_error.unwrap_or("unknown error").lines() {
+                println!("    {}", line);
+            }
+        }
+    }
+}
+
+fn handle_init_config() {
+    match config::Config::load() {
+        Ok(cfg) => {
+            match config::Config::config_path() {
+                Ok(path) => println!("Config loaded successfully from {}", path.display()),
+                Err(e) => println!("Config loaded, but config path unknown: {:#}", e),
+            }
+            println!("{:#?}", cfg);
+        }
+        Err(e) => {
+            println!("Config missing or invalid: {:#}", e);
+            println!("Creating default config...");
+
+            let cfg = config::Config::default();
+            if let Err(err) = cfg.save() {
+                eprintln!("Failed to save default config: {:#}", err);
+                process::exit(1);
+            } else {
+                match config::Config::config_path() {
+                    Ok(path) => println!("Default config saved to {}", path.display()),
+                    Err(e) => println!("Default config saved (path unknown): {:#}", e),
+                }
+            }
+        }
+    }
+}
+
+fn handle_profile(action: ProfileAction) {
+    use engine::{Profile, ProfileFormat};
+
+    let to_engine_format = |format: ProfileFileFormat| match format {
+        ProfileFileFormat::Json => ProfileFormat::Json,
+        ProfileFileFormat::Toml => ProfileFormat::Toml,
+    };
+    let find_or_exit = |name: &str| {
+        Profile::find(name).unwrap_or_else(|| {
+            eprintln!("Error: profile '{}' not found", name);
+            process::exit(1);
+        })
+    };
+    let profiles_dir = || {
+        Profile::profiles_dir().unwrap_or_else(|e| {
+            eprintln!("Error: cannot access profiles directory: {}", e);
+            process::exit(1);
+        })
+    };
+
+    match action {
+        ProfileAction::List => {
+            let builtins = Profile::builtin_names();
+            let dir = profiles_dir();
+            let mut saved = Profile::list_saved(&dir).unwrap_or_default();
+            saved.sort();
+
+            println!("Built-in profiles:");
+            for name in &builtins {
+                println!("  {}", name);
+            }
+            println!("\nSaved profiles ({}):", dir.display());
+            if saved.is_empty() {
+                println!("  (none)");
+            }
+            for name in &saved {
+                if builtins.contains(name) {
+                    println!("  {} (overrides built-in)", name);
+                } else {
+                    println!("  {}", name);
+                }
+            }
+        }
+        ProfileAction::Show { name, format } => {
+            let profile = find_or_exit(&name);
+            match profile.to_string_as(to_engine_format(format)) {
+                Ok(content) => println!("{}", content.trim_end()),
+                Err(e) => {
+                    eprintln!("Error: failed to serialize profile: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        ProfileAction::Export {
+            name,
+            output,
+            format,
+        } => {
+            let profile = find_or_exit(&name);
+            let format = match (format, &output) {
+                (Some(format), _) => to_engine_format(format),
+                (None, Some(path)) => ProfileFormat::from_path(path),
+                (None, None) => ProfileFormat::Json,
+            };
+            let path = output.unwrap_or_else(|| {
+                std::path::PathBuf::from(format!(
+                    "{}.{}",
+                    profile.name.to_lowercase().replace(' ', "_"),
+                    format.extension()
+                ))
+            });
+
+            let result = profile
+                .to_string_as(format)
+                .and_then(|content| std::fs::write(&path, content));
+            match result {
+                Ok(()) => println!("Exported '{}' to {}", profile.name, path.display()),
+                Err(e) => {
+                    eprintln!("Error: failed to export profile: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        ProfileAction::Import { file, name, force } => {
+            let mut profile = match Profile::import_file(&file) {
+                Ok(profile) => profile,
+                Err(e) => {
+                    eprintln!("Error: failed to read {}: {}", file.display(), e);
+                    process::exit(1);
+                }
+            };
+            if let Some(name) = name {
+                profile.name = name;
+            }
+
+            let dir = profiles_dir();
+            if !force && Profile::load(&dir, &profile.name).is_ok() {
+                eprintln!(
+                    "Error: saved profile '{}' already exists (use --force to replace it)",
+                    profile.name
+                );
+                process::exit(1);
+            }
+            if let Err(e) = profile.save(&dir) {
+                eprintln!("Error: failed to save profile: {}", e);
+                process::exit(1);
+            }
+
+            println!("Imported '{}' into {}", profile.name, dir.display());
+            if Profile::get_builtin(&profile.name).is_some() {
+                println!("Note: this saved profile overrides the built-in of the same name");
+            }
+        }
+        ProfileAction::Diff { left, right } => {
+            let left_profile = find_or_exit(&left);
+            let right_profile = find_or_exit(&right);
+            let diffs = match left_profile.diff(&right_profile) {
+                Ok(diffs) => diffs,
+                Err(e) => {
+                    eprintln!("Error: failed to compare profiles: {}", e);
+                    process::exit(1);
+                }
+            };
+
+            if diffs.is_empty() {
+                println!("Profiles '{}' and '{}' are identical", left, right);
+                return;
+            }
+
+            let width = diffs.iter().map(|d| d.field.len()).max().unwrap_or(0);
+            println!("--- {}\n+++ {}", left, right);
+            for diff in &diffs {
+                println!(
+                    "{:width$}  {} -> {}",
+                    diff.field,
+                    diff.left.as_deref().unwrap_or("(unset)"),
+                    diff.right.as_deref().unwrap_or("(unset)"),
+                    width = width
+                );
+            }
+        }
+        ProfileAction::Delete { name } => {
+            let dir = profiles_dir();
+            match Profile::delete(&dir, &name) {
+                Ok(()) => println!("Deleted saved profile '{}'", name),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    if Profile::get_builtin(&name).is_some() {
+                        eprintln!("Error: '{}' is a built-in profile and cannot be deleted", name);
+                    } else {
+                        eprintln!("Error: saved profile '{}' not found", name);
+                    }
+                    process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("Error: failed to delete profile: {}", e);
+                    process::exit(1);
                 }
             }
         }
@@ -1,13 +1,27 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "ffdash")]
 #[command(about = "A dope FFmpeg dashboard", long_about = None)]
 pub struct Cli {
-    /// Root directory to scan for video files (defaults to current directory)
-    #[arg(value_name = "DIRECTORY")]
-    pub directory: Option<PathBuf>,
+    /// Directories or video files to queue (defaults to current directory).
+    /// More than one path, or any file, starts a session.
+    #[arg(value_name = "PATH")]
+    pub paths: Vec<PathBuf>,
+
+    /// Also queue the paths listed in FILE (one per line, # for comments)
+    #[arg(long, value_name = "FILE")]
+    pub from_list: Option<PathBuf>,
+
+    /// Session to store a multi-path queue in (defaults to "default").
+    /// Given without paths, resumes the sources that session saved.
+    #[arg(long, value_name = "NAME")]
+    pub session: Option<String>,
+
+    /// Queue DIR, then keep queueing new video files in it once they finish copying
+    #[arg(long, value_name = "DIR")]
+    pub watch: Option<PathBuf>,
 
     #[command(subcommand)]
     pub command: Option<Commands>,
@@ -31,6 +45,17 @@ pub struct Cli {
     /// Don't scan for files on TUI launch (overrides config)
     #[arg(long, conflicts_with = "scan")]
     pub no_scan: bool,
+
+    /// Mirror worker events as a machine-readable stream (requires --events-file in the TUI)
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub events: Option<EventsFormat>,
+
+    /// File or FIFO to write the event stream to
+    #[arg(long, value_name = "PATH", requires = "events")]
+    pub events_file: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub filter: ScanFilterArgs,
 }
 
 #[derive(Subcommand)]
@@ -45,10 +70,77 @@ pub enum Commands {
         test_encode: bool,
     },
 
-    /// Probe a video file to get its duration
+    /// Probe a video file: container, streams and chapters
     Probe {
         /// Path to the video file
         file: PathBuf,
+
+        /// Print the full stream inventory as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Score an existing encode against its source with VMAF
+    Vmaf {
+        /// Original source file (reference)
+        source: PathBuf,
+
+        /// Encoded file to score (distorted)
+        encoded: PathBuf,
+
+        /// Score the whole file instead of sampled windows
+        #[arg(long)]
+        full: bool,
+
+        /// Length of each sampled window in seconds
+        #[arg(long, default_value_t = 10, conflicts_with = "full")]
+        window_duration: u32,
+
+        /// Total seconds of video to sample across all windows
+        #[arg(long, default_value_t = 60, conflicts_with = "full")]
+        budget: u32,
+
+        /// Score every Nth frame (defaults to 30 for windows, 1 with --full)
+        #[arg(long, value_name = "N")]
+        subsample: Option<u32>,
+
+        /// Low percentile to report (5 = score that 95% of frames meet or beat)
+        #[arg(long, default_value_t = 5.0)]
+        percentile: f32,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Find the quality setting that reaches a VMAF target, without encoding
+    Calibrate {
+        /// Video file or directory to calibrate
+        path: PathBuf,
+
+        /// Profile to calibrate (defaults to the configured profile)
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// VMAF target (defaults to the profile's Auto-VMAF target)
+        #[arg(long)]
+        target: Option<f32>,
+
+        /// Store results in .enc_state so a later encode reuses them
+        #[arg(long)]
+        save: bool,
+
+        /// Queue directory whose .enc_state --save writes to (defaults to PATH, or the
+        /// file's folder)
+        #[arg(long, value_name = "DIR", requires = "save")]
+        root: Option<PathBuf>,
+
+        /// Print results as JSON
+        #[arg(long)]
+        json: bool,
+
+        #[command(flatten)]
+        filter: ScanFilterArgs,
     },
 
     /// Scan directory and show jobs without encoding
@@ -59,6 +151,9 @@ pub enum Commands {
         /// Include files that already have output files (re-encode)
         #[arg(long)]
         overwrite: bool,
+
+        #[command(flatten)]
+        filter: ScanFilterArgs,
     },
 
     /// Show ffmpeg commands without executing (dry run)
@@ -69,6 +164,33 @@ pub enum Commands {
         /// Include files that already have output files (re-encode)
         #[arg(long)]
         overwrite: bool,
+
+        /// Profile to build commands for, built-in or saved (defaults to config profile)
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+
+        /// Output directory (defaults to the profile's output directory)
+        #[arg(long, value_name = "DIR")]
+        output_dir: Option<String>,
+
+        /// Output filename pattern, e.g. "{basename}.av1" (defaults to config filename_pattern)
+        #[arg(long, value_name = "PATTERN")]
+        pattern: Option<String>,
+
+        /// Output container extension (defaults to the profile's container)
+        #[arg(long, value_name = "EXT")]
+        container: Option<String>,
+
+        /// Build hardware encoder commands regardless of the profile setting
+        #[arg(long)]
+        hw: bool,
+
+        /// Print commands as JSON instead of shell lines
+        #[arg(long)]
+        json: bool,
+
+        #[command(flatten)]
+        filter: ScanFilterArgs,
     },
 
     /// Encode only the first pending job
@@ -79,11 +201,112 @@ pub enum Commands {
         /// Include files that already have output files (re-encode)
         #[arg(long)]
         overwrite: bool,
+
+        #[command(flatten)]
+        filter: ScanFilterArgs,
+    },
+
+    /// Encode every pending job in a directory without the TUI
+    Encode {
+        /// Directory to scan (defaults to current directory)
+        directory: Option<PathBuf>,
+
+        /// Number of parallel encode workers (defaults to config max_workers)
+        #[arg(long, value_name = "N")]
+        workers: Option<usize>,
+
+        /// Profile to encode with, built-in or saved (defaults to config profile)
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+
+        /// Include files that already have output files (re-encode)
+        #[arg(long)]
+        overwrite: bool,
+
+        /// Keep running and encode new files as they appear in the directory
+        #[arg(long)]
+        watch: bool,
+
+        /// Emit worker events as a machine-readable stream (stdout unless --events-file is set)
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        events: Option<EventsFormat>,
+
+        /// File or FIFO to write the event stream to
+        #[arg(long, value_name = "PATH", requires = "events")]
+        events_file: Option<PathBuf>,
+
+        #[command(flatten)]
+        filter: ScanFilterArgs,
+    },
+
+    /// Show the saved queue for a directory without launching the TUI
+    Status {
+        /// Directory containing .enc_state (defaults to current directory)
+        directory: Option<PathBuf>,
+
+        /// Print machine-readable JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show lifetime encoding statistics
+    Stats {
+        /// Print totals and monthly breakdown as JSON
+        #[arg(long, conflicts_with = "csv")]
+        json: bool,
+
+        /// Print one CSV row per day
+        #[arg(long)]
+        csv: bool,
+
+        /// Clear all recorded statistics
+        #[arg(long, conflicts_with_all = ["json", "csv", "since"])]
+        reset: bool,
+
+        /// Only count days on or after DATE (YYYY-MM-DD or YYYY-MM)
+        #[arg(long, value_name = "DATE")]
+        since: Option<String>,
+    },
+
+    /// Show finished and failed encodes, including ones from cleared queues
+    History {
+        /// Only show jobs whose source or output is this file or inside this directory
+        path: Option<PathBuf>,
+
+        /// Only show jobs that ended on or after DATE (YYYY-MM-DD or YYYY-MM)
+        #[arg(long, value_name = "DATE")]
+        since: Option<String>,
+
+        /// Only show jobs that ended on or before DATE (YYYY-MM-DD or YYYY-MM)
+        #[arg(long, value_name = "DATE")]
+        until: Option<String>,
+
+        /// Only show jobs that ended this way
+        #[arg(long, value_enum)]
+        status: Option<HistoryStatusArg>,
+
+        /// Only show jobs encoded with this profile
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+
+        /// Show only the most recent N matching jobs
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+
+        /// Print the matching records as JSON lines
+        #[arg(long)]
+        json: bool,
     },
 
     /// Show config status and location, or create default config if missing
     InitConfig,
 
+    /// Manage built-in and saved encoding profiles
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+
     /// Run minimal encode/dry-run checks for selected profiles
     #[cfg(feature = "dev-tools")]
     SmokeTest {
@@ -129,6 +352,126 @@ pub fn parse() -> Cli {
     Cli::parse()
 }
 
+#[derive(Subcommand)]
+pub enum ProfileAction {
+    /// List built-in and saved profiles
+    List,
+
+    /// Print a resolved profile
+    Show {
+        /// Profile name (saved profiles take precedence over built-ins)
+        name: String,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ProfileFileFormat::Toml)]
+        format: ProfileFileFormat,
+    },
+
+    /// Write a profile to a file for sharing or version control
+    Export {
+        /// Profile name (saved profiles take precedence over built-ins)
+        name: String,
+
+        /// Destination file (defaults to <name>.<format> in the current directory)
+        #[arg(long, short, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// File format (defaults to the output extension, else JSON)
+        #[arg(long, value_enum)]
+        format: Option<ProfileFileFormat>,
+    },
+
+    /// Save a profile file (JSON or TOML) into the profiles directory
+    Import {
+        /// Profile file to import
+        file: PathBuf,
+
+        /// Save under a different name
+        #[arg(long, value_name = "NAME")]
+        name: Option<String>,
+
+        /// Replace an existing saved profile with the same name
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Compare two profiles field by field
+    Diff {
+        /// First profile name
+        left: String,
+
+        /// Second profile name
+        right: String,
+    },
+
+    /// Delete a saved profile
+    Delete {
+        /// Saved profile name
+        name: String,
+    },
+}
+
+/// Scan filter flags shared by every command that walks a directory.
+/// Patterns add to the `[scan]` lists in config.toml; the other flags override it.
+#[derive(Args, Clone, Default)]
+pub struct ScanFilterArgs {
+    /// Only queue files matching GLOB (repeatable)
+    #[arg(long = "include", value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Skip files and directories matching GLOB (repeatable)
+    #[arg(long = "exclude", value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Skip files smaller than MB mebibytes
+    #[arg(long, value_name = "MB")]
+    pub min_size_mb: Option<u64>,
+
+    /// Skip files larger than MB mebibytes
+    #[arg(long, value_name = "MB")]
+    pub max_size_mb: Option<u64>,
+
+    /// Skip files shorter than SECS seconds (probes every file)
+    #[arg(long, value_name = "SECS")]
+    pub min_duration: Option<f64>,
+
+    /// Directory levels to descend below the root (0 = root only)
+    #[arg(long, value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Whether to scan dot-files and dot-directories
+    #[arg(long, value_enum, value_name = "POLICY")]
+    pub hidden: Option<HiddenPolicy>,
+
+    /// Probe extensionless and unknown files and queue those with real video
+    #[arg(long)]
+    pub sniff: bool,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum HiddenPolicy {
+    Include,
+    Skip,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum ProfileFileFormat {
+    Json,
+    Toml,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum HistoryStatusArg {
+    Done,
+    Failed,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum EventsFormat {
+    /// Newline-delimited JSON, one object per worker message
+    Json,
+}
+
 #[cfg(feature = "dev-tools")]
 #[derive(clap::ValueEnum, Clone, Copy)]
 pub enum SmokeFormat {
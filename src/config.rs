@@ -12,6 +12,34 @@ pub struct Config {
 
     #[serde(default)]
     pub defaults: DefaultsConfig,
+
+    /// Include/exclude rules for directory scans
+    #[serde(default)]
+    pub scan: crate::engine::ScanFilter,
+
+    /// Scan-time rules for skipping already-efficient sources
+    #[serde(default)]
+    pub skip: crate::engine::SkipPolicy,
+
+    /// ffprobe pool size and per-probe timeout
+    #[serde(default)]
+    pub probe: crate::engine::probe::ProbeSettings,
+
+    /// Settle time and poll interval for `--watch`
+    #[serde(default)]
+    pub watch: crate::engine::watch::WatchSettings,
+
+    /// What to do when two jobs (or a job and its source) share an output path
+    #[serde(default)]
+    pub output: crate::engine::OutputSettings,
+
+    /// Segment-based resumable encoding of long files
+    #[serde(default)]
+    pub segments: crate::engine::SegmentSettings,
+
+    /// Ordered per-file profile rules (first match wins)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<crate::engine::ProfileRule>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -106,6 +134,17 @@ impl Default for DefaultsConfig {
 }
 
 impl Config {
+    /// The `[skip]` and `[[rules]]` sections, as applied when building jobs
+    pub fn job_policy(&self) -> crate::engine::JobPolicy {
+        crate::engine::JobPolicy {
+            skip: self.skip.clone(),
+            rules: self.rules.clone(),
+            collision: self.output.collision,
+            mirror_tree: self.output.mirror_tree,
+            scan_roots: Vec::new(),
+        }
+    }
+
     /// Get the path to the config file
     pub fn config_path() -> Result<PathBuf> {
         let config_dir = if cfg!(target_os = "macos") {
@@ -249,4 +288,101 @@ mod tests {
         let deserialized2: Config = toml::from_str(&toml_str2).unwrap();
         assert_eq!(deserialized2.defaults.filename_pattern, "{basename}");
     }
+
+    #[test]
+    fn test_scan_section_parses() {
+        let config: Config = toml::from_str(
+            r#"
+            [scan]
+            exclude = ["Extras", "*sample*"]
+            min_size_mb = 50
+            max_depth = 3
+            hidden = "skip"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.scan.exclude, vec!["Extras", "*sample*"]);
+        assert_eq!(config.scan.min_size_mb, Some(50));
+        assert_eq!(config.scan.max_depth, Some(3));
+        assert_eq!(config.scan.hidden, crate::engine::HiddenFiles::Skip);
+        assert!(config.scan.include.is_empty());
+        assert!(!config.scan.sniff);
+        assert!(
+            config.scan.extensions.iter().any(|ext| ext == "m2ts"),
+            "Unset extensions fall back to the default list"
+        );
+
+        // Round-trips through the writer used by Config::save
+        let written = toml::to_string_pretty(&config).unwrap();
+        let reread: Config = toml::from_str(&written).unwrap();
+        assert_eq!(reread.scan, config.scan);
+    }
+
+    #[test]
+    fn test_rules_table_parses_in_order() {
+        let config: Config = toml::from_str(
+            r#"
+            [[rules]]
+            name = "4K HDR films"
+            profile = "4K HDR Film"
+            min_height = 2160
+            hdr = true
+
+            [[rules]]
+            profile = "Anime"
+            path = "Anime/**"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.rules.len(), 2);
+        assert_eq!(config.rules[0].label(), "4K HDR films");
+        assert_eq!(config.rules[0].hdr, Some(true));
+        assert_eq!(config.rules[1].label(), "Anime");
+        assert_eq!(config.job_policy().rules, config.rules);
+
+        let written = toml::to_string_pretty(&config).unwrap();
+        let reread: Config = toml::from_str(&written).unwrap();
+        assert_eq!(reread.rules, config.rules);
+    }
+
+    #[test]
+    fn test_probe_section_defaults_and_overrides() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.probe.workers, None);
+        assert_eq!(config.probe.timeout_secs, 30);
+        assert!(config.probe.cache);
+        assert!(config.probe.effective_workers() >= 1);
+
+        let config: Config = toml::from_str(
+            r#"
+            [probe]
+            workers = 3
+            timeout_secs = 0
+            cache = false
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.probe.workers, Some(3));
+        assert_eq!(config.probe.timeout_secs, 0);
+        assert!(!config.probe.cache);
+        assert_eq!(config.probe.effective_workers(), 3);
+    }
+
+    #[test]
+    fn test_watch_section_defaults_and_overrides() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.watch.settle_secs, 10);
+        assert_eq!(config.watch.interval_secs, 5);
+
+        let config: Config = toml::from_str(
+            r#"
+            [watch]
+            settle_secs = 120
+            interval_secs = 0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.watch.settle(), std::time::Duration::from_secs(120));
+        assert_eq!(config.watch.interval(), std::time::Duration::from_secs(1));
+    }
 }
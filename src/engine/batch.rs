@@ -0,0 +1,417 @@
+// Headless batch encoding driven by the worker pool (no TUI)
+
+use anyhow::{Context, Result, bail};
+use std::path::Path;
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::{Duration, Instant};
+
+use super::worker::{WorkerMessage, WorkerPool};
+use super::{EncState, HwEncodingConfig, JobStatus, VideoJob};
+
+/// Outcome counts for a finished batch run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BatchSummary {
+    pub done: usize,
+    pub failed: usize,
+    pub skipped: usize,
+}
+
+impl BatchSummary {
+    pub fn has_failures(&self) -> bool {
+        self.failed > 0
+    }
+}
+
+/// Run every pending job in `state` through a worker pool until the queue drains.
+///
+/// Mirrors the TUI scheduler: jobs are dispatched as workers go idle, and
+/// `.enc_state` (in `root`) and `.enc_queue` (in each queue root) are rewritten
+/// after each job finishes, so a later TUI session sees the same queue.
+/// `on_message` is called after each worker message has been applied to `state`.
+pub fn run_batch<F>(
+    state: &mut EncState,
+    root: &Path,
+    max_workers: usize,
+    hw_config: Option<&HwEncodingConfig>,
+    on_message: F,
+) -> Result<BatchSummary>
+where
+    F: FnMut(&WorkerMessage, &EncState),
+{
+    run_queue(
+        state,
+        root,
+        max_workers,
+        hw_config,
+        None,
+        |_| None,
+        on_message,
+    )
+}
+
+/// Like `run_batch`, but keeps running after the queue drains: every `poll_interval`
+/// the jobs returned by `next_jobs` (given the current queue) are appended and dispatched.
+///
+/// Stops watching once `next_jobs` returns `None`, then finishes the jobs in flight.
+pub fn run_batch_watch<N, F>(
+    state: &mut EncState,
+    root: &Path,
+    max_workers: usize,
+    hw_config: Option<&HwEncodingConfig>,
+    poll_interval: Duration,
+    next_jobs: N,
+    on_message: F,
+) -> Result<BatchSummary>
+where
+    N: FnMut(&[VideoJob]) -> Option<Vec<VideoJob>>,
+    F: FnMut(&WorkerMessage, &EncState),
+{
+    run_queue(
+        state,
+        root,
+        max_workers,
+        hw_config,
+        Some(poll_interval),
+        next_jobs,
+        on_message,
+    )
+}
+
+fn run_queue<N, F>(
+    state: &mut EncState,
+    root: &Path,
+    max_workers: usize,
+    hw_config: Option<&HwEncodingConfig>,
+    poll_interval: Option<Duration>,
+    mut next_jobs: N,
+    mut on_message: F,
+) -> Result<BatchSummary>
+where
+    N: FnMut(&[VideoJob]) -> Option<Vec<VideoJob>>,
+    F: FnMut(&WorkerMessage, &EncState),
+{
+    let max_workers = max_workers.max(1);
+    let pool = WorkerPool::new(max_workers);
+
+    state
+        .save(root)
+        .context("Failed to save .enc_state before encoding")?;
+
+    // Track busy worker slots ourselves: the pool only bumps its active count once
+    // the worker thread is running, which is too late for back-to-back spawns. Each
+    // slot is a worker id, freed again by that worker's WorkerIdle.
+    let mut busy = vec![false; max_workers];
+    while spawn_next(&pool, state, hw_config, &mut busy)? {}
+
+    let mut watching = poll_interval;
+    let mut last_poll = Instant::now();
+
+    while busy.contains(&true) || watching.is_some() {
+        let msg = match watching {
+            Some(interval) => {
+                match pool
+                    .receiver()
+                    .recv_timeout(interval.saturating_sub(last_poll.elapsed()))
+                {
+                    Ok(msg) => Some(msg),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        bail!("Worker channel closed unexpectedly")
+                    }
+                }
+            }
+            None => Some(
+                pool.receiver()
+                    .recv()
+                    .context("Worker channel closed unexpectedly")?,
+            ),
+        };
+
+        if let Some(msg) = msg {
+            apply_message(state, &msg);
+
+            match &msg {
+                // Finished segments are saved right away, so a restart keeps them
+                WorkerMessage::SegmentsUpdated { .. }
+                | WorkerMessage::JobCompleted { .. }
+                | WorkerMessage::JobFailed { .. } => {
+                    let _ = state.save(root);
+                    let _ = state.save_all_queue_status(root);
+                }
+                WorkerMessage::WorkerIdle { worker_id } => {
+                    if let Some(slot) = busy.get_mut(*worker_id) {
+                        *slot = false;
+                    }
+                    while spawn_next(&pool, state, hw_config, &mut busy)? {}
+                }
+                _ => {}
+            }
+
+            on_message(&msg, state);
+        }
+
+        if let Some(interval) = watching {
+            if last_poll.elapsed() >= interval {
+                last_poll = Instant::now();
+                match next_jobs(&state.jobs) {
+                    Some(jobs) if !jobs.is_empty() => {
+                        state.jobs.extend(jobs);
+                        let _ = state.save(root);
+                        let _ = state.save_all_queue_status(root);
+                        while spawn_next(&pool, state, hw_config, &mut busy)? {}
+                    }
+                    Some(_) => {}
+                    None => watching = None,
+                }
+            }
+        }
+    }
+
+    let _ = state.save(root);
+    let _ = state.save_all_queue_status(root);
+
+    Ok(summarize(state))
+}
+
+/// Dispatch the next encodable job on the lowest free worker slot, returning false when
+/// every slot is busy or the queue is drained
+fn spawn_next(
+    pool: &WorkerPool,
+    state: &mut EncState,
+    hw_config: Option<&HwEncodingConfig>,
+    busy: &mut [bool],
+) -> Result<bool> {
+    let Some(worker_id) = busy.iter().position(|&slot| !slot) else {
+        return Ok(false);
+    };
+    let Some(idx) = state.next_encodable_job() else {
+        return Ok(false);
+    };
+
+    let job = state.jobs[idx].clone();
+    state.jobs[idx].status = JobStatus::Running;
+    // Rule-matched jobs take their hardware settings from their own profile
+    let hw_config = if job.profile_rule.is_some() {
+        None
+    } else {
+        hw_config.cloned()
+    };
+    let profile = state.profile_for(&job);
+    pool.spawn_worker_with_profile(worker_id, job, hw_config, profile)?;
+    busy[worker_id] = true;
+
+    Ok(true)
+}
+
+/// Apply a worker message to the persisted job list
+fn apply_message(state: &mut EncState, msg: &WorkerMessage) {
+    match msg {
+        WorkerMessage::JobStarted { job_id } => {
+            if let Some(job) = state.jobs.iter_mut().find(|j| j.id == *job_id) {
+                job.status = JobStatus::Running;
+                job.started_at = Some(std::time::Instant::now());
+            }
+        }
+        WorkerMessage::ProgressUpdate {
+            job_id,
+            progress_pct,
+            out_time_s,
+            fps,
+            speed,
+            bitrate_kbps,
+            size_bytes,
+            vmaf_result,
+            vmaf_target,
+            status,
+        } => {
+            if let Some(job) = state.jobs.iter_mut().find(|j| j.id == *job_id) {
+                job.status = status.clone();
+                job.progress_pct = *progress_pct;
+                job.out_time_s = *out_time_s;
+                job.fps = *fps;
+                job.speed = *speed;
+                job.bitrate_kbps = *bitrate_kbps;
+                job.size_bytes = *size_bytes;
+                job.vmaf_result = *vmaf_result;
+                job.vmaf_target = *vmaf_target;
+            }
+        }
+        WorkerMessage::SegmentsUpdated { job_id, segments } => {
+            if let Some(job) = state.jobs.iter_mut().find(|j| j.id == *job_id) {
+                job.segments = segments.clone();
+            }
+        }
+        WorkerMessage::JobReport { job_id, report } => {
+            if let Some(job) = state.jobs.iter_mut().find(|j| j.id == *job_id) {
+                job.report = Some(report.clone());
+            }
+        }
+        WorkerMessage::JobCompleted { job_id } => {
+            if let Some(job) = state.jobs.iter_mut().find(|j| j.id == *job_id) {
+                job.status = JobStatus::Done;
+                job.progress_pct = 100.0;
+            }
+        }
+        WorkerMessage::JobFailed { job_id, error } => {
+            if let Some(job) = state.jobs.iter_mut().find(|j| j.id == *job_id) {
+                job.status = JobStatus::Failed;
+                job.last_error = Some(error.clone());
+            }
+        }
+        // Calibration progress also arrives as ProgressUpdate; nothing extra to persist
+        WorkerMessage::CalibrationStep { .. } | WorkerMessage::WorkerIdle { .. } => {}
+    }
+}
+
+fn summarize(state: &EncState) -> BatchSummary {
+    let count = |status: JobStatus| state.jobs.iter().filter(|j| j.status == status).count();
+    BatchSummary {
+        done: count(JobStatus::Done),
+        failed: count(JobStatus::Failed),
+        skipped: count(JobStatus::Skipped),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{CollisionPolicy, VideoJob};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_run_batch_with_no_pending_jobs_returns_immediately() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut skipped = VideoJob::new(
+            PathBuf::from("a.mp4"),
+            PathBuf::from("a.webm"),
+            "vp9-good".to_string(),
+        );
+        skipped.status = JobStatus::Skipped;
+        let mut done = VideoJob::new(
+            PathBuf::from("b.mp4"),
+            PathBuf::from("b.webm"),
+            "vp9-good".to_string(),
+        );
+        done.status = JobStatus::Done;
+
+        let mut state = EncState::new(
+            vec![skipped, done],
+            "vp9-good".to_string(),
+            temp_dir.path().to_path_buf(),
+        );
+
+        let mut messages = 0;
+        let summary = run_batch(&mut state, temp_dir.path(), 2, None, |_, _| messages += 1)
+            .expect("batch should succeed");
+
+        assert_eq!(messages, 0, "No jobs should have been dispatched");
+        assert_eq!(
+            summary,
+            BatchSummary {
+                done: 1,
+                failed: 0,
+                skipped: 1
+            }
+        );
+        assert!(!summary.has_failures());
+        assert!(EncState::exists(temp_dir.path()), ".enc_state should be persisted");
+        assert!(temp_dir.path().join(".enc_queue").exists());
+    }
+
+    #[test]
+    fn test_run_batch_watch_appends_new_jobs_until_stopped() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut state = EncState::new(
+            Vec::new(),
+            "vp9-good".to_string(),
+            temp_dir.path().to_path_buf(),
+        );
+
+        // Already-encoded arrivals are queued as skipped, so nothing is dispatched
+        let mut polls = 0;
+        let summary = run_batch_watch(
+            &mut state,
+            temp_dir.path(),
+            1,
+            None,
+            Duration::from_millis(1),
+            |_| {
+                polls += 1;
+                let mut job = VideoJob::new(
+                    PathBuf::from(format!("new{}.mp4", polls)),
+                    PathBuf::from(format!("new{}.webm", polls)),
+                    "vp9-good".to_string(),
+                );
+                job.status = JobStatus::Skipped;
+                (polls < 3).then(|| vec![job])
+            },
+            |_, _| {},
+        )
+        .expect("watch should stop cleanly");
+
+        assert_eq!(polls, 3);
+        assert_eq!(summary.skipped, 2);
+        let saved = EncState::load(temp_dir.path(), CollisionPolicy::default()).unwrap();
+        assert_eq!(saved.jobs.len(), 2, "Arrivals should be persisted");
+    }
+
+    #[test]
+    fn test_spawn_next_takes_lowest_free_worker_slot() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let job = VideoJob::new(
+            temp_dir.path().join("missing.mp4"),
+            temp_dir.path().join("missing.webm"),
+            "vp9-good".to_string(),
+        );
+        let mut state = EncState::new(
+            vec![job],
+            "vp9-good".to_string(),
+            temp_dir.path().to_path_buf(),
+        );
+        let pool = WorkerPool::new(3);
+
+        let mut busy = [true, true, true];
+        assert!(!spawn_next(&pool, &mut state, None, &mut busy).unwrap());
+        assert_eq!(state.jobs[0].status, JobStatus::Pending);
+
+        let mut busy = [true, false, false];
+        assert!(spawn_next(&pool, &mut state, None, &mut busy).unwrap());
+        assert_eq!(busy, [true, true, false]);
+        assert!(!spawn_next(&pool, &mut state, None, &mut busy).unwrap());
+
+        // The worker reports idle under the slot it was given
+        let messages = pool.receiver();
+        let idle = loop {
+            let msg = messages.recv_timeout(Duration::from_secs(30)).unwrap();
+            if let WorkerMessage::WorkerIdle { worker_id } = msg {
+                break worker_id;
+            }
+        };
+        assert_eq!(idle, 1);
+    }
+
+    #[test]
+    fn test_apply_message_tracks_failure() {
+        let job = VideoJob::new(
+            PathBuf::from("a.mp4"),
+            PathBuf::from("a.webm"),
+            "vp9-good".to_string(),
+        );
+        let job_id = job.id;
+        let mut state = EncState::new(vec![job], "vp9-good".to_string(), PathBuf::from("."));
+
+        apply_message(&mut state, &WorkerMessage::JobStarted { job_id });
+        assert_eq!(state.jobs[0].status, JobStatus::Running);
+
+        apply_message(
+            &mut state,
+            &WorkerMessage::JobFailed {
+                job_id,
+                error: "boom".to_string(),
+            },
+        );
+        assert_eq!(state.jobs[0].status, JobStatus::Failed);
+        assert_eq!(state.jobs[0].last_error.as_deref(), Some("boom"));
+        assert!(summarize(&state).has_failures());
+    }
+}
@@ -3,10 +3,19 @@ mod builtin_profiles;
 mod ffmpeg_cmd;
 mod ffmpeg_info;
 mod hw_config;
+mod job_policy;
+mod lock;
 mod log;
+mod output;
 mod profile;
+mod queue_order;
 mod scan;
+mod scan_filter;
+mod segments;
+mod session;
+mod skip_policy;
 mod state;
+mod template;
 mod types;
 mod vp9_config;
 
@@ -15,17 +24,43 @@ pub use ffmpeg_cmd::{
     build_av1_nvenc_cmd, build_av1_qsv_cmd, build_av1_software_cmd, build_av1_vaapi_cmd,
     build_ffmpeg_cmd, build_ffmpeg_cmd_with_profile, build_ffmpeg_cmds_with_profile,
     build_software_cmd, build_vaapi_cmd, encode_job, encode_job_with_callback,
-    encode_job_with_callback_and_profile, format_ffmpeg_cmd, two_pass_log_prefix,
+    encode_job_with_callback_and_profile, format_command, format_ffmpeg_cmd,
+    format_ffmpeg_cmd_with_profile, hw_config_from_profile, two_pass_log_prefix,
     validate_vaapi_config,
 };
 pub use ffmpeg_info::{
     ffmpeg_version, ffprobe_version, parse_ffprobe_duration, probe_duration, vmaf_filter_available,
 };
 pub use hw_config::HwEncodingConfig;
+pub use job_policy::{JobPolicy, ProfileRule, rule_profile};
+pub use lock::QueueLock;
 pub use log::write_debug_log;
-pub use profile::{Profile, derive_output_path};
-pub use scan::{build_job_from_path, build_job_queue, is_video_file, scan, scan_streaming};
+pub use output::{
+    CollisionPolicy, OutputClaims, OutputSettings, finish_partial, is_partial, job_partial_path,
+    partial_path, resolve_own_output,
+};
+pub use profile::{
+    Profile, ProfileFieldDiff, ProfileFormat, derive_output_path, derive_output_path_with,
+};
+pub use queue_order::{
+    QueueSort, estimated_savings_bytes, move_job, natural_cmp, next_pending, sort_jobs, toggle_pin,
+};
+pub use scan::{
+    ScanResult, build_job_from_path, build_job_queue, build_job_queue_with_policy,
+    build_job_with_policy, is_video_file, scan, scan_jobs_streaming, scan_streaming,
+    scan_streaming_filtered, scan_with_filter,
+};
+pub use scan_filter::{
+    DEFAULT_VIDEO_EXTENSIONS, ExclusionReason, HiddenFiles, ScanFilter, classify_media, glob_match,
+    pattern_matches, sniff_file,
+};
+pub use segments::{Segment, SegmentPlan, SegmentSettings};
+pub use session::{
+    DEFAULT_SESSION, data_dir, expand_sources, queue_root, read_path_list, session_dir,
+};
+pub use skip_policy::SkipPolicy;
 pub use state::EncState;
+pub use template::{MediaName, PATTERN_TOKENS, PatternContext, pattern_warning, validate_pattern};
 pub use types::{JobStatus, ProgressParser, VideoJob};
 pub use vp9_config::Vp9Config;
 
@@ -43,11 +78,325 @@ mod tests {
         assert!(is_video_file(Path::new("test.mov")));
         assert!(is_video_file(Path::new("test.avi")));
 
+        assert!(is_video_file(Path::new("test.m2ts")));
+        assert!(is_video_file(Path::new("test.VOB")));
+
         assert!(!is_video_file(Path::new("test.txt")));
         assert!(!is_video_file(Path::new("test.jpg")));
         assert!(!is_video_file(Path::new("test")));
     }
 
+    #[test]
+    fn test_scan_filter_extensions_and_sniff_candidates() {
+        let filter = ScanFilter {
+            extensions: vec![".TS".to_string(), "mkv".to_string()],
+            ..ScanFilter::default()
+        };
+        assert!(filter.has_video_extension(Path::new("rec/show.ts")));
+        assert!(filter.has_video_extension(Path::new("movie.MKV")));
+        assert!(!filter.has_video_extension(Path::new("movie.mp4")));
+        assert!(
+            !filter.should_sniff(Path::new("capture")),
+            "Sniff is opt-in"
+        );
+
+        let sniffing = ScanFilter {
+            sniff: true,
+            ..filter
+        };
+        assert!(sniffing.should_sniff(Path::new("capture")));
+        assert!(sniffing.should_sniff(Path::new("capture.dat")));
+        assert!(!sniffing.should_sniff(Path::new("movie.en.srt")));
+        assert!(!sniffing.should_sniff(Path::new("poster.JPG")));
+    }
+
+    #[test]
+    fn test_classify_media_rejects_cover_art_and_images() {
+        use crate::engine::probe::MediaInfo;
+
+        let classify =
+            |json: serde_json::Value| classify_media(&MediaInfo::from_ffprobe_json(&json));
+
+        let video = serde_json::json!({
+            "streams": [{ "index": 0, "codec_type": "video", "codec_name": "mpeg2video" }],
+            "format": { "format_name": "mpegts" }
+        });
+        assert_eq!(classify(video), None);
+
+        let cover_art = serde_json::json!({
+            "streams": [
+                { "index": 0, "codec_type": "audio", "codec_name": "aac" },
+                { "index": 1, "codec_type": "video", "codec_name": "mjpeg", "disposition": { "attached_pic": 1 } }
+            ],
+            "format": { "format_name": "mov,mp4,m4a,3gp,3g2,mj2" }
+        });
+        assert_eq!(
+            classify(cover_art),
+            Some(ExclusionReason::AttachedPictureOnly)
+        );
+
+        let audio = serde_json::json!({
+            "streams": [{ "index": 0, "codec_type": "audio", "codec_name": "mp3" }],
+            "format": { "format_name": "mp3" }
+        });
+        assert_eq!(classify(audio), Some(ExclusionReason::NoVideoStream));
+
+        let image = serde_json::json!({
+            "streams": [{ "index": 0, "codec_type": "video", "codec_name": "mjpeg" }],
+            "format": { "format_name": "jpeg_pipe" }
+        });
+        assert_eq!(classify(image), Some(ExclusionReason::StillImage));
+    }
+
+    #[test]
+    fn test_skip_policy_codec_bitrate_and_bpp() {
+        use crate::engine::probe::MediaInfo;
+
+        // 1080p30 at 2 Mbps is ~0.032 bits per pixel
+        let info = |codec: &str| {
+            MediaInfo::from_ffprobe_json(&serde_json::json!({
+                "streams": [{
+                    "index": 0, "codec_type": "video", "codec_name": codec,
+                    "width": 1920, "height": 1080, "r_frame_rate": "30/1", "bit_rate": "2000000"
+                }],
+                "format": { "format_name": "matroska,webm", "bit_rate": "2200000" }
+            }))
+        };
+
+        let disabled = SkipPolicy::default();
+        assert!(!disabled.is_enabled());
+        assert_eq!(disabled.skip_reason(&info("h264")), None);
+
+        let codecs = SkipPolicy {
+            codecs: vec!["AV1".to_string(), "hevc".to_string()],
+            ..SkipPolicy::default()
+        };
+        assert!(codecs.is_enabled());
+        assert_eq!(
+            codecs.skip_reason(&info("av1")).as_deref(),
+            Some("Source is already av1")
+        );
+        assert_eq!(codecs.skip_reason(&info("h264")), None);
+
+        let bitrate = SkipPolicy {
+            max_bitrate_kbps: Some(2000),
+            ..SkipPolicy::default()
+        };
+        assert!(bitrate.skip_reason(&info("h264")).is_some());
+
+        let bpp = |max| SkipPolicy {
+            max_bits_per_pixel: Some(max),
+            ..SkipPolicy::default()
+        };
+        assert!(bpp(0.05).skip_reason(&info("h264")).is_some());
+        assert_eq!(bpp(0.02).skip_reason(&info("h264")), None);
+    }
+
+    #[test]
+    fn test_profile_rules_first_match_wins() {
+        use crate::engine::probe::MediaInfo;
+
+        let uhd_hdr = MediaInfo::from_ffprobe_json(&serde_json::json!({
+            "streams": [{
+                "index": 0, "codec_type": "video", "codec_name": "hevc",
+                "width": 3840, "height": 2160, "color_transfer": "smpte2084"
+            }],
+            "format": { "format_name": "matroska,webm", "duration": "7200.0" }
+        }));
+        let sd = MediaInfo::from_ffprobe_json(&serde_json::json!({
+            "streams": [{ "index": 0, "codec_type": "video", "codec_name": "mpeg2video", "height": 480 }],
+            "format": { "format_name": "mpeg", "duration": "1500.0" }
+        }));
+
+        let policy = JobPolicy {
+            rules: vec![
+                ProfileRule {
+                    name: Some("Animation".to_string()),
+                    profile: "Anime".to_string(),
+                    path: Some("Anime/**".to_string()),
+                    ..ProfileRule::default()
+                },
+                ProfileRule {
+                    profile: "4K HDR".to_string(),
+                    min_height: Some(2160),
+                    hdr: Some(true),
+                    ..ProfileRule::default()
+                },
+                ProfileRule {
+                    profile: "SD".to_string(),
+                    max_height: Some(576),
+                    max_duration_secs: Some(3600.0),
+                    ..ProfileRule::default()
+                },
+            ],
+            ..JobPolicy::default()
+        };
+        assert!(policy.needs_probe());
+
+        let label = |path: &str, info: Option<&MediaInfo>| {
+            policy
+                .match_rule(Path::new(path), info)
+                .map(|rule| rule.label().to_string())
+        };
+        assert_eq!(
+            label("/media/Anime/Show/ep1.mkv", Some(&uhd_hdr)).as_deref(),
+            Some("Animation"),
+            "Earlier rules win"
+        );
+        assert_eq!(
+            label("/media/Films/Dune.mkv", Some(&uhd_hdr)).as_deref(),
+            Some("4K HDR")
+        );
+        assert_eq!(label("/media/TV/old.mpg", Some(&sd)).as_deref(), Some("SD"));
+        assert_eq!(
+            label("/media/TV/old.mpg", None),
+            None,
+            "Unprobed files only match path rules"
+        );
+        assert_eq!(
+            label("/media/Anime/ep2.mkv", None).as_deref(),
+            Some("Animation")
+        );
+    }
+
+    #[test]
+    fn test_glob_match_components_and_double_star() {
+        assert!(glob_match("*.mkv", "Movie.MKV"));
+        assert!(!glob_match("*.mkv", "dir/movie.mkv"));
+        assert!(glob_match("**/*.mkv", "movie.mkv"));
+        assert!(glob_match("**/*.mkv", "a/b/movie.mkv"));
+        assert!(glob_match("tv/**", "TV/Show/S01E01.mkv"));
+        assert!(glob_match("s??e*", "s01e02.mkv"));
+
+        assert!(pattern_matches("Extras", "Movie (2020)/extras/clip.mkv"));
+        assert!(pattern_matches("*sample*", "Movie/movie-sample.mkv"));
+        assert!(!pattern_matches("Extras", "Movie/Extras Edition.mkv"));
+        assert!(pattern_matches(
+            "Movies/*/trailers/",
+            "movies/Heat/Trailers"
+        ));
+    }
+
+    #[test]
+    fn test_scan_with_filter_reports_exclusions() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("Movie/Extras")).unwrap();
+        std::fs::create_dir_all(root.join("Deep/Deeper")).unwrap();
+        std::fs::create_dir_all(root.join(".hidden")).unwrap();
+        std::fs::write(root.join("Movie/movie.mkv"), vec![0u8; 2 * 1024 * 1024]).unwrap();
+        std::fs::write(root.join("Movie/Extras/featurette.mkv"), b"x").unwrap();
+        std::fs::write(root.join("Movie/tiny.mkv"), b"x").unwrap();
+        std::fs::write(root.join("Movie/notes.txt"), b"x").unwrap();
+        std::fs::write(root.join("Deep/Deeper/far.mkv"), b"x").unwrap();
+        std::fs::write(root.join(".hidden/secret.mkv"), b"x").unwrap();
+
+        let filter = ScanFilter {
+            exclude: vec!["Extras".to_string()],
+            min_size_mb: Some(1),
+            max_depth: Some(1),
+            hidden: HiddenFiles::Skip,
+            ..ScanFilter::default()
+        };
+        let result = scan_with_filter(root, &filter).unwrap();
+
+        assert_eq!(result.files, vec![root.join("Movie/movie.mkv")]);
+        let reason_for = |rel: &str| {
+            result
+                .excluded
+                .iter()
+                .find(|(p, _)| p == &root.join(rel))
+                .map(|(_, r)| r.clone())
+        };
+        assert_eq!(
+            reason_for("Movie/Extras"),
+            Some(ExclusionReason::ExcludePattern {
+                pattern: "Extras".to_string()
+            })
+        );
+        assert!(matches!(
+            reason_for("Movie/tiny.mkv"),
+            Some(ExclusionReason::TooSmall { .. })
+        ));
+        assert_eq!(
+            reason_for("Deep/Deeper"),
+            Some(ExclusionReason::MaxDepth { max_depth: 1 })
+        );
+        assert_eq!(reason_for(".hidden"), Some(ExclusionReason::Hidden));
+        assert_eq!(
+            reason_for("Movie/notes.txt"),
+            None,
+            "Non-video files are not reported"
+        );
+
+        // The default filter keeps the previous behavior
+        let mut all = scan(root).unwrap();
+        all.sort();
+        assert_eq!(all.len(), 5);
+    }
+
+    #[test]
+    fn test_map_ordered_keeps_input_order() {
+        // Early items finish last, so results complete out of order
+        let mut results = Vec::new();
+        scan::map_ordered(
+            0..16u64,
+            4,
+            |n| {
+                std::thread::sleep(std::time::Duration::from_millis((16 - n) * 2));
+                n * 10
+            },
+            |r| results.push(r),
+        );
+        assert_eq!(results, (0..16u64).map(|n| n * 10).collect::<Vec<_>>());
+
+        let mut single = Vec::new();
+        scan::map_ordered(vec!["a", "b"], 0, str::to_uppercase, |r| single.push(r));
+        assert_eq!(single, vec!["A", "B"]);
+    }
+
+    #[test]
+    fn test_scan_jobs_streaming_matches_walk_order() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for dir in ["a", "b", "c"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            for name in ["1.mkv", "2.mp4", "skip.txt"] {
+                std::fs::write(root.join(dir).join(name), b"x").unwrap();
+            }
+        }
+
+        let filter = ScanFilter::default();
+        let walked = scan_with_filter(root, &filter).unwrap().files;
+        let mut streamed = Vec::new();
+        scan_jobs_streaming(
+            root,
+            &filter,
+            |path| VideoJob::new(path.clone(), path, "test".to_string()),
+            |job| streamed.push(job.input_path),
+        )
+        .unwrap();
+
+        assert_eq!(walked.len(), 6);
+        assert_eq!(streamed, walked);
+    }
+
+    #[test]
+    fn test_scan_filter_include_patterns() {
+        let filter = ScanFilter {
+            include: vec!["TV/**".to_string()],
+            ..ScanFilter::default()
+        };
+        assert_eq!(
+            filter.check_file(Path::new("/lib/Movies/a.mkv"), Path::new("Movies/a.mkv")),
+            Some(ExclusionReason::NotIncluded)
+        );
+        assert_eq!(
+            filter.check_file(Path::new("/lib/TV/Show/b.mkv"), Path::new("TV/Show/b.mkv")),
+            None
+        );
+    }
+
     #[test]
     fn test_parse_ffprobe_duration() {
         // Sample ffprobe JSON output
@@ -150,6 +499,902 @@ mod tests {
         assert_eq!(deserialized.root_path, PathBuf::from("/test/path"));
     }
 
+    #[test]
+    fn test_enc_state_load_read_only_keeps_running_and_failed() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut running = VideoJob::new(
+            PathBuf::from("a.mp4"),
+            PathBuf::from("a.webm"),
+            "vp9-good".to_string(),
+        );
+        running.status = JobStatus::Running;
+        running.progress_pct = 40.0;
+        let mut failed = VideoJob::new(
+            PathBuf::from("b.mp4"),
+            PathBuf::from("b.webm"),
+            "vp9-good".to_string(),
+        );
+        failed.status = JobStatus::Failed;
+        failed.last_error = Some("encoder crashed".to_string());
+
+        EncState::new(
+            vec![running, failed],
+            "vp9-good".to_string(),
+            temp_dir.path().to_path_buf(),
+        )
+        .save(temp_dir.path())
+        .unwrap();
+
+        let inspected = EncState::load_read_only(temp_dir.path()).unwrap();
+        assert_eq!(inspected.jobs[0].status, JobStatus::Running);
+        assert_eq!(inspected.jobs[0].progress_pct, 40.0);
+        assert_eq!(inspected.jobs[1].status, JobStatus::Failed);
+
+        let resumed = EncState::load(temp_dir.path(), CollisionPolicy::default()).unwrap();
+        assert_eq!(resumed.jobs[0].status, JobStatus::Pending);
+        assert_eq!(resumed.jobs[1].status, JobStatus::Pending);
+    }
+
+    #[test]
+    fn test_enc_state_estimated_remaining_secs() {
+        let job = |status: JobStatus, duration: Option<f64>| {
+            let mut job = VideoJob::new(
+                PathBuf::from("in.mp4"),
+                PathBuf::from("out.webm"),
+                "vp9-good".to_string(),
+            );
+            job.status = status;
+            job.duration_s = duration;
+            job
+        };
+
+        let mut done = job(JobStatus::Done, Some(600.0));
+        done.speed = Some(2.0);
+        let mut running = job(JobStatus::Running, Some(600.0));
+        running.out_time_s = 200.0;
+        running.smoothed_speed = Some(2.0);
+
+        let state = EncState::new(
+            vec![
+                done,
+                running,
+                job(JobStatus::Pending, Some(1000.0)),
+                job(JobStatus::Pending, None),
+                job(JobStatus::Skipped, Some(5000.0)),
+            ],
+            "vp9-good".to_string(),
+            PathBuf::from("."),
+        );
+
+        // (400 + 1000) media seconds at 2.0x
+        assert_eq!(state.estimated_remaining_secs(1), Some(700.0));
+        assert_eq!(state.estimated_remaining_secs(2), Some(350.0));
+
+        let finished = EncState::new(
+            vec![job(JobStatus::Done, Some(60.0))],
+            "vp9-good".to_string(),
+            PathBuf::from("."),
+        );
+        assert_eq!(finished.estimated_remaining_secs(1), None);
+    }
+
+    #[test]
+    fn test_natural_cmp_orders_episodes() {
+        use std::cmp::Ordering;
+
+        let mut names = vec![
+            "Show/Season 10/Show S10E01.mkv",
+            "Show/Season 2/Show S02E10.mkv",
+            "Show/Season 2/show S02E2.mkv",
+            "Show/Season 2/Show S02E01.mkv",
+        ];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            vec![
+                "Show/Season 2/Show S02E01.mkv",
+                "Show/Season 2/show S02E2.mkv",
+                "Show/Season 2/Show S02E10.mkv",
+                "Show/Season 10/Show S10E01.mkv",
+            ]
+        );
+        assert_eq!(natural_cmp("ep007", "ep7"), Ordering::Greater);
+        assert_eq!(natural_cmp("a", "a1"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_queue_priority_moves_and_pins() {
+        let job = |name: &str| {
+            VideoJob::new(
+                PathBuf::from(name),
+                PathBuf::from(format!("{}.webm", name)),
+                "vp9-good".to_string(),
+            )
+        };
+        let mut jobs = vec![job("a"), job("b"), job("c"), job("d")];
+        jobs[0].status = JobStatus::Done;
+
+        // Queue order among equal priorities
+        assert_eq!(next_pending(&jobs), Some(1));
+
+        // Pinning beats queue order; pinning again unpins
+        toggle_pin(&mut jobs, 3);
+        assert_eq!(jobs[3].priority, 1);
+        assert_eq!(next_pending(&jobs), Some(3));
+        toggle_pin(&mut jobs, 2);
+        assert_eq!(jobs[2].priority, 2);
+        assert_eq!(next_pending(&jobs), Some(2));
+        toggle_pin(&mut jobs, 2);
+        assert_eq!(jobs[2].priority, 0);
+        assert_eq!(next_pending(&jobs), Some(3));
+
+        // Moves swap neighbours and stop at the ends
+        assert_eq!(move_job(&mut jobs, 1, true), Some(0));
+        assert_eq!(jobs[0].input_path, PathBuf::from("b"));
+        assert_eq!(move_job(&mut jobs, 0, true), None);
+        assert_eq!(move_job(&mut jobs, 3, false), None);
+        assert_eq!(next_pending(&jobs), Some(3));
+
+        // The persisted state takes the dashboard's order and priorities
+        let mut state = EncState::new(
+            vec![job("x"), jobs[2].clone(), jobs[0].clone()],
+            "vp9-good".to_string(),
+            PathBuf::from("."),
+        );
+        let mut reordered = vec![jobs[0].clone(), jobs[2].clone()];
+        reordered[1].priority = 5;
+        state.apply_order(&reordered);
+        assert_eq!(state.jobs[0].id, jobs[0].id);
+        assert_eq!(state.jobs[1].id, jobs[2].id);
+        assert_eq!(state.jobs[1].priority, 5);
+        assert_eq!(state.jobs[2].input_path, PathBuf::from("x"));
+        assert_eq!(state.next_encodable_job(), Some(1));
+    }
+
+    #[test]
+    fn test_sort_jobs_by_name_size_and_duration() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let job = |name: &str, bytes: usize, duration: f64| {
+            let path = temp_dir.path().join(name);
+            std::fs::write(&path, vec![0u8; bytes]).unwrap();
+            let mut job = VideoJob::new(path, PathBuf::from("out.webm"), "vp9-good".to_string());
+            job.duration_s = Some(duration);
+            job
+        };
+        let mut jobs = vec![
+            job("ep10.mkv", 10, 300.0),
+            job("ep2.mkv", 30, 100.0),
+            job("ep1.mkv", 20, 200.0),
+        ];
+        let names = |jobs: &[VideoJob]| {
+            jobs.iter()
+                .map(|j| j.input_path.file_name().unwrap().to_string_lossy())
+                .map(|name| name.into_owned())
+                .collect::<Vec<_>>()
+        };
+
+        sort_jobs(&mut jobs, QueueSort::Name);
+        assert_eq!(names(&jobs), vec!["ep1.mkv", "ep2.mkv", "ep10.mkv"]);
+        sort_jobs(&mut jobs, QueueSort::Size);
+        assert_eq!(names(&jobs), vec!["ep2.mkv", "ep1.mkv", "ep10.mkv"]);
+        sort_jobs(&mut jobs, QueueSort::Duration);
+        assert_eq!(names(&jobs), vec!["ep10.mkv", "ep1.mkv", "ep2.mkv"]);
+        assert_eq!(QueueSort::Savings.next(), QueueSort::Name);
+
+        // Started jobs project savings from their progress so far
+        jobs[0].progress_pct = 50.0;
+        jobs[0].size_bytes = Some(2);
+        assert_eq!(estimated_savings_bytes(&jobs[0]), Some(6));
+    }
+
+    #[test]
+    fn test_session_sources_expand_in_order() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("shows")).unwrap();
+        std::fs::create_dir_all(root.join("movies")).unwrap();
+        std::fs::write(root.join("shows/ep1.mkv"), b"x").unwrap();
+        std::fs::write(root.join("shows/notes.txt"), b"x").unwrap();
+        std::fs::write(root.join("movies/film.mp4"), b"x").unwrap();
+        std::fs::write(
+            root.join("list.txt"),
+            "# queued tonight\nmovies/film.mp4\n\nshows\n",
+        )
+        .unwrap();
+
+        let listed = read_path_list(&root.join("list.txt")).unwrap();
+        assert_eq!(listed, vec![root.join("movies/film.mp4"), root.join("shows")]);
+
+        // The film is named directly and reached again through its directory
+        let mut sources = listed;
+        sources.push(root.join("movies"));
+        let files = expand_sources(&sources, &ScanFilter::default()).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                (root.join("movies/film.mp4"), root.join("movies/film.mp4")),
+                (root.join("shows"), root.join("shows/ep1.mkv")),
+            ]
+        );
+        assert_eq!(queue_root(&root.join("movies/film.mp4")), root.join("movies"));
+        assert_eq!(queue_root(&root.join("shows")), root.join("shows"));
+
+        sources.push(root.join("missing"));
+        assert!(expand_sources(&sources, &ScanFilter::default()).is_err());
+        assert!(session_dir("../escape").is_err());
+        assert!(session_dir(DEFAULT_SESSION).unwrap().ends_with("sessions/default"));
+    }
+
+    #[test]
+    fn test_session_queue_status_per_root() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (shows, movies, session) = (
+            temp_dir.path().join("shows"),
+            temp_dir.path().join("movies"),
+            temp_dir.path().join("session"),
+        );
+        for dir in [&shows, &movies, &session] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        std::fs::write(movies.join("film.mp4"), b"x").unwrap();
+        // Another queue already tracks this directory
+        std::fs::write(movies.join(".enc_queue"), "# VP9 header\n# old.mp4\nfilm.mp4\n").unwrap();
+
+        let job = |origin: &Path, input: PathBuf, status: JobStatus| {
+            let mut job = VideoJob::new(input, PathBuf::from("out.webm"), "vp9-good".to_string());
+            job.origin = Some(origin.to_path_buf());
+            job.status = status;
+            job
+        };
+        let mut state = EncState::new(
+            vec![
+                job(&shows, shows.join("ep1.mkv"), JobStatus::Done),
+                job(&movies.join("film.mp4"), movies.join("film.mp4"), JobStatus::Done),
+            ],
+            "vp9-good".to_string(),
+            session.clone(),
+        );
+        state.sources = vec![shows.clone(), movies.join("film.mp4")];
+        assert_eq!(state.queue_roots(&session), vec![shows.clone(), movies.clone()]);
+
+        state.save_all_queue_status(&session).unwrap();
+        let queue = |dir: &Path| std::fs::read_to_string(dir.join(".enc_queue")).unwrap();
+        assert!(queue(&shows).contains("# ep1.mkv"));
+        assert!(!queue(&shows).contains("film.mp4"));
+        assert!(queue(&movies).contains("# old.mp4"), "Other queue's lines are kept");
+        assert!(queue(&movies).contains("# film.mp4"));
+        assert!(!queue(&movies).lines().any(|line| line == "film.mp4"));
+        assert!(!session.join(".enc_queue").exists());
+
+        // Sources and origins survive the session file
+        state.save(&session).unwrap();
+        let loaded = EncState::load(&session, CollisionPolicy::default()).unwrap();
+        assert!(loaded.is_session());
+        assert_eq!(loaded.sources, state.sources);
+        assert_eq!(loaded.jobs[0].origin, Some(shows.clone()));
+    }
+
+    #[test]
+    fn test_output_collisions_follow_policy() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let job = |input: &str, output: &str| {
+            VideoJob::new(dir.join(input), dir.join(output), "vp9-good".to_string())
+        };
+
+        let mut claims = OutputClaims::new(CollisionPolicy::Suffix);
+        let mut jobs = vec![
+            job("show.mp4", "show.webm"),
+            job("show.mkv", "show.webm"),
+            job("show.avi", "show.webm"),
+        ];
+        let collided: Vec<bool> = jobs.iter_mut().map(|j| claims.claim(j)).collect();
+        assert_eq!(collided, vec![false, true, true]);
+        assert_eq!(jobs[1].output_path, dir.join("show (2).webm"));
+        assert_eq!(jobs[2].output_path, dir.join("show (3).webm"));
+        assert!(jobs.iter().all(|j| j.status == JobStatus::Pending));
+
+        // A suffixed name an earlier run already wrote is skipped, saying why
+        std::fs::write(dir.join("show (2).webm"), b"done").unwrap();
+        let mut claims = OutputClaims::new(CollisionPolicy::Suffix);
+        let mut jobs = vec![job("show.mp4", "show.webm"), job("show.mkv", "show.webm")];
+        for j in &mut jobs {
+            claims.claim(j);
+        }
+        assert_eq!(jobs[1].status, JobStatus::Skipped);
+        assert_eq!(
+            jobs[1].last_error.as_deref(),
+            Some("Output show (2).webm already exists and overwrite is disabled")
+        );
+
+        // Jobs already in the queue keep their outputs
+        let queued = vec![job("a.mp4", "a.webm")];
+        let mut skip = OutputClaims::with_jobs(CollisionPolicy::Skip, &queued);
+        let mut late = job("a.mkv", "a.webm");
+        assert!(skip.claim(&mut late));
+        assert_eq!(late.status, JobStatus::Skipped);
+        assert_eq!(
+            late.last_error.as_deref(),
+            Some("Output a.webm is already written for a.mp4")
+        );
+
+        let mut error = OutputClaims::with_jobs(CollisionPolicy::Error, &queued);
+        let mut late = job("a.mkv", "a.webm");
+        assert!(error.claim(&mut late));
+        assert_eq!(late.status, JobStatus::Failed);
+
+        // Jobs that won't write anything only claim
+        let mut done = job("a.avi", "a.webm");
+        done.status = JobStatus::Done;
+        assert!(!error.claim(&mut done));
+
+        let mut own = job("clip.webm", "clip.webm");
+        assert!(resolve_own_output(&mut own, CollisionPolicy::Skip));
+        assert_eq!(own.status, JobStatus::Skipped);
+        let mut own = job("clip.webm", "clip.webm");
+        assert!(!resolve_own_output(&mut own, CollisionPolicy::Suffix));
+        assert_eq!(own.output_path, dir.join("clip (2).webm"));
+    }
+
+    #[test]
+    fn test_build_job_queue_detects_output_collisions() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let files: Vec<PathBuf> = ["show.mp4", "show.mkv", "clip.webm"]
+            .iter()
+            .map(|name| {
+                std::fs::write(dir.join(name), b"x").unwrap();
+                dir.join(name)
+            })
+            .collect();
+
+        let jobs = build_job_queue_with_policy(
+            files.clone(),
+            "vp9-good",
+            false,
+            None,
+            Some("{basename}"),
+            Some("webm"),
+            &JobPolicy::default(),
+        );
+        assert_eq!(jobs[0].status, JobStatus::Pending);
+        assert_eq!(jobs[1].status, JobStatus::Skipped);
+        assert!(jobs[1].last_error.as_deref().unwrap().contains("show.mp4"));
+        assert_eq!(jobs[2].status, JobStatus::Skipped);
+        assert_eq!(
+            jobs[2].last_error.as_deref(),
+            Some("Output path is the source file itself")
+        );
+
+        let suffix = JobPolicy {
+            collision: CollisionPolicy::Suffix,
+            ..JobPolicy::default()
+        };
+        let jobs = build_job_queue_with_policy(
+            files,
+            "vp9-good",
+            false,
+            None,
+            Some("{basename}"),
+            Some("webm"),
+            &suffix,
+        );
+        assert!(jobs.iter().all(|j| j.status == JobStatus::Pending));
+        assert_eq!(jobs[1].output_path, dir.join("show (2).webm"));
+        assert_eq!(jobs[2].output_path, dir.join("clip (2).webm"));
+
+        // Resuming doesn't retry a job that failed on a taken output path
+        let mut failed = jobs[1].clone();
+        failed.output_path = dir.join("show.webm");
+        failed.status = JobStatus::Failed;
+        EncState::new(
+            vec![jobs[0].clone(), failed],
+            "vp9-good".to_string(),
+            dir.to_path_buf(),
+        )
+        .save(dir)
+        .unwrap();
+        let resumed = EncState::load(dir, CollisionPolicy::Error).unwrap();
+        assert_eq!(resumed.jobs[0].status, JobStatus::Pending);
+        assert_eq!(resumed.jobs[1].status, JobStatus::Failed);
+        // ...and resolves it with the configured policy
+        let resumed = EncState::load(dir, CollisionPolicy::Skip).unwrap();
+        assert_eq!(resumed.jobs[1].status, JobStatus::Skipped);
+        let resumed = EncState::load(dir, CollisionPolicy::Suffix).unwrap();
+        assert_eq!(resumed.jobs[1].status, JobStatus::Pending);
+        assert_eq!(resumed.jobs[1].output_path, dir.join("show (2).webm"));
+    }
+
+    #[test]
+    fn test_mirror_tree_keeps_folders_under_output_dir() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (root, out) = (temp_dir.path().join("show"), temp_dir.path().join("out"));
+        let files: Vec<PathBuf> = ["Season 1/E01.mkv", "Season 2/E01.mkv", "Extras/a/b/E01.mkv"]
+            .iter()
+            .map(|rel| root.join(rel))
+            .collect();
+        let build = |policy: &JobPolicy| {
+            build_job_queue_with_policy(
+                files.clone(),
+                "vp9-good",
+                false,
+                out.to_str(),
+                Some("{basename}-{profile}"),
+                Some("webm"),
+                policy,
+            )
+        };
+
+        let mirrored = JobPolicy {
+            mirror_tree: true,
+            ..JobPolicy::default()
+        }
+        .with_scan_roots(vec![temp_dir.path().to_path_buf(), root.clone()]);
+        let jobs = build(&mirrored);
+        assert!(jobs.iter().all(|j| j.status == JobStatus::Pending));
+        assert_eq!(jobs[0].output_path, out.join("Season 1/E01-vp9good.webm"));
+        assert_eq!(jobs[1].output_path, out.join("Season 2/E01-vp9good.webm"));
+        assert_eq!(jobs[2].output_path, out.join("Extras/a/b/E01-vp9good.webm"));
+
+        // Flattened, the second season collides with the first
+        let jobs = build(&JobPolicy::default());
+        assert_eq!(jobs[0].output_path, out.join("E01-vp9good.webm"));
+        assert_eq!(jobs[1].status, JobStatus::Skipped);
+    }
+
+    #[test]
+    fn test_filename_pattern_tokens() {
+        let input = Path::new("/media/tv/Show/Season 1/Show.Name.S01E02.1080p.mkv");
+        let policy = JobPolicy::default().with_scan_roots(vec![PathBuf::from("/media/tv")]);
+        let info = crate::engine::probe::MediaInfo::from_ffprobe_json(&serde_json::json!({
+            "format": {},
+            "streams": [{
+                "codec_type": "video",
+                "width": 3840,
+                "height": 2160,
+                "color_transfer": "smpte2084"
+            }]
+        }));
+        let render = |pattern: &str, info| {
+            assert!(validate_pattern(pattern).is_ok(), "{}", pattern);
+            derive_output_path_with(
+                input,
+                "av1-svt",
+                Some("/out"),
+                Some(pattern),
+                Some("mkv"),
+                &PatternContext {
+                    info,
+                    relpath: policy.relative_dir(input),
+                },
+            )
+        };
+
+        assert_eq!(
+            render("{relpath}/{title} - S{season}E{episode} [{codec}]", None),
+            Path::new("/out/Show/Season 1/Show Name - S01E02 [av1].mkv")
+        );
+        assert_eq!(
+            render("{parent}/{basename}.{encoder}", None),
+            Path::new("/out/Season 1/Show.Name.S01E02.1080p.libsvtav1.mkv")
+        );
+        assert_eq!(
+            render("{title} {width}x{height} {hdr} q{quality}", Some(&info)),
+            PathBuf::from(format!(
+                "/out/Show Name 3840x2160 HDR q{}.mkv",
+                Profile::get("av1-svt").crf
+            ))
+        );
+        let date = render("{date}", None);
+        assert_eq!(date.file_stem().unwrap().len(), "2025-01-31".len());
+        assert!(pattern_warning("{basename} {date}").is_some());
+        assert!(pattern_warning("{basename}").is_none());
+
+        // Tokens without a value drop out along with their brackets and separators
+        assert_eq!(
+            render("{title} ({year}) [{hdr}] - {vmaf}", None),
+            Path::new("/out/Show Name.mkv")
+        );
+
+        assert_eq!(
+            render("[{hdr}] {title} - {year} - {codec}", None),
+            Path::new("/out/Show Name - av1.mkv")
+        );
+        assert_eq!(
+            render("{basename} ({year} {hdr})", Some(&info)),
+            Path::new("/out/Show.Name.S01E02.1080p (HDR).mkv")
+        );
+
+        // Only the pattern's own text next to a missing value is cleaned up
+        let odd = Path::new("/media/-Show..Name__Cut--.mkv");
+        let render_odd = |pattern| {
+            derive_output_path_with(
+                odd,
+                "av1-svt",
+                None,
+                Some(pattern),
+                Some("mkv"),
+                &PatternContext::default(),
+            )
+        };
+        assert_eq!(
+            render_odd("{basename} [{hdr}]"),
+            Path::new("/media/-Show..Name__Cut--.mkv")
+        );
+        assert_eq!(
+            render_odd("{hdr}.{basename}..x"),
+            Path::new("/media/-Show..Name__Cut--..x.mkv")
+        );
+
+        let err = validate_pattern("{basename}-{resolution}").unwrap_err();
+        assert!(err.to_string().contains("unknown token {resolution}"));
+        assert!(validate_pattern("{basename").is_err());
+        assert!(validate_pattern("basename}").is_err());
+    }
+
+    #[test]
+    fn test_media_name_parse() {
+        let parse = |stem| {
+            let name = MediaName::parse(stem);
+            (name.title, name.year, name.season, name.episode)
+        };
+        assert_eq!(
+            parse("Show.Name.S01E02.1080p.WEB"),
+            ("Show Name".into(), None, Some(1), Some(2))
+        );
+        assert_eq!(
+            parse("Show Name (2019) - 2x10 - Pilot"),
+            ("Show Name".into(), Some(2019), Some(2), Some(10))
+        );
+        assert_eq!(
+            parse("Blade Runner 2049 (2017)"),
+            ("Blade Runner 2049".into(), Some(2017), None, None)
+        );
+        assert_eq!(
+            parse("1917.2019.2160p.x265"),
+            ("1917".into(), Some(2019), None, None)
+        );
+        assert_eq!(parse("home_video"), ("home video".into(), None, None, None));
+    }
+
+    #[test]
+    fn test_enc_state_save_is_atomic_and_recovers_from_backup() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let save = |names: &[&str]| {
+            let jobs = names
+                .iter()
+                .map(|name| VideoJob::new(root.join(name), root.join("out.webm"), "vp9".into()))
+                .collect();
+            EncState::new(jobs, "vp9-good".into(), root.into()).save(root)
+        };
+
+        save(&["a.mp4"]).unwrap();
+        save(&["a.mp4", "b.mp4"]).unwrap();
+        assert!(!root.join(".enc_state.tmp").exists());
+        assert_eq!(EncState::load_read_only(root).unwrap().jobs.len(), 2);
+
+        // A write cut short falls back to the previous state
+        let full = std::fs::read_to_string(root.join(".enc_state")).unwrap();
+        std::fs::write(root.join(".enc_state"), &full[..full.len() / 2]).unwrap();
+        assert_eq!(EncState::load_read_only(root).unwrap().jobs.len(), 1);
+
+        // Without a usable backup the parse error is reported
+        std::fs::write(root.join(".enc_state.bak"), "{").unwrap();
+        let err = EncState::load_read_only(root).unwrap_err();
+        assert!(format!("{:#}", err).contains("Failed to parse .enc_state"));
+
+        // A deleted queue stays deleted
+        std::fs::remove_file(root.join(".enc_state")).unwrap();
+        assert!(EncState::load_read_only(root).is_err());
+    }
+
+    #[test]
+    fn test_queue_lock_excludes_second_owner() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+
+        assert_eq!(QueueLock::holder(root), None);
+        let lock = QueueLock::acquire(root).unwrap();
+        assert_eq!(QueueLock::holder(root), Some(std::process::id()));
+        let err = QueueLock::acquire(root).unwrap_err();
+        assert!(err.to_string().contains("another ffdash"));
+
+        // Held even while the owner's PID isn't written yet
+        std::fs::write(root.join(".ffdash.lock"), "").unwrap();
+        assert!(QueueLock::acquire(root).is_err());
+        drop(lock);
+        assert!(!root.join(".ffdash.lock").exists());
+
+        // A lock file left by a process that is gone is taken over
+        std::fs::write(root.join(".ffdash.lock"), format!("{}\n", u32::MAX - 1)).unwrap();
+        assert_eq!(QueueLock::holder(root), None);
+        let _lock = QueueLock::acquire(root).unwrap();
+        assert_eq!(QueueLock::holder(root), Some(std::process::id()));
+    }
+
+    #[test]
+    fn test_partial_outputs_are_published_and_never_scanned() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let output = dir.join("out/show.webm");
+
+        let partial = partial_path(&output, None);
+        assert_eq!(partial, dir.join("out/show.partial.webm"));
+        assert!(is_partial(&partial));
+        assert!(!is_partial(&output));
+
+        // A staging dir keeps the partial name; finishing creates the output dir
+        let staged = partial_path(&output, Some(&dir.join("staging")));
+        assert_eq!(staged, dir.join("staging/show.partial.webm"));
+        std::fs::create_dir(dir.join("staging")).unwrap();
+        std::fs::write(&staged, b"encoded").unwrap();
+        finish_partial(&staged, &output).unwrap();
+        assert!(!staged.exists());
+        assert_eq!(std::fs::read(&output).unwrap(), b"encoded");
+
+        // A leftover partial is neither queued nor mistaken for the finished output
+        let source = dir.join("staging/clip.mkv");
+        std::fs::write(&source, b"x").unwrap();
+        std::fs::write(dir.join("staging/clip.partial.webm"), b"half").unwrap();
+        assert_eq!(scan(&dir.join("staging")).unwrap(), vec![source.clone()]);
+        let job = build_job_from_path(source, "vp9-good", false, None, None, Some("webm"));
+        assert_eq!(job.status, JobStatus::Pending);
+    }
+
+    #[test]
+    fn test_staged_partials_are_per_job() {
+        let staging = Path::new("/mnt/scratch");
+        let job = |season: &str| {
+            VideoJob::new(
+                PathBuf::from(format!("/tv/{}/E01.mkv", season)),
+                PathBuf::from(format!("/out/{}/E01.webm", season)),
+                "vp9-good".to_string(),
+            )
+        };
+        let (first, second) = (job("Season 1"), job("Season 2"));
+
+        let (a, b) = (
+            job_partial_path(&first, Some(staging)),
+            job_partial_path(&second, Some(staging)),
+        );
+        assert_ne!(a, b);
+        for (job, partial) in [(&first, a), (&second, b)] {
+            let job_dir = staging.join(job.id.to_string());
+            assert_eq!(partial, job_dir.join("E01.partial.webm"));
+        }
+
+        // Next to the output, the output's own folder keeps them apart
+        assert_eq!(
+            job_partial_path(&first, None),
+            PathBuf::from("/out/Season 1/E01.partial.webm")
+        );
+    }
+
+    #[test]
+    fn test_segment_plan_splits_at_keyframes_and_resumes() {
+        let settings = SegmentSettings {
+            enabled: true,
+            length_secs: 300,
+        };
+        assert!(!SegmentSettings::default().applies_to(Some(3600.0)));
+        assert!(!settings.applies_to(Some(599.0)));
+        assert!(settings.applies_to(Some(600.0)));
+        assert!(!settings.applies_to(None));
+
+        // Packet times are made relative to the file's start_time (last line)
+        let csv = "0.040000,K_\n0.080000,__\n150.040000,K_\n299.000000,K_\n\
+                   301.040000,K_\n450.000000,K_\n620.040000,K_\n890.000000,K_\n0.040000\n";
+        let keyframes = segments::parse_keyframes(csv);
+        assert_eq!(keyframes[..4], [0, 150_000_000, 298_960_000, 301_000_000]);
+
+        // Segments start on the first keyframe 300s after the last start, but not in the
+        // last 150s of the file
+        let length_us = 300_000_000;
+        let plan = |duration_us, audio| {
+            SegmentPlan::new(String::new(), &keyframes, length_us, duration_us, audio)
+        };
+        let full = plan(1_000_000_000, true);
+        let bounds: Vec<_> = full
+            .segments
+            .iter()
+            .map(|s| (s.start_us, s.end_us))
+            .collect();
+        assert_eq!(
+            bounds,
+            vec![
+                (0, Some(301_000_000)),
+                (301_000_000, Some(620_000_000)),
+                (620_000_000, None)
+            ]
+        );
+        assert_eq!(full.audio_done, Some(false));
+
+        // Reading a window after each wanted start finds the same starts as listing every
+        // keyframe; like ffprobe, a read begins at the keyframe before the seek point
+        let read = |from_us: u64, window_us: u64| {
+            let seek = keyframes.iter().rev().find(|&&k| k <= from_us).copied();
+            Ok(keyframes
+                .iter()
+                .copied()
+                .filter(|&k| Some(k) >= seek && k <= from_us + window_us)
+                .collect())
+        };
+        let windowed = segments::segment_keyframes(length_us, 1_000_000_000, read).unwrap();
+        assert_eq!(windowed, [301_000_000, 620_000_000]);
+        let windowed = SegmentPlan::new(String::new(), &windowed, length_us, 1_000_000_000, true);
+        assert_eq!(windowed, full);
+
+        let short = plan(700_000_000, false);
+        assert_eq!(short.segments.len(), 2);
+        assert_eq!(short.audio_done, None);
+
+        // An interrupted job keeps its finished segments and their share of the progress
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut job = VideoJob::new(
+            PathBuf::from("/videos/long.mkv"),
+            PathBuf::from("/videos/long.webm"),
+            "vp9-good".to_string(),
+        );
+        let mut plan = full;
+        plan.segments[0].done = true;
+        job.status = JobStatus::Running;
+        job.duration_s = Some(1000.0);
+        job.progress_pct = 42.0;
+        job.segments = Some(plan.clone());
+        let root = temp_dir.path();
+        let state = EncState::new(vec![job], "vp9-good".to_string(), root.to_path_buf());
+        state.save(root).unwrap();
+
+        let loaded = EncState::load(root, CollisionPolicy::default()).unwrap();
+        assert_eq!(loaded.jobs[0].status, JobStatus::Pending);
+        assert_eq!(loaded.jobs[0].segments, Some(plan));
+        assert!((loaded.jobs[0].progress_pct - 30.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_segment_cmd_seeks_and_drops_audio() {
+        let output = Path::new("/videos/long.partial.mkv");
+        let mut full = std::process::Command::new("ffmpeg");
+        full.env("LIBVA_DRIVER_NAME", "iHD")
+            .args(["-hwaccel", "vaapi", "-i", "/videos/long.mkv"])
+            .args(["-c:v", "av1_vaapi", "-map", "0:v:0?", "-map", "0:a:0?"])
+            .args(["-c:a:0", "libopus"])
+            .arg(output);
+        let length_us = 300_000_000;
+        let segment = Segment {
+            start_us: 301_000_000,
+            end_us: Some(620_000_500),
+            done: false,
+        };
+
+        let file = Path::new("/tmp/job/segment_0001.mkv");
+        let cmd = segments::segment_cmd(&full, output, &segment, file);
+        assert_eq!(
+            format_command(&cmd),
+            "LIBVA_DRIVER_NAME=iHD ffmpeg -hwaccel vaapi -ss 301.000000 -t 319.000500 \
+             -i /videos/long.mkv -c:v av1_vaapi -map '0:v:0?' -map '0:a:0?' -c:a:0 libopus \
+             -an -sn -dn -y /tmp/job/segment_0001.mkv"
+        );
+        assert_eq!(cmd.get_envs().count(), 1);
+
+        // Pass 1 keeps writing to the null muxer; the last segment runs to the end
+        let mut pass1 = std::process::Command::new("ffmpeg");
+        pass1.args(["-i", "/videos/long.mkv", "-pass", "1"]);
+        pass1.args(["-f", "null", "/dev/null"]);
+        let last = Segment {
+            end_us: None,
+            ..segment
+        };
+        let cmd = segments::segment_cmd(&pass1, output, &last, file);
+        assert_eq!(
+            format_command(&cmd),
+            "ffmpeg -ss 301.000000 -i /videos/long.mkv -pass 1 -f null -an -sn -dn -y /dev/null"
+        );
+
+        // Joined segments keep their planned lengths
+        let plan = SegmentPlan::new(String::new(), &[301_000_000], length_us, 900_000_000, true);
+        assert_eq!(
+            segments::concat_list(&plan, "mkv"),
+            "file 'segment_0000.mkv'\nduration 301.000000\nfile 'segment_0001.mkv'\n"
+        );
+    }
+
+    #[test]
+    fn test_enc_state_restore_calibrations() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut calibrated = VideoJob::new(
+            temp_dir.path().join("a.mp4"),
+            temp_dir.path().join("a.webm"),
+            "vp9-good".to_string(),
+        );
+        calibrated.vmaf_target = Some(93.0);
+        calibrated.vmaf_result = Some(93.4);
+        calibrated.calibrated_quality = Some(34);
+        calibrated.calibrated_hw = Some(false);
+        let mut other_profile = calibrated.clone();
+        other_profile.input_path = temp_dir.path().join("b.mp4");
+        other_profile.profile = "av1-svt".to_string();
+
+        EncState::new(
+            vec![calibrated, other_profile],
+            "vp9-good".to_string(),
+            temp_dir.path().to_path_buf(),
+        )
+        .save(temp_dir.path())
+        .unwrap();
+
+        let rescanned = ["a.mp4", "b.mp4"]
+            .iter()
+            .map(|name| {
+                VideoJob::new(
+                    temp_dir.path().join(name),
+                    temp_dir.path().join("out.webm"),
+                    "vp9-good".to_string(),
+                )
+            })
+            .collect();
+        let mut state = EncState::new(
+            rescanned,
+            "vp9-good".to_string(),
+            temp_dir.path().to_path_buf(),
+        );
+
+        assert_eq!(state.restore_calibrations(temp_dir.path()), 1);
+        assert_eq!(state.jobs[0].calibrated_quality, Some(34));
+        assert_eq!(state.jobs[0].calibrated_hw, Some(false));
+        assert_eq!(state.jobs[0].vmaf_target, Some(93.0));
+        assert_eq!(state.jobs[1].calibrated_quality, None, "Profile mismatch must not restore");
+    }
+
+    #[test]
+    fn test_enc_state_restore_segments() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let job = |name: &str| {
+            VideoJob::new(
+                root.join(name),
+                root.join("out.webm"),
+                "vp9-good".to_string(),
+            )
+        };
+        let plan = SegmentPlan::new(
+            String::new(),
+            &[301_000_000],
+            300_000_000,
+            700_000_000,
+            false,
+        );
+        let mut resumed = job("a.mp4");
+        resumed.segments = Some(plan.clone());
+        let mut gone = job("b.mp4");
+        gone.segments = Some(plan.clone());
+        for old in [&resumed, &gone] {
+            let dir = crate::engine::vmaf::create_job_temp_dir(old).unwrap();
+            std::fs::write(dir.join("segment_0000.webm"), b"done").unwrap();
+        }
+        EncState::new(
+            vec![resumed.clone(), gone.clone()],
+            "vp9-good".to_string(),
+            root.to_path_buf(),
+        )
+        .save(root)
+        .unwrap();
+
+        // A rebuilt queue takes over a.mp4's segments and folder; b.mp4 is no longer queued
+        let mut state = EncState::new(
+            vec![job("a.mp4"), job("c.mp4")],
+            "vp9-good".to_string(),
+            root.to_path_buf(),
+        );
+        assert_eq!(state.restore_segments(root), 1);
+        assert_eq!(state.jobs[0].id, resumed.id);
+        assert_eq!(state.jobs[0].segments, Some(plan));
+        assert!(state.jobs[1].segments.is_none());
+        assert!(crate::engine::vmaf::job_temp_dir(&resumed).exists());
+        assert!(!crate::engine::vmaf::job_temp_dir(&gone).exists());
+    }
+
     #[test]
     fn test_build_job_queue_with_overwrite() {
         use std::fs;
@@ -325,7 +1570,7 @@ mod tests {
         );
 
         // Now simulate what would happen if we incorrectly loaded the old state
-        let mut loaded_state = EncState::load(dir_path).unwrap();
+        let mut loaded_state = EncState::load(dir_path, CollisionPolicy::default()).unwrap();
         loaded_state.load_queue_status(dir_path).unwrap();
 
         // The loaded state would have 3 Done and 1 Pending (wrong for overwrite=true!)
@@ -446,7 +1691,10 @@ mod tests {
             output_path: PathBuf::from("/tmp/test.webm"),
             profile: "test".to_string(),
             status: JobStatus::Pending,
+            profile_rule: None,
             overwrite: true,
+            priority: 0,
+            origin: None,
             progress_pct: 0.0,
             duration_s: None,
             out_time_s: 0.0,
@@ -463,9 +1711,12 @@ mod tests {
             vmaf_target: None,
             vmaf_result: None,
             calibrated_quality: None,
+            calibrated_hw: None,
             vmaf_partial_scores: Vec::new(),
+            segments: None,
             calibrating_total_steps: None,
             calibrating_completed_steps: 0,
+            report: None,
         };
 
         let cmd = build_vaapi_cmd(&job, &profile, &hw_config);
@@ -537,7 +1788,10 @@ mod tests {
             output_path: PathBuf::from("/tmp/test.webm"),
             profile: "test".to_string(),
             status: JobStatus::Pending,
+            profile_rule: None,
             overwrite: true,
+            priority: 0,
+            origin: None,
             progress_pct: 0.0,
             duration_s: None,
             out_time_s: 0.0,
@@ -554,9 +1808,12 @@ mod tests {
             vmaf_target: None,
             vmaf_result: None,
             calibrated_quality: None,
+            calibrated_hw: None,
             vmaf_partial_scores: Vec::new(),
+            segments: None,
             calibrating_total_steps: None,
             calibrating_completed_steps: 0,
+            report: None,
         };
 
         let cmd = build_vaapi_cmd(&job, &profile, &hw_config);
@@ -615,7 +1872,10 @@ mod tests {
             output_path: PathBuf::from("/tmp/test.webm"),
             profile: "test".to_string(),
             status: JobStatus::Pending,
+            profile_rule: None,
             overwrite: true,
+            priority: 0,
+            origin: None,
             progress_pct: 0.0,
             duration_s: None,
             out_time_s: 0.0,
@@ -632,9 +1892,12 @@ mod tests {
             vmaf_target: None,
             vmaf_result: None,
             calibrated_quality: None,
+            calibrated_hw: None,
             vmaf_partial_scores: Vec::new(),
+            segments: None,
             calibrating_total_steps: None,
             calibrating_completed_steps: 0,
+            report: None,
         };
 
         let cmd = build_vaapi_cmd(&job, &profile, &hw_config);
@@ -694,7 +1957,10 @@ mod tests {
             output_path: PathBuf::from("/tmp/test.mkv"),
             profile: "test".to_string(),
             status: JobStatus::Pending,
+            profile_rule: None,
             overwrite: true,
+            priority: 0,
+            origin: None,
             progress_pct: 0.0,
             duration_s: None,
             out_time_s: 0.0,
@@ -711,9 +1977,12 @@ mod tests {
             vmaf_target: None,
             vmaf_result: None,
             calibrated_quality: None,
+            calibrated_hw: None,
             vmaf_partial_scores: Vec::new(),
+            segments: None,
             calibrating_total_steps: None,
             calibrating_completed_steps: 0,
+            report: None,
         };
 
         let cmd = super::ffmpeg_cmd::build_av1_vaapi_cmd(&job, &profile);
@@ -782,7 +2051,10 @@ mod tests {
             output_path: PathBuf::from("/tmp/test.mp4"),
             profile: "test".to_string(),
             status: JobStatus::Pending,
+            profile_rule: None,
             overwrite: true,
+            priority: 0,
+            origin: None,
             progress_pct: 0.0,
             duration_s: None,
             out_time_s: 0.0,
@@ -799,9 +2071,12 @@ mod tests {
             vmaf_target: None,
             vmaf_result: None,
             calibrated_quality: None,
+            calibrated_hw: None,
             vmaf_partial_scores: Vec::new(),
+            segments: None,
             calibrating_total_steps: None,
             calibrating_completed_steps: 0,
+            report: None,
         };
 
         let cmd = super::ffmpeg_cmd::build_av1_vaapi_cmd(&job, &profile);
@@ -879,4 +2154,40 @@ mod tests {
         );
         assert!(cmd2.contains("-c:a"), "Pass 2 should include audio");
     }
+
+    #[test]
+    fn test_format_command_shell_quotes_unsafe_args() {
+        let mut cmd = std::process::Command::new("ffmpeg");
+        cmd.env("LIBVA_DRIVER_NAME", "iHD")
+            .arg("-i")
+            .arg("/videos/My Show's Pilot.mkv")
+            .arg("-vf")
+            .arg("scale=-2:1080");
+
+        assert_eq!(
+            format_command(&cmd),
+            "LIBVA_DRIVER_NAME=iHD ffmpeg -i '/videos/My Show'\\''s Pilot.mkv' -vf scale=-2:1080"
+        );
+    }
+
+    #[test]
+    fn test_format_ffmpeg_cmd_with_profile_joins_two_pass_commands() {
+        let mut profile = Profile::get("vp9-good");
+        profile.use_hardware_encoding = false;
+        profile.two_pass = true;
+        profile.video_target_bitrate = 2000;
+
+        let job = VideoJob::new(
+            PathBuf::from("/tmp/in put.mp4"),
+            PathBuf::from("/tmp/output.webm"),
+            "vp9-good".to_string(),
+        );
+
+        let formatted = format_ffmpeg_cmd_with_profile(&job, None, Some(&profile));
+        let lines: Vec<&str> = formatted.split("\n&& \\\n").collect();
+        assert_eq!(lines.len(), 2, "Two-pass should render both commands");
+        assert!(lines[0].contains("-pass 1"));
+        assert!(lines[1].contains("-pass 2"));
+        assert!(lines[1].contains("'/tmp/in put.mp4'"));
+    }
 }
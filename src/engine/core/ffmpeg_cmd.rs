@@ -1,17 +1,21 @@
 use super::ffmpeg_info::probe_duration;
 use super::log::write_debug_log;
+use super::output::{finish_partial, job_partial_path};
 use super::profile::{Codec, HwEncodingConfig, Profile};
+use super::segments::encode_in_segments;
 use super::types::{JobStatus, ProgressParser, VideoJob};
+use crate::engine::history::SourceSummary;
 use crate::engine::worker::PidRegistry;
-use crate::engine::{hardware, probe};
+use crate::engine::{hardware, probe, probe_cache};
 use anyhow::{Context, Result};
 use std::fs;
 use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
 use std::process::{Command, ExitStatus, Stdio};
+use std::time::Instant;
 
 /// Check if FFmpeg was cancelled by user signal (SIGTERM, SIGINT, SIGQUIT)
-/// Returns true if we should preserve partial output files
+/// Returns true for a user cancel, which must not trigger the QSV→VAAPI retry
 ///
 /// FFmpeg catches signals and exits gracefully, printing "Exiting normally, received signal X"
 /// So we check both the process signal status AND the stderr for this message.
@@ -39,7 +43,7 @@ fn was_user_cancelled(_status: &ExitStatus, stderr: &str) -> bool {
     stderr.contains("received signal")
 }
 
-fn container_from_output(job: &VideoJob, profile: &Profile) -> String {
+pub(super) fn container_from_output(job: &VideoJob, profile: &Profile) -> String {
     job.output_path
         .extension()
         .and_then(|s| s.to_str())
@@ -150,6 +154,21 @@ fn apply_audio_settings(cmd: &mut Command, profile: &Profile, container: &str) {
     }
 }
 
+/// Audio tracks only, encoded the way the full encode would encode them (segment mode
+/// encodes audio in one piece beside the video segments)
+pub(super) fn build_audio_only_cmd(job: &VideoJob, profile: &Profile, output: &Path) -> Command {
+    let mut cmd = Command::new("ffmpeg");
+    cmd.arg("-i").arg(&job.input_path);
+    cmd.arg("-progress").arg("-").arg("-nostats");
+    cmd.arg("-vn").arg("-sn").arg("-dn");
+
+    let container = container_from_output(job, profile);
+    apply_audio_settings(&mut cmd, profile, &container);
+
+    cmd.arg("-y").arg(output);
+    cmd
+}
+
 fn apply_color_metadata(cmd: &mut Command, profile: &Profile) {
     // Warn about 8-bit + HDR10 combination (will cause severe banding)
     if profile.pix_fmt == "yuv420p"
@@ -218,7 +237,8 @@ fn should_use_two_pass_software_vp9(
         && profile.video_target_bitrate > 0
 }
 
-fn hw_config_from_profile(profile: &Profile) -> HwEncodingConfig {
+/// Hardware settings stored in a profile, for callers without a UI-provided `HwEncodingConfig`
+pub fn hw_config_from_profile(profile: &Profile) -> HwEncodingConfig {
     // Read quality from codec config (post-sync, hw_global_quality is synced from codec)
     // But prefer reading from codec directly for clarity
     let global_quality = match &profile.codec {
@@ -306,6 +326,26 @@ fn apply_calibrated_quality(profile: &Profile, quality: u32) -> Profile {
     calibrated
 }
 
+/// Reuse a calibration already stored on the job instead of calibrating again
+///
+/// Set by `ffdash calibrate --save` or an earlier run and restored from `.enc_state`.
+/// Only trusted when it was made for the same VMAF target as the current profile, and
+/// for the quality it sets: a CRF means nothing as a hardware quality and vice versa.
+fn stored_calibration(job: &VideoJob, profile: &Profile) -> Option<Profile> {
+    let quality = job.calibrated_quality?;
+    if job.vmaf_target != Some(profile.vmaf_target)
+        || job.calibrated_hw != Some(profile.use_hardware_encoding)
+    {
+        return None;
+    }
+
+    let _ = write_debug_log(&format!(
+        "[Auto-VAMF] Reusing stored calibration: quality={} (VMAF: {:?})",
+        quality, job.vmaf_result
+    ));
+    Some(apply_calibrated_quality(profile, quality))
+}
+
 /// Build VAAPI hardware encoding command (for Intel Arc and other VAAPI-capable GPUs)
 pub fn build_vaapi_cmd(job: &VideoJob, profile: &Profile, hw: &HwEncodingConfig) -> Command {
     // Validate configuration (log warning if invalid)
@@ -1793,30 +1833,51 @@ pub fn build_ffmpeg_cmd(job: &VideoJob, hw_config: Option<&HwEncodingConfig>) ->
 
 /// Format ffmpeg command as a shell-safe string for display
 pub fn format_ffmpeg_cmd(job: &VideoJob, hw_config: Option<&HwEncodingConfig>) -> String {
-    let cmds = build_ffmpeg_cmds_with_profile(job, hw_config, None);
-    cmds.into_iter()
-        .map(|cmd| {
+    format_ffmpeg_cmd_with_profile(job, hw_config, None)
+}
+
+/// Format the full command sequence (both passes for two-pass) for a specific profile
+pub fn format_ffmpeg_cmd_with_profile(
+    job: &VideoJob,
+    hw_config: Option<&HwEncodingConfig>,
+    profile_override: Option<&Profile>,
+) -> String {
+    build_ffmpeg_cmds_with_profile(job, hw_config, profile_override)
+        .iter()
+        .map(format_command)
+        .collect::<Vec<_>>()
+        .join("\n&& \\\n")
+}
+
+/// Render a single command as a line that can be pasted into a POSIX shell,
+/// including any environment overrides (e.g. VAAPI driver selection)
+pub fn format_command(cmd: &Command) -> String {
+    let envs = cmd.get_envs().filter_map(|(key, value)| {
+        value.map(|v| {
             format!(
-                "{} {}",
-                cmd.get_program().to_string_lossy(),
-                cmd.get_args()
-                    .map(|arg| {
-                        let s = arg.to_string_lossy();
-                        if s.contains(' ') {
-                            format!("\"{}\"", s)
-                        } else {
-                            s.to_string()
-                        }
-                    })
-                    .collect::<Vec<_>>()
-                    .join(" ")
+                "{}={}",
+                key.to_string_lossy(),
+                shell_quote(&v.to_string_lossy())
             )
         })
-        .collect::<Vec<_>>()
-        .join("\n&& \\\n")
+    });
+    let program = std::iter::once(shell_quote(&cmd.get_program().to_string_lossy()));
+    let args = cmd.get_args().map(|arg| shell_quote(&arg.to_string_lossy()));
+
+    envs.chain(program).chain(args).collect::<Vec<_>>().join(" ")
+}
+
+/// Single-quote an argument for POSIX shells when it contains anything unsafe
+fn shell_quote(arg: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=,+@%".contains(c);
+    if !arg.is_empty() && arg.chars().all(is_safe) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
 }
 
-fn run_ffmpeg_once(
+pub(super) fn run_ffmpeg_once(
     job: &mut VideoJob,
     mut cmd: Command,
     silent: bool,
@@ -1913,12 +1974,126 @@ pub fn encode_job(job: &mut VideoJob) -> Result<()> {
 /// Set silent=true to suppress console output (for TUI usage)
 /// If profile_override is provided, it will be used instead of loading profile by name
 /// If pid_registry is provided, FFmpeg process PIDs will be tracked for graceful shutdown
+///
+/// FFmpeg writes to `name.partial.ext` (in `[output] staging_dir` when set), which is
+/// checked and renamed to `job.output_path` only once the encode succeeds, so media
+/// servers never see a half-written file. Failed and cancelled encodes remove it.
 pub fn encode_job_with_callback_and_profile<F>(
     job: &mut VideoJob,
     silent: bool,
     hw_config: Option<&HwEncodingConfig>,
     profile_override: Option<&Profile>,
     pid_registry: Option<PidRegistry>,
+    callback: F,
+) -> Result<()>
+where
+    F: FnMut(&VideoJob, &ProgressParser),
+{
+    let staging_dir = crate::config::Config::load()
+        .ok()
+        .and_then(|c| c.output.staging_dir);
+    let output_path = job.output_path.clone();
+    let partial = job_partial_path(job, staging_dir.as_deref());
+    let staged = staging_dir.is_some();
+    let started = Instant::now();
+    job.report = Some(Box::default());
+
+    // One left by a killed run would make ffmpeg refuse to start (no -y without overwrite)
+    remove_partial(&partial, "stale");
+    job.output_path = partial.clone();
+    let result = encode_to_partial(
+        job,
+        &output_path,
+        silent,
+        hw_config,
+        profile_override,
+        pid_registry,
+        callback,
+    );
+    job.output_path = output_path;
+    let encoded = Instant::now();
+    let timings = &mut job.report_mut().timings;
+    timings.total_secs = (encoded - started).as_secs_f64();
+    timings.encode_secs = timings.total_secs - timings.calibration_secs.unwrap_or(0.0);
+
+    if result.is_err() {
+        remove_partial(&partial, "failed");
+        remove_job_staging_dir(&partial, staged);
+        return result;
+    }
+    let published = publish_partial(&partial, job);
+    let timings = &mut job.report_mut().timings;
+    timings.finish_secs = encoded.elapsed().as_secs_f64();
+    timings.total_secs = started.elapsed().as_secs_f64();
+    if let Err(e) = published {
+        remove_partial(&partial, "unverified");
+        remove_job_staging_dir(&partial, staged);
+        job.status = JobStatus::Failed;
+        job.last_error = Some(format!("{:#}", e));
+        return Err(e);
+    }
+    remove_job_staging_dir(&partial, staged);
+    if !silent {
+        println!("✓ Completed: {}", job.output_path.display());
+    }
+    write_debug_log(&format!("✓ Success: {}\n", job.output_path.display())).ok();
+    Ok(())
+}
+
+/// Check a finished partial output and move it to the job's output path
+fn publish_partial(partial: &Path, job: &VideoJob) -> Result<()> {
+    let size = fs::metadata(partial).map(|m| m.len()).unwrap_or(0);
+    if size == 0 {
+        anyhow::bail!("Output file is empty");
+    }
+    probe_duration(partial).context("Output file is unreadable")?;
+    // Something else wrote the output while we were encoding
+    if !job.overwrite && job.output_path.exists() {
+        anyhow::bail!(
+            "Output {} appeared during the encode; left it alone",
+            job.output_path.display()
+        );
+    }
+    finish_partial(partial, &job.output_path).context("Failed to move the finished output")
+}
+
+fn remove_partial(partial: &Path, why: &str) {
+    if partial.exists() {
+        match fs::remove_file(partial) {
+            Ok(()) => write_debug_log(&format!(
+                "[cleanup] Removed {} partial output: {}\n",
+                why,
+                partial.display()
+            )),
+            Err(e) => write_debug_log(&format!(
+                "[cleanup] Failed to remove partial output {}: {}\n",
+                partial.display(),
+                e
+            )),
+        }
+        .ok();
+    }
+}
+
+/// Remove the job's folder in the staging dir once its partial is gone
+fn remove_job_staging_dir(partial: &Path, staged: bool) {
+    if staged {
+        if let Some(dir) = partial.parent() {
+            // Fails while the partial is still there, which is what we want
+            let _ = fs::remove_dir(dir);
+        }
+    }
+}
+
+/// The encode itself, writing to `job.output_path` (the partial); `output_path` is the
+/// final path, for messages
+fn encode_to_partial<F>(
+    job: &mut VideoJob,
+    output_path: &Path,
+    silent: bool,
+    hw_config: Option<&HwEncodingConfig>,
+    profile_override: Option<&Profile>,
+    pid_registry: Option<PidRegistry>,
     mut callback: F,
 ) -> Result<()>
 where
@@ -1934,18 +2109,20 @@ where
         }
     }
 
-    let disable_vaapi_fallback = crate::config::Config::load()
-        .map(|c| c.defaults.disable_vaapi_fallback)
-        .unwrap_or(false);
+    let config = crate::config::Config::load().unwrap_or_default();
+    let disable_vaapi_fallback = config.defaults.disable_vaapi_fallback;
 
     // Probe duration first
     job.duration_s = probe_duration(&job.input_path).ok();
+    job.report_mut().source = probe_cache::media_info(&job.input_path)
+        .ok()
+        .map(|info| SourceSummary::from_info(&info));
 
     if !silent {
         println!(
             "Encoding: {} → {}",
             job.input_path.display(),
-            job.output_path.display()
+            output_path.display()
         );
         if let Some(dur) = job.duration_s {
             println!("Duration: {:.2}s", dur);
@@ -1955,10 +2132,17 @@ where
     // Determine effective profile (with Auto-VAMF calibration if enabled)
     let effective_profile: Option<Profile> = if let Some(provided_profile) = profile_override {
         // Profile was provided, check if Auto-VAMF is enabled
-        if provided_profile.vmaf_enabled {
+        let stored = provided_profile
+            .vmaf_enabled
+            .then(|| stored_calibration(job, provided_profile))
+            .flatten();
+        if stored.is_some() {
+            stored
+        } else if provided_profile.vmaf_enabled {
             // Set status to Calibrating and store target
             job.status = JobStatus::Calibrating;
             job.vmaf_target = Some(provided_profile.vmaf_target);
+            let calibration_started = Instant::now();
             callback(job, &ProgressParser::new()); // Notify UI of status change
 
             let _ = write_debug_log("[Auto-VAMF] Calibration enabled, starting...");
@@ -1991,6 +2175,7 @@ where
                     // Store calibration results in job
                     job.vmaf_result = Some(result.measured_vmaf);
                     job.calibrated_quality = Some(result.quality);
+                    job.calibrated_hw = Some(provided_profile.use_hardware_encoding);
 
                     // Apply calibrated quality
                     Some(apply_calibrated_quality(provided_profile, result.quality))
@@ -2000,6 +2185,7 @@ where
                     let _ = write_debug_log("[Auto-VAMF] Falling back to baseline quality");
                     job.vmaf_result = None;
                     job.calibrated_quality = None;
+                    job.calibrated_hw = None;
                     Some(provided_profile.clone())
                 }
             };
@@ -2008,6 +2194,8 @@ where
             job.progress_pct = 0.0;
             job.calibrating_total_steps = None;
             job.calibrating_completed_steps = 0;
+            job.report_mut().timings.calibration_secs =
+                Some(calibration_started.elapsed().as_secs_f64());
             callback(job, &ProgressParser::new());
 
             calibrated_profile
@@ -2025,10 +2213,17 @@ where
         .or_else(|| Profile::get_builtin(&job.profile))
         .unwrap_or_else(|| Profile::get(&job.profile));
 
-        if loaded_profile.vmaf_enabled {
+        let stored = loaded_profile
+            .vmaf_enabled
+            .then(|| stored_calibration(job, &loaded_profile))
+            .flatten();
+        if stored.is_some() {
+            stored
+        } else if loaded_profile.vmaf_enabled {
             // Set status to Calibrating and store target
             job.status = JobStatus::Calibrating;
             job.vmaf_target = Some(loaded_profile.vmaf_target);
+            let calibration_started = Instant::now();
             callback(job, &ProgressParser::new()); // Notify UI of status change
 
             let _ = write_debug_log("[Auto-VAMF] Calibration enabled, starting...");
@@ -2060,6 +2255,7 @@ where
                     // Store calibration results in job
                     job.vmaf_result = Some(result.measured_vmaf);
                     job.calibrated_quality = Some(result.quality);
+                    job.calibrated_hw = Some(loaded_profile.use_hardware_encoding);
 
                     Some(apply_calibrated_quality(&loaded_profile, result.quality))
                 }
@@ -2068,6 +2264,7 @@ where
                     let _ = write_debug_log("[Auto-VAMF] Falling back to baseline quality");
                     job.vmaf_result = None;
                     job.calibrated_quality = None;
+                    job.calibrated_hw = None;
                     Some(loaded_profile)
                 }
             };
@@ -2076,6 +2273,8 @@ where
             job.progress_pct = 0.0;
             job.calibrating_total_steps = None;
             job.calibrating_completed_steps = 0;
+            job.report_mut().timings.calibration_secs =
+                Some(calibration_started.elapsed().as_secs_f64());
             callback(job, &ProgressParser::new());
 
             calibrated_profile
@@ -2124,6 +2323,11 @@ where
         .first()
         .and_then(extract_video_encoder_arg)
         .unwrap_or_default();
+    let calibrated_quality = job.calibrated_quality;
+    let report = job.report_mut();
+    report.profile = effective_profile.clone();
+    report.calibrated_quality = calibrated_quality;
+    report.encoder = Some(selected_encoder.clone()).filter(|e| !e.is_empty());
 
     let mut cmd_strings = cmds.iter().map(cmd_to_string).collect::<Vec<_>>();
 
@@ -2151,6 +2355,22 @@ where
         }
     }
 
+    // Long files in segment mode resume from their last finished segment
+    if config.segments.applies_to(job.duration_s) {
+        let profile = effective_profile
+            .as_ref()
+            .context("Effective profile missing")?;
+        return encode_in_segments(
+            job,
+            &cmds,
+            profile,
+            &config.segments,
+            silent,
+            pid_registry.as_ref(),
+            &mut callback,
+        );
+    }
+
     let (mut status, mut last_parser, mut last_stderr_output, mut failed_pass) =
         run_cmds_with_progress(job, cmds, silent, cmd_strings.len(), pid_registry.as_ref(), &mut callback)?;
 
@@ -2230,6 +2450,9 @@ where
                 "av1_qsv" => build_av1_vaapi_cmd(job, profile_for_cmd),
                 _ => unreachable!("fallback guarded by encoder check"),
             };
+            let report = job.report_mut();
+            report.fallback_from = Some(selected_encoder.clone());
+            report.encoder = extract_video_encoder_arg(&fallback_cmd);
 
             let fallback_cmd_strings = vec![cmd_to_string(&fallback_cmd)];
             if !silent {
@@ -2263,11 +2486,6 @@ where
         // Verify output file exists
         if job.output_path.exists() {
             job.status = JobStatus::Done;
-            if !silent {
-                println!("✓ Completed: {}", job.output_path.display());
-            }
-            // Log success
-            write_debug_log(&format!("✓ Success: {}\n", job.output_path.display())).ok();
 
             // Cleanup passlog directory on success
             if cmd_strings.len() == 2 {
@@ -2327,28 +2545,7 @@ where
             last_stderr_output
         ))
         .ok();
-
-        // Clean up partial output file on actual FFmpeg failure (not user cancellation)
-        // Only delete if FFmpeg returned non-zero exit code AND wasn't killed by user signal
-        if !status.success() && !was_user_cancelled(&status, &last_stderr_output) && job.output_path.exists() {
-            if let Err(e) = fs::remove_file(&job.output_path) {
-                let _ = write_debug_log(&format!(
-                    "[cleanup] Failed to remove partial output {}: {}\n",
-                    job.output_path.display(),
-                    e
-                ));
-            } else {
-                let _ = write_debug_log(&format!(
-                    "[cleanup] Removed partial output from failed encode: {}\n",
-                    job.output_path.display()
-                ));
-            }
-        } else if was_user_cancelled(&status, &last_stderr_output) && job.output_path.exists() {
-            let _ = write_debug_log(&format!(
-                "[cleanup] Preserving partial output (user cancelled): {}\n",
-                job.output_path.display()
-            ));
-        }
+        // The caller removes the partial output
     }
 
     // Return error if job failed, so callers know to handle it as a failure
@@ -2463,4 +2660,35 @@ mod tests {
         // Should return empty vec on unsupported value (early return)
         assert_eq!(opts.len(), 0, "Unsupported values should return empty vec");
     }
+
+    #[test]
+    fn test_stored_calibration_requires_matching_target() {
+        let mut profile = Profile::get("vp9-good");
+        profile.vmaf_target = 93.0;
+        let mut job = VideoJob::new(
+            PathBuf::from("a.mp4"),
+            PathBuf::from("a.webm"),
+            "vp9-good".to_string(),
+        );
+        assert!(stored_calibration(&job, &profile).is_none());
+
+        job.calibrated_quality = Some(36);
+        job.vmaf_target = Some(95.0);
+        assert!(stored_calibration(&job, &profile).is_none());
+
+        job.vmaf_target = Some(93.0);
+        // Saved before the quality kind was recorded: unknown, so calibrate again
+        assert!(stored_calibration(&job, &profile).is_none());
+
+        job.calibrated_hw = Some(false);
+        let reused = stored_calibration(&job, &profile).expect("should reuse");
+        assert_eq!(reused.crf, 36);
+
+        // A CRF is not a hardware quality
+        profile.use_hardware_encoding = true;
+        assert!(stored_calibration(&job, &profile).is_none());
+        job.calibrated_hw = Some(true);
+        let reused = stored_calibration(&job, &profile).expect("should reuse");
+        assert_eq!(reused.hw_global_quality, 36);
+    }
 }
@@ -66,15 +66,16 @@ pub fn ffprobe_version() -> Result<String> {
 
 /// Probe a video file to get its duration in seconds
 pub fn probe_duration(path: &Path) -> Result<f64> {
-    let output = Command::new("ffprobe")
-        .arg("-v")
-        .arg("quiet")
-        .arg("-print_format")
-        .arg("json")
-        .arg("-show_format")
-        .arg(path)
-        .output()
-        .context("Failed to execute ffprobe")?;
+    let output = crate::engine::probe::run_ffprobe(
+        Command::new("ffprobe")
+            .arg("-v")
+            .arg("quiet")
+            .arg("-print_format")
+            .arg("json")
+            .arg("-show_format")
+            .arg(path),
+    )
+    .context("Failed to execute ffprobe")?;
 
     if !output.status.success() {
         anyhow::bail!(
@@ -0,0 +1,182 @@
+// Scan-time job decisions made from probe data: profile rules and skip rules
+
+use super::output::CollisionPolicy;
+use super::profile::Profile;
+use super::scan_filter::pattern_matches;
+use super::skip_policy::SkipPolicy;
+use super::types::VideoJob;
+use crate::engine::probe::MediaInfo;
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// One entry of the `[[rules]]` table in config.toml.
+///
+/// Every condition that is set must hold; the first matching rule picks the
+/// job's profile. Conditions on stream data never match files ffprobe can't read.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileRule {
+    /// Shown in the dashboard (defaults to the profile name)
+    pub name: Option<String>,
+    /// Saved or built-in profile to encode matching files with
+    pub profile: String,
+    /// Path glob (same syntax as `[scan]` patterns); `/` patterns may start at any directory
+    pub path: Option<String>,
+    pub min_height: Option<u32>,
+    pub max_height: Option<u32>,
+    pub hdr: Option<bool>,
+    /// Source video codecs (ffprobe names)
+    pub codecs: Vec<String>,
+    pub min_duration_secs: Option<f64>,
+    pub max_duration_secs: Option<f64>,
+    pub min_bitrate_kbps: Option<u64>,
+    pub max_bitrate_kbps: Option<u64>,
+}
+
+impl ProfileRule {
+    /// Name shown for jobs this rule matched
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.profile)
+    }
+
+    pub fn matches(&self, path: &Path, info: Option<&MediaInfo>) -> bool {
+        if let Some(pattern) = &self.path {
+            if !path_matches(pattern, path) {
+                return false;
+            }
+        }
+        if !self.needs_probe() {
+            return true;
+        }
+
+        let Some(info) = info else {
+            return false;
+        };
+        let Some(video) = info.primary_video() else {
+            return false;
+        };
+
+        let in_range = |value: Option<f64>, min: Option<f64>, max: Option<f64>| {
+            if min.is_none() && max.is_none() {
+                return true;
+            }
+            value.is_some_and(|v| min.is_none_or(|min| v >= min) && max.is_none_or(|max| v <= max))
+        };
+
+        let height = video.height.map(f64::from);
+        let bitrate_kbps = video
+            .bitrate_bps
+            .or(info.container.bitrate_bps)
+            .map(|bps| bps as f64 / 1000.0);
+
+        in_range(
+            height,
+            self.min_height.map(f64::from),
+            self.max_height.map(f64::from),
+        ) && self.hdr.is_none_or(|hdr| info.is_hdr() == hdr)
+            && (self.codecs.is_empty()
+                || video
+                    .codec_name
+                    .as_deref()
+                    .is_some_and(|codec| self.codecs.iter().any(|c| c.eq_ignore_ascii_case(codec))))
+            && in_range(
+                info.container.duration,
+                self.min_duration_secs,
+                self.max_duration_secs,
+            )
+            && in_range(
+                bitrate_kbps,
+                self.min_bitrate_kbps.map(|kbps| kbps as f64),
+                self.max_bitrate_kbps.map(|kbps| kbps as f64),
+            )
+    }
+
+    /// Whether any condition needs stream data rather than just the path
+    fn needs_probe(&self) -> bool {
+        self.min_height.is_some()
+            || self.max_height.is_some()
+            || self.hdr.is_some()
+            || !self.codecs.is_empty()
+            || self.min_duration_secs.is_some()
+            || self.max_duration_secs.is_some()
+            || self.min_bitrate_kbps.is_some()
+            || self.max_bitrate_kbps.is_some()
+    }
+}
+
+/// Everything `build_job_with_policy` decides from probe data, plus what to do when
+/// a job's output path is taken
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JobPolicy {
+    pub skip: SkipPolicy,
+    pub rules: Vec<ProfileRule>,
+    pub collision: CollisionPolicy,
+    /// Keep each file's folders below its scan root under the output directory
+    pub mirror_tree: bool,
+    /// Directories being scanned; files mirror their path below the deepest one
+    pub scan_roots: Vec<PathBuf>,
+}
+
+impl JobPolicy {
+    /// The same policy for a queue scanned from `roots`
+    pub fn with_scan_roots(mut self, roots: Vec<PathBuf>) -> Self {
+        self.scan_roots = roots;
+        self
+    }
+
+    /// Folders between `input`'s scan root and the file, when mirroring the source tree
+    pub fn mirrored_dir<'a>(&self, input: &'a Path) -> Option<&'a Path> {
+        self.mirror_tree.then(|| self.relative_dir(input)).flatten()
+    }
+
+    /// Folders between the deepest scan root containing `input` and the file
+    pub fn relative_dir<'a>(&self, input: &'a Path) -> Option<&'a Path> {
+        let parent = input.parent()?;
+        self.scan_roots
+            .iter()
+            .filter_map(|root| parent.strip_prefix(root).ok())
+            .min_by_key(|rel| rel.components().count())
+    }
+
+    /// Whether any skip check or rule reads stream data rather than just the path
+    pub fn needs_probe(&self) -> bool {
+        self.skip.is_enabled() || self.rules.iter().any(|r| r.needs_probe())
+    }
+
+    /// First rule matching the file, if any
+    pub fn match_rule(&self, path: &Path, info: Option<&MediaInfo>) -> Option<&ProfileRule> {
+        self.rules.iter().find(|rule| rule.matches(path, info))
+    }
+
+    /// Check up front that every rule names a profile that exists
+    pub fn validate(&self) -> Result<()> {
+        for (idx, rule) in self.rules.iter().enumerate() {
+            if rule.profile.is_empty() {
+                bail!("rule {} ('{}') has no profile", idx + 1, rule.label());
+            }
+            if Profile::find(&rule.profile).is_none() {
+                bail!(
+                    "rule {} ('{}') uses unknown profile '{}'",
+                    idx + 1,
+                    rule.label(),
+                    rule.profile
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The profile a rule picked for `job`, or `None` when no rule matched it
+pub fn rule_profile(job: &VideoJob) -> Option<Profile> {
+    job.profile_rule.as_ref()?;
+    Profile::find(&job.profile)
+}
+
+/// Match a rule glob against a path; patterns with a '/' may start at any directory
+fn path_matches(pattern: &str, path: &Path) -> bool {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+    (0..components.len()).any(|start| pattern_matches(pattern, &components[start..].join("/")))
+}
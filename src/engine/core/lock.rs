@@ -0,0 +1,186 @@
+// Advisory lock on a queue root, so two instances never write one `.enc_state`
+
+use anyhow::{Context, Result, bail};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const LOCK_FILE: &str = ".ffdash.lock";
+
+/// Held while this process owns a queue root; released on drop.
+///
+/// Ownership is an OS lock on `.ffdash.lock` (flock on Unix, an exclusive open on
+/// Windows), so the OS releases it when the process exits however it exits, and it
+/// also holds across hosts for roots on a network share. The file's contents (PID and
+/// host of the owner) are only for messages.
+#[derive(Debug)]
+pub struct QueueLock {
+    path: PathBuf,
+    /// None when the root is read-only, so there was nothing to lock
+    file: Option<File>,
+}
+
+impl QueueLock {
+    /// Lock `root`, failing if another ffdash holds it
+    pub fn acquire(root: &Path) -> Result<Self> {
+        let path = root.join(LOCK_FILE);
+        loop {
+            let mut file = match open_locked(&path) {
+                Ok(Some(file)) => file,
+                Ok(None) => {
+                    let owner = fs::read_to_string(&path)
+                        .ok()
+                        .and_then(|contents| describe_owner(&contents))
+                        .map(|owner| format!(" ({})", owner))
+                        .unwrap_or_default();
+                    bail!(
+                        "another ffdash{} is using {}; `ffdash status {}` shows its progress read-only",
+                        owner,
+                        root.display(),
+                        root.display()
+                    );
+                }
+                // Nothing can write a queue state here either, so there is nothing to guard
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem
+                    ) =>
+                {
+                    return Ok(Self { path, file: None });
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to lock {}", path.display()));
+                }
+            };
+
+            // The previous owner removed the file between our open and our lock
+            if !still_at(&file, &path) {
+                continue;
+            }
+
+            let host = sysinfo::System::host_name().unwrap_or_default();
+            file.set_len(0)
+                .and_then(|()| writeln!(file, "{}\n{}", std::process::id(), host))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            return Ok(Self {
+                path,
+                file: Some(file),
+            });
+        }
+    }
+
+    /// PID written by the ffdash holding `root`'s lock, if one holds it
+    pub fn holder(root: &Path) -> Option<u32> {
+        let path = root.join(LOCK_FILE);
+        if !path.exists() {
+            return None;
+        }
+        match open_locked(&path) {
+            // Nobody holds it; ours is released again on drop
+            Ok(Some(_)) => None,
+            Ok(None) => read_pid(&path),
+            Err(_) => None,
+        }
+    }
+}
+
+impl Drop for QueueLock {
+    fn drop(&mut self) {
+        if let Some(file) = self.file.take() {
+            // Unix: remove the file while still locking it, so an instance that opened
+            // it meanwhile sees it is gone and retries. Windows can't remove an open
+            // file, and nobody else can open it while we do.
+            #[cfg(unix)]
+            {
+                let _ = fs::remove_file(&self.path);
+                drop(file);
+            }
+            #[cfg(windows)]
+            {
+                drop(file);
+                let _ = fs::remove_file(&self.path);
+            }
+        }
+    }
+}
+
+/// Open and lock `path`, or None if another process holds the lock
+#[cfg(unix)]
+fn open_locked(path: &Path) -> io::Result<Option<File>> {
+    use std::os::unix::io::AsRawFd;
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(Some(file));
+    }
+    let err = io::Error::last_os_error();
+    if err.kind() == io::ErrorKind::WouldBlock {
+        Ok(None)
+    } else {
+        Err(err)
+    }
+}
+
+/// Open `path` for writing with only reads shared, or None if another process has it
+#[cfg(windows)]
+fn open_locked(path: &Path) -> io::Result<Option<File>> {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    const FILE_SHARE_READ: u32 = 0x1;
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+
+    match OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .share_mode(FILE_SHARE_READ)
+        .open(path)
+    {
+        Ok(file) => Ok(Some(file)),
+        Err(e) if e.raw_os_error() == Some(ERROR_SHARING_VIOLATION) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether `file` is still the file at `path`
+#[cfg(unix)]
+fn still_at(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(open), Ok(current)) => open.dev() == current.dev() && open.ino() == current.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(windows)]
+fn still_at(_file: &File, _path: &Path) -> bool {
+    true
+}
+
+/// "pid 123 on nas" from a lock file's contents
+fn describe_owner(contents: &str) -> Option<String> {
+    let mut lines = contents.lines();
+    let pid: u32 = lines.next()?.trim().parse().ok()?;
+    match lines.next().map(str::trim).filter(|host| !host.is_empty()) {
+        Some(host) => Some(format!("pid {} on {}", pid, host)),
+        None => Some(format!("pid {}", pid)),
+    }
+}
+
+fn read_pid(path: &Path) -> Option<u32> {
+    fs::read_to_string(path)
+        .ok()?
+        .lines()
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
@@ -0,0 +1,206 @@
+// Output path settings, collision handling (two jobs writing one file, or a job
+// writing over its own source) and partial outputs of unfinished encodes
+
+use super::types::{JobStatus, VideoJob};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// What happens to a job whose output path is taken
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CollisionPolicy {
+    /// Add ` (2)`, ` (3)`, … to the output name
+    Suffix,
+    /// Skip the job, with the reason shown in the dashboard
+    #[default]
+    Skip,
+    /// Fail the job, with the reason shown in the dashboard
+    Error,
+}
+
+/// `[output]` settings in config.toml
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputSettings {
+    pub collision: CollisionPolicy,
+    /// Recreate each file's folders below the scan root under the output directory
+    pub mirror_tree: bool,
+    /// Encode here instead of next to the output, then move the finished file over
+    pub staging_dir: Option<PathBuf>,
+}
+
+/// Apply `policy` to a job whose output path is its own input.
+///
+/// Called at build time, before the output-exists check (which would otherwise skip
+/// the job without saying why). Returns true if the job was skipped or failed.
+pub fn resolve_own_output(job: &mut VideoJob, policy: CollisionPolicy) -> bool {
+    if path_key(&job.input_path) != path_key(&job.output_path) {
+        return false;
+    }
+    let reason = "Output path is the source file itself".to_string();
+    match policy {
+        CollisionPolicy::Suffix => {
+            job.output_path = suffixed(&job.output_path, 2);
+            false
+        }
+        CollisionPolicy::Skip => mark(job, JobStatus::Skipped, reason),
+        CollisionPolicy::Error => mark(job, JobStatus::Failed, reason),
+    }
+}
+
+/// Output paths already written by jobs in a queue, so later jobs can't reuse them
+#[derive(Debug, Clone, Default)]
+pub struct OutputClaims {
+    policy: CollisionPolicy,
+    /// Output path -> input of the job that claimed it first
+    outputs: HashMap<PathBuf, PathBuf>,
+}
+
+impl OutputClaims {
+    pub fn new(policy: CollisionPolicy) -> Self {
+        Self {
+            policy,
+            outputs: HashMap::new(),
+        }
+    }
+
+    /// Claims for a queue that already holds `jobs`
+    pub fn with_jobs(policy: CollisionPolicy, jobs: &[VideoJob]) -> Self {
+        let mut claims = Self::new(policy);
+        for job in jobs {
+            claims.insert(job);
+        }
+        claims
+    }
+
+    /// Resolve a pending job whose output an earlier job already writes, then claim its
+    /// output. Returns true if the job collided.
+    ///
+    /// Jobs that won't write anything (skipped because their output exists, done,
+    /// failed) only claim.
+    pub fn claim(&mut self, job: &mut VideoJob) -> bool {
+        let collided = job.status == JobStatus::Pending
+            && self.outputs.contains_key(&path_key(&job.output_path));
+
+        if collided {
+            let first = &self.outputs[&path_key(&job.output_path)];
+            let reason = format!(
+                "Output {} is already written for {}",
+                file_name(&job.output_path),
+                file_name(first)
+            );
+            match self.policy {
+                CollisionPolicy::Suffix => {
+                    let free = (2..)
+                        .map(|n| suffixed(&job.output_path, n))
+                        .find(|path| !self.outputs.contains_key(&path_key(path)))
+                        .expect("unbounded range always yields a free name");
+                    job.output_path = free;
+                    // An earlier run may have written the suffixed name already
+                    if !job.overwrite && job.output_path.exists() {
+                        let reason = format!(
+                            "Output {} already exists and overwrite is disabled",
+                            file_name(&job.output_path)
+                        );
+                        mark(job, JobStatus::Skipped, reason);
+                    }
+                }
+                CollisionPolicy::Skip => {
+                    mark(job, JobStatus::Skipped, reason);
+                }
+                CollisionPolicy::Error => {
+                    mark(job, JobStatus::Failed, reason);
+                }
+            }
+        }
+
+        self.insert(job);
+        collided
+    }
+
+    fn insert(&mut self, job: &VideoJob) {
+        self.outputs
+            .entry(path_key(&job.output_path))
+            .or_insert_with(|| job.input_path.clone());
+    }
+}
+
+/// Where `job` encodes until it is finished. In `staging_dir` every job gets a folder
+/// named after its id, so outputs with the same file name in different folders (`Season
+/// 1/E01`, `Season 2/E01`) never share a partial.
+pub fn job_partial_path(job: &VideoJob, staging_dir: Option<&Path>) -> PathBuf {
+    let job_dir = staging_dir.map(|dir| dir.join(job.id.to_string()));
+    partial_path(&job.output_path, job_dir.as_deref())
+}
+
+/// `name.partial.ext` for `output`, next to it or in `dir`
+pub fn partial_path(output: &Path, dir: Option<&Path>) -> PathBuf {
+    let name = match output.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{}.partial.{}", file_stem(output), ext),
+        None => format!("{}.partial", file_stem(output)),
+    };
+    match dir {
+        Some(dir) => dir.join(name),
+        None => output.with_file_name(name),
+    }
+}
+
+/// Whether `path` is an unfinished encode (`name.partial.ext`)
+pub fn is_partial(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .is_some_and(|stem| stem.ends_with(".partial"))
+}
+
+/// Move a finished partial to `output`. The last step is always a rename within the
+/// output directory, so the output appears complete or not at all.
+pub fn finish_partial(partial: &Path, output: &Path) -> io::Result<()> {
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let beside = partial_path(output, None);
+    match fs::rename(partial, output) {
+        Ok(()) => return Ok(()),
+        Err(e) if partial == beside => return Err(e),
+        Err(_) => {}
+    }
+    // The staging dir is on another filesystem: copy next to the output first
+    fs::copy(partial, &beside)?;
+    fs::rename(&beside, output)?;
+    fs::remove_file(partial)
+}
+
+/// `dir/name (n).ext` for `dir/name.ext`
+fn suffixed(path: &Path, n: u32) -> PathBuf {
+    let stem = file_stem(path);
+    let name = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{} ({}).{}", stem, n, ext),
+        None => format!("{} ({})", stem, n),
+    };
+    path.with_file_name(name)
+}
+
+fn mark(job: &mut VideoJob, status: JobStatus, reason: String) -> bool {
+    job.status = status;
+    job.last_error = Some(reason);
+    true
+}
+
+fn file_stem(path: &Path) -> &str {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("output")
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+fn path_key(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
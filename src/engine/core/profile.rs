@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use std::io;
 use std::path::Path;
 
+use super::template::{PatternContext, render_pattern};
+
 use crate::engine::validate::{HardwareAvailability, validate_profile};
 
 // Re-export codec-specific configs from their dedicated modules
@@ -870,8 +872,7 @@ impl Profile {
     /// Determine the encoder ID that will be used for this profile
     ///
     /// Used for PARAMS validation to determine which encoder-specific
-    /// parameter ranges to check against.
-    #[cfg(feature = "dev-tools")]
+    /// parameter ranges to check against, and for the `{encoder}` filename token.
     pub fn resolved_encoder_id(&self) -> String {
         match &self.codec {
             Codec::Vp9(_) => {
@@ -945,6 +946,14 @@ impl Profile {
         Ok(profile)
     }
 
+    /// Resolve a profile by name: saved profiles first, then built-in user-facing profiles
+    pub fn find(name: &str) -> Option<Self> {
+        Self::profiles_dir()
+            .ok()
+            .and_then(|dir| Self::load(&dir, name).ok())
+            .or_else(|| Self::get_builtin(name))
+    }
+
     /// List all saved profiles
     pub fn list_saved(profiles_dir: &Path) -> io::Result<Vec<String>> {
         use std::fs;
@@ -981,6 +990,105 @@ impl Profile {
 
         Ok(())
     }
+
+    /// Serialize profile for sharing outside the profiles directory
+    pub fn to_string_as(&self, format: ProfileFormat) -> io::Result<String> {
+        match format {
+            ProfileFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+            ProfileFormat::Toml => toml::to_string_pretty(self)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        }
+    }
+
+    /// Parse a profile exported by `to_string_as`
+    pub fn from_str_as(content: &str, format: ProfileFormat) -> io::Result<Self> {
+        let mut profile: Self = match format {
+            ProfileFormat::Json => serde_json::from_str(content)?,
+            ProfileFormat::Toml => toml::from_str(content)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+        };
+        profile.sync_legacy_fields();
+        Ok(profile)
+    }
+
+    /// Read a profile file, picking the format from its extension
+    pub fn import_file(path: &Path) -> io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::from_str_as(&content, ProfileFormat::from_path(path))
+    }
+
+    /// Compare two profiles field by field (nested codec settings use dotted paths)
+    pub fn diff(&self, other: &Self) -> io::Result<Vec<ProfileFieldDiff>> {
+        let mut left = std::collections::BTreeMap::new();
+        let mut right = std::collections::BTreeMap::new();
+        flatten_json("", &serde_json::to_value(self)?, &mut left);
+        flatten_json("", &serde_json::to_value(other)?, &mut right);
+
+        let fields: std::collections::BTreeSet<&String> = left.keys().chain(right.keys()).collect();
+        Ok(fields
+            .into_iter()
+            .filter(|field| left.get(*field) != right.get(*field))
+            .map(|field| ProfileFieldDiff {
+                field: field.clone(),
+                left: left.get(field).cloned(),
+                right: right.get(field).cloned(),
+            })
+            .collect())
+    }
+}
+
+/// File format for exported/imported profiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileFormat {
+    Json,
+    Toml,
+}
+
+impl ProfileFormat {
+    /// TOML for `.toml` files, JSON otherwise (the on-disk profile format)
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => ProfileFormat::Toml,
+            _ => ProfileFormat::Json,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ProfileFormat::Json => "json",
+            ProfileFormat::Toml => "toml",
+        }
+    }
+}
+
+/// One differing field between two profiles (None = field absent on that side)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProfileFieldDiff {
+    pub field: String,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+fn flatten_json(
+    prefix: &str,
+    value: &serde_json::Value,
+    out: &mut std::collections::BTreeMap<String, String>,
+) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten_json(&path, child, out);
+            }
+        }
+        other => {
+            out.insert(prefix.to_string(), other.to_string());
+        }
+    }
 }
 
 /// Derive output path from input path and profile
@@ -993,6 +1101,9 @@ impl Profile {
 /// - {profile}: profile suffix (e.g., "vp9good")
 /// - {ext}: output container extension (e.g., "webm")
 ///
+/// See `PATTERN_TOKENS` for the rest; `derive_output_path_with` fills in the probe- and
+/// scan-dependent ones.
+///
 /// Examples:
 /// - Append: `{filename}_converted` → video.mp4 → video.mp4_converted.webm
 /// - Prepend: `encoded_{filename}` → video.mp4 → encoded_video.mp4.webm
@@ -1003,6 +1114,26 @@ pub fn derive_output_path(
     custom_output_dir: Option<&str>,
     custom_pattern: Option<&str>,
     custom_container: Option<&str>,
+) -> std::path::PathBuf {
+    derive_output_path_with(
+        input_path,
+        profile,
+        custom_output_dir,
+        custom_pattern,
+        custom_container,
+        &PatternContext::default(),
+    )
+}
+
+/// Like `derive_output_path`, with the source's probe and scan-relative folder for
+/// pattern tokens such as `{height}` and `{relpath}`
+pub fn derive_output_path_with(
+    input_path: &Path,
+    profile: &str,
+    custom_output_dir: Option<&str>,
+    custom_pattern: Option<&str>,
+    custom_container: Option<&str>,
+    ctx: &PatternContext,
 ) -> std::path::PathBuf {
     let profile_obj = Profile::get(profile);
 
@@ -1017,10 +1148,6 @@ pub fn derive_output_path(
         .file_stem()
         .and_then(|s| s.to_str())
         .unwrap_or("output");
-    let original_filename = input_path
-        .file_name()
-        .and_then(|s| s.to_str())
-        .unwrap_or("output");
 
     // Use custom container or profile's default
     let container = custom_container.unwrap_or(&profile_obj.container);
@@ -1028,11 +1155,7 @@ pub fn derive_output_path(
     // Use custom pattern (filename_pattern is now a global setting, not part of profiles)
     let filename = if let Some(pat) = custom_pattern {
         // Custom template-based filename transformation
-        let result = pat
-            .replace("{basename}", stem)
-            .replace("{filename}", original_filename)
-            .replace("{profile}", &profile_obj.suffix)
-            .replace("{ext}", container);
+        let result = render_pattern(pat, input_path, &profile_obj, container, ctx);
 
         // Add extension
         format!("{}.{}", result, container)
@@ -1109,4 +1232,45 @@ mod tests {
             "hw_global_quality should be synced from Vp9Config"
         );
     }
+
+    #[test]
+    fn test_profile_toml_roundtrip() {
+        let profile = Profile::get_builtin("1080p Film").unwrap();
+
+        let toml = profile.to_string_as(ProfileFormat::Toml).unwrap();
+        let restored = Profile::from_str_as(&toml, ProfileFormat::Toml).unwrap();
+
+        assert_eq!(restored.name, "1080p Film");
+        assert!(profile.diff(&restored).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_profile_diff_reports_nested_codec_fields() {
+        let left = Profile::get_builtin("1080p Film").unwrap();
+        let mut right = left.clone();
+        right.crf = left.crf + 4;
+        if let Codec::Av1(av1) = &mut right.codec {
+            av1.preset += 1;
+        }
+
+        let diffs = right.diff(&left).unwrap();
+        let fields: Vec<&str> = diffs.iter().map(|d| d.field.as_str()).collect();
+
+        assert!(fields.contains(&"crf"));
+        assert!(fields.contains(&"codec.preset"));
+        assert_eq!(diffs.len(), 2, "Only changed fields should be reported: {:?}", fields);
+    }
+
+    #[test]
+    fn test_profile_format_from_path() {
+        assert_eq!(
+            ProfileFormat::from_path(Path::new("film.TOML")),
+            ProfileFormat::Toml
+        );
+        assert_eq!(
+            ProfileFormat::from_path(Path::new("film.json")),
+            ProfileFormat::Json
+        );
+        assert_eq!(ProfileFormat::from_path(Path::new("film")), ProfileFormat::Json);
+    }
 }
@@ -0,0 +1,166 @@
+// Queue ordering: manual moves, pins, sorts and the scheduler's pick
+
+use super::types::{JobStatus, VideoJob};
+use crate::engine::probe_cache;
+use std::cmp::{Ordering, Reverse};
+use std::path::Path;
+
+/// Bits per pixel of a typical AV1/VP9 encode, used to guess how far a source will shrink
+const REFERENCE_BITS_PER_PIXEL: f64 = 0.05;
+
+/// Keys the queue can be sorted by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QueueSort {
+    /// Path in natural order, so `S01E2` comes before `S01E10`
+    #[default]
+    Name,
+    /// Largest source first
+    Size,
+    /// Longest source first
+    Duration,
+    /// Largest estimated savings first
+    Savings,
+}
+
+impl QueueSort {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Size => "size",
+            Self::Duration => "duration",
+            Self::Savings => "est. savings",
+        }
+    }
+
+    /// The sort the dashboard switches to next
+    pub fn next(self) -> Self {
+        match self {
+            Self::Name => Self::Size,
+            Self::Size => Self::Duration,
+            Self::Duration => Self::Savings,
+            Self::Savings => Self::Name,
+        }
+    }
+}
+
+/// Reorder the whole queue (stable, so equal keys keep their current order)
+pub fn sort_jobs(jobs: &mut [VideoJob], sort: QueueSort) {
+    match sort {
+        QueueSort::Name => jobs.sort_by(|a, b| {
+            natural_cmp(
+                &a.input_path.to_string_lossy(),
+                &b.input_path.to_string_lossy(),
+            )
+        }),
+        QueueSort::Size => jobs.sort_by_cached_key(|job| Reverse(file_size(&job.input_path))),
+        QueueSort::Duration => jobs.sort_by(|a, b| {
+            let (a, b) = (a.duration_s.unwrap_or(0.0), b.duration_s.unwrap_or(0.0));
+            b.total_cmp(&a)
+        }),
+        QueueSort::Savings => {
+            jobs.sort_by_cached_key(|job| Reverse(estimated_savings_bytes(job).unwrap_or(i64::MIN)))
+        }
+    }
+}
+
+/// Swap a job with its neighbour; returns its new index
+pub fn move_job(jobs: &mut [VideoJob], idx: usize, up: bool) -> Option<usize> {
+    let target = if up { idx.checked_sub(1)? } else { idx + 1 };
+    if target >= jobs.len() {
+        return None;
+    }
+    jobs.swap(idx, target);
+    Some(target)
+}
+
+/// Pin a job to run before every other pending job, or unpin it if it already is
+pub fn toggle_pin(jobs: &mut [VideoJob], idx: usize) {
+    let Some(job) = jobs.get(idx) else {
+        return;
+    };
+    if job.priority > 0 {
+        jobs[idx].priority = 0;
+        return;
+    }
+    let highest = jobs.iter().map(|j| j.priority).max().unwrap_or(0);
+    jobs[idx].priority = highest.max(0) + 1;
+}
+
+/// The pending job the scheduler runs next: highest priority, then queue order
+pub fn next_pending(jobs: &[VideoJob]) -> Option<usize> {
+    jobs.iter()
+        .enumerate()
+        .filter(|(_, job)| job.status == JobStatus::Pending)
+        .max_by_key(|(idx, job)| (job.priority, Reverse(*idx)))
+        .map(|(idx, _)| idx)
+}
+
+/// Rough bytes an encode saves (negative when it grows).
+///
+/// Started jobs project their output size from progress so far. Others compare the
+/// source's bits per pixel (from the probe cache) with a typical encode's; `None`
+/// when neither is known.
+pub fn estimated_savings_bytes(job: &VideoJob) -> Option<i64> {
+    let input_bytes = file_size(&job.input_path)?;
+
+    if let Some(output_bytes) = job.size_bytes.filter(|_| job.progress_pct > 0.0) {
+        let projected = output_bytes as f64 * 100.0 / job.progress_pct.min(100.0);
+        return Some(input_bytes as i64 - projected as i64);
+    }
+
+    let info = probe_cache::cached_media_info(&job.input_path)?;
+    let video = info.primary_video()?;
+    let duration = job.duration_s.or(info.container.duration)?;
+    let pixels_per_sec =
+        video.width? as f64 * video.height? as f64 * video.frame_rate.unwrap_or(24.0);
+    if duration <= 0.0 || pixels_per_sec <= 0.0 {
+        return None;
+    }
+
+    let source_bpp = input_bytes as f64 * 8.0 / (duration * pixels_per_sec);
+    let shrink = (REFERENCE_BITS_PER_PIXEL / source_bpp).min(1.0);
+    Some((input_bytes as f64 * (1.0 - shrink)) as i64)
+}
+
+/// Compare strings with digit runs as numbers (`ep2` < `ep10`), ignoring ASCII case
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        match (a.chars().next(), b.chars().next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (num_a, rest_a) = split_digits(a);
+                let (num_b, rest_b) = split_digits(b);
+                let (trim_a, trim_b) =
+                    (num_a.trim_start_matches('0'), num_b.trim_start_matches('0'));
+                let order = trim_a
+                    .len()
+                    .cmp(&trim_b.len())
+                    .then_with(|| trim_a.cmp(trim_b))
+                    .then_with(|| num_a.len().cmp(&num_b.len()));
+                if order != Ordering::Equal {
+                    return order;
+                }
+                (a, b) = (rest_a, rest_b);
+            }
+            (Some(x), Some(y)) => {
+                let order = x.to_ascii_lowercase().cmp(&y.to_ascii_lowercase());
+                if order != Ordering::Equal {
+                    return order;
+                }
+                (a, b) = (&a[x.len_utf8()..], &b[y.len_utf8()..]);
+            }
+        }
+    }
+}
+
+fn split_digits(s: &str) -> (&str, &str) {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    s.split_at(end)
+}
+
+fn file_size(path: &Path) -> Option<u64> {
+    std::fs::metadata(path).ok().map(|m| m.len())
+}
@@ -1,43 +1,95 @@
-use super::ffmpeg_info::probe_duration;
-use super::profile::derive_output_path;
+use super::job_policy::JobPolicy;
+use super::output::{OutputClaims, is_partial, resolve_own_output};
+use super::profile::{Profile, derive_output_path_with};
+use super::scan_filter::{DEFAULT_VIDEO_EXTENSIONS, ExclusionReason, ScanFilter, sniff_file};
+use super::template::PatternContext;
 use super::types::{JobStatus, VideoJob};
-use anyhow::Result;
+use crate::engine::{probe, probe_cache};
+use anyhow::{Result, anyhow};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, mpsc};
+use std::thread;
 use walkdir::WalkDir;
 
-/// Default video file extensions to scan for
-const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mkv", "webm", "mov", "avi", "flv", "m4v", "wmv"];
-
-/// Check if a path has a video file extension
+/// Check if a path has one of the default video file extensions
 pub fn is_video_file(path: &Path) -> bool {
     if let Some(ext) = path.extension() {
         if let Some(ext_str) = ext.to_str() {
-            return VIDEO_EXTENSIONS.contains(&ext_str.to_lowercase().as_str());
+            return DEFAULT_VIDEO_EXTENSIONS.contains(&ext_str.to_lowercase().as_str());
         }
     }
     false
 }
 
 /// Scan a directory recursively for video files and invoke a callback for each file found
-pub fn scan_streaming<F>(root: &Path, mut on_file: F) -> Result<()>
+pub fn scan_streaming<F>(root: &Path, on_file: F) -> Result<()>
+where
+    F: FnMut(PathBuf),
+{
+    scan_streaming_filtered(root, &ScanFilter::default(), on_file, |_, _| {})
+}
+
+/// Like `scan_streaming`, but applies `filter` and reports every file or directory it
+/// leaves out (pruned directories are reported once, not per file)
+pub fn scan_streaming_filtered<F, X>(
+    root: &Path,
+    filter: &ScanFilter,
+    mut on_file: F,
+    mut on_excluded: X,
+) -> Result<()>
 where
     F: FnMut(PathBuf),
+    X: FnMut(PathBuf, ExclusionReason),
 {
     // Memo from ops: when we followed links, someone archived /proc into git
-    for entry in WalkDir::new(root)
-        .follow_links(false)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
+    let mut entries = WalkDir::new(root).follow_links(false).into_iter();
+    while let Some(entry) = entries.next() {
+        let Ok(entry) = entry else {
+            continue;
+        };
         let path = entry.path();
+        let is_dir = entry.file_type().is_dir();
 
         // Skip temporary VMAF calibration directories
-        if path.components().any(|c| c.as_os_str() == ".ffdash_tmp") {
+        if entry.file_name() == ".ffdash_tmp" {
+            if is_dir {
+                entries.skip_current_dir();
+            }
+            continue;
+        }
+        // Unfinished encodes are never sources
+        if !is_dir && is_partial(path) {
             continue;
         }
 
-        if path.is_file() && is_video_file(path) {
-            on_file(path.to_path_buf());
+        let rel_path = path.strip_prefix(root).unwrap_or(path);
+        if is_dir {
+            if entry.depth() > 0 {
+                if let Some(reason) = filter.check_dir(rel_path, entry.depth()) {
+                    on_excluded(path.to_path_buf(), reason);
+                    entries.skip_current_dir();
+                }
+            }
+            continue;
+        }
+
+        if !path.is_file() {
+            continue;
+        }
+        let reason = if filter.has_video_extension(path) {
+            filter.check_file(path, rel_path)
+        } else if filter.should_sniff(path) {
+            // Cheap name/size checks first so excluded files are never probed
+            filter
+                .check_file(path, rel_path)
+                .or_else(|| sniff_file(path))
+        } else {
+            continue;
+        };
+        match reason {
+            Some(reason) => on_excluded(path.to_path_buf(), reason),
+            None => on_file(path.to_path_buf()),
         }
     }
 
@@ -51,6 +103,131 @@ pub fn scan(root: &Path) -> Result<Vec<PathBuf>> {
     Ok(files)
 }
 
+/// Result of a filtered scan
+#[derive(Debug, Default)]
+pub struct ScanResult {
+    /// Files to queue, in walk order
+    pub files: Vec<PathBuf>,
+    /// Files and pruned directories the filter left out
+    pub excluded: Vec<(PathBuf, ExclusionReason)>,
+}
+
+/// Scan with filters, returning queued files and everything that was left out
+pub fn scan_with_filter(root: &Path, filter: &ScanFilter) -> Result<ScanResult> {
+    let mut result = ScanResult::default();
+    scan_streaming_filtered(
+        root,
+        filter,
+        |path| result.files.push(path),
+        |path, reason| result.excluded.push((path, reason)),
+    )?;
+    // Keep probes made by duration and sniff filters
+    probe_cache::flush();
+    Ok(result)
+}
+
+/// Walk and filter like `scan_streaming_filtered`, building each queued file's job with
+/// `build` on a pool of `[probe] workers` threads. Jobs reach `on_job` in walk order, as
+/// soon as each job and every job before it is ready.
+pub fn scan_jobs_streaming<B, J>(
+    root: &Path,
+    filter: &ScanFilter,
+    build: B,
+    on_job: J,
+) -> Result<()>
+where
+    B: Fn(PathBuf) -> VideoJob + Sync,
+    J: FnMut(VideoJob),
+{
+    let (path_tx, path_rx) = mpsc::channel();
+    thread::scope(|scope| {
+        let walker = scope.spawn(move || {
+            scan_streaming_filtered(
+                root,
+                filter,
+                |path| {
+                    let _ = path_tx.send(path);
+                },
+                |_, _| {},
+            )
+        });
+
+        map_ordered(
+            path_rx,
+            probe::settings().effective_workers(),
+            build,
+            on_job,
+        );
+        probe_cache::flush();
+
+        walker
+            .join()
+            .unwrap_or_else(|_| Err(anyhow!("Scan thread panicked")))
+    })
+}
+
+/// Apply `f` to `items` on `workers` threads, handing results to `on_result` in input
+/// order as soon as each result and all of its predecessors are ready
+pub(crate) fn map_ordered<I, T, R, F, C>(items: I, workers: usize, f: F, mut on_result: C)
+where
+    I: IntoIterator<Item = T>,
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+    C: FnMut(R),
+{
+    let workers = workers.max(1);
+    let (work_tx, work_rx) = mpsc::sync_channel::<(usize, T)>(workers);
+    let work_rx = Mutex::new(work_rx);
+    let (done_tx, done_rx) = mpsc::channel::<(usize, R)>();
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            let done_tx = done_tx.clone();
+            let (work_rx, f) = (&work_rx, &f);
+            scope.spawn(move || {
+                loop {
+                    let next = match work_rx.lock() {
+                        Ok(rx) => rx.recv(),
+                        Err(_) => break,
+                    };
+                    let Ok((seq, item)) = next else {
+                        break;
+                    };
+                    if done_tx.send((seq, f(item))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(done_tx);
+
+        let mut pending = BTreeMap::new();
+        let mut next_seq = 0;
+        let mut accept = |seq: usize, result: R| {
+            pending.insert(seq, result);
+            while let Some(result) = pending.remove(&next_seq) {
+                on_result(result);
+                next_seq += 1;
+            }
+        };
+
+        for (seq, item) in items.into_iter().enumerate() {
+            if work_tx.send((seq, item)).is_err() {
+                break;
+            }
+            for (seq, result) in done_rx.try_iter() {
+                accept(seq, result);
+            }
+        }
+        drop(work_tx);
+
+        for (seq, result) in done_rx {
+            accept(seq, result);
+        }
+    });
+}
+
 /// Build job queue from scanned files
 /// Jobs are marked as Skipped if the output file already exists (unless overwrite is true)
 pub fn build_job_from_path(
@@ -61,24 +238,80 @@ pub fn build_job_from_path(
     custom_pattern: Option<&str>,
     custom_container: Option<&str>,
 ) -> VideoJob {
-    let output_path = derive_output_path(
+    build_job_with_policy(
+        input_path,
+        profile,
+        overwrite,
+        custom_output_dir,
+        custom_pattern,
+        custom_container,
+        &JobPolicy::default(),
+    )
+}
+
+/// Like `build_job_from_path`, but lets the first matching `[[rules]]` entry pick the
+/// profile (and its container), and marks already-efficient sources Skipped with the
+/// reason in `last_error`
+pub fn build_job_with_policy(
+    input_path: PathBuf,
+    profile: &str,
+    overwrite: bool,
+    custom_output_dir: Option<&str>,
+    custom_pattern: Option<&str>,
+    custom_container: Option<&str>,
+    policy: &JobPolicy,
+) -> VideoJob {
+    // One cached probe serves the rules, the skip checks and the duration
+    let info = probe_cache::media_info(&input_path).ok();
+
+    let rule = policy.match_rule(&input_path, info.as_ref());
+    let rule_profile = rule.and_then(|rule| Profile::find(&rule.profile));
+    let (profile, custom_container) = match (rule, &rule_profile) {
+        (Some(rule), Some(matched)) => (rule.profile.as_str(), Some(matched.container.as_str())),
+        _ => (profile, custom_container),
+    };
+
+    // A mirrored tree puts the file's folders (below its scan root) under the output dir
+    let mirrored_dir = custom_output_dir
+        .zip(policy.mirrored_dir(&input_path))
+        .map(|(dir, rel)| Path::new(dir).join(rel));
+    let custom_output_dir = mirrored_dir
+        .as_deref()
+        .and_then(Path::to_str)
+        .or(custom_output_dir);
+
+    let output_path = derive_output_path_with(
         &input_path,
         profile,
         custom_output_dir,
         custom_pattern,
         custom_container,
+        &PatternContext {
+            info: info.as_ref(),
+            relpath: policy.relative_dir(&input_path),
+        },
     );
-    let mut job = VideoJob::new(input_path.clone(), output_path.clone(), profile.to_string());
+    let mut job = VideoJob::new(input_path, output_path, profile.to_string());
 
     // Set overwrite flag
     job.overwrite = overwrite;
+    if rule_profile.is_some() {
+        job.profile_rule = rule.map(|rule| rule.label().to_string());
+    }
 
     // Probe duration for ETA calculation
-    job.duration_s = probe_duration(&input_path).ok();
+    job.duration_s = info.as_ref().and_then(|info| info.container.duration);
+
+    if resolve_own_output(&mut job, policy.collision) {
+        return job;
+    }
 
     // Skip detection: if output exists and overwrite is disabled, mark as Skipped
-    if !overwrite && output_path.exists() {
+    if !overwrite && job.output_path.exists() {
+        job.status = JobStatus::Skipped;
+    } else if let Some(reason) = info.as_ref().and_then(|info| policy.skip.skip_reason(info)) {
         job.status = JobStatus::Skipped;
+        job.last_error = Some(reason);
     }
 
     job
@@ -92,17 +325,50 @@ pub fn build_job_queue(
     custom_pattern: Option<&str>,
     custom_container: Option<&str>,
 ) -> Vec<VideoJob> {
-    files
-        .into_iter()
-        .map(|input_path| {
-            build_job_from_path(
+    build_job_queue_with_policy(
+        files,
+        profile,
+        overwrite,
+        custom_output_dir,
+        custom_pattern,
+        custom_container,
+        &JobPolicy::default(),
+    )
+}
+
+pub fn build_job_queue_with_policy(
+    files: Vec<PathBuf>,
+    profile: &str,
+    overwrite: bool,
+    custom_output_dir: Option<&str>,
+    custom_pattern: Option<&str>,
+    custom_container: Option<&str>,
+    policy: &JobPolicy,
+) -> Vec<VideoJob> {
+    // Each job costs at least one ffprobe, so build them on the probe pool
+    let mut jobs = Vec::with_capacity(files.len());
+    map_ordered(
+        files,
+        probe::settings().effective_workers(),
+        |input_path| {
+            build_job_with_policy(
                 input_path,
                 profile,
                 overwrite,
                 custom_output_dir,
                 custom_pattern,
                 custom_container,
+                policy,
             )
-        })
-        .collect()
+        },
+        |job| jobs.push(job),
+    );
+    probe_cache::flush();
+
+    // Earlier files keep their output name; later ones that would write it are resolved
+    let mut claims = OutputClaims::new(policy.collision);
+    for job in &mut jobs {
+        claims.claim(job);
+    }
+    jobs
 }
@@ -0,0 +1,327 @@
+// Include/exclude rules applied while walking a library
+
+use crate::engine::probe::{MediaInfo, StreamKind};
+use crate::engine::probe_cache;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// Video file extensions scanned when `[scan] extensions` is not set
+pub const DEFAULT_VIDEO_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "webm", "mov", "avi", "flv", "m4v", "wmv", "ts", "m2ts", "mpg", "vob", "3gp",
+    "ogv", "mxf",
+];
+
+/// Files that are never worth an ffprobe in sniff mode (sidecars, artwork, audio, notes)
+const NEVER_SNIFF_EXTENSIONS: &[&str] = &[
+    "srt", "ass", "ssa", "sub", "idx", "sup", "vtt", "nfo", "txt", "md", "log", "json", "xml",
+    "jpg", "jpeg", "png", "gif", "bmp", "webp", "tif", "tiff", "mp3", "flac", "m4a", "aac", "ogg",
+    "opus", "wav", "wma", "pdf", "part", "tmp",
+];
+
+/// Whether dot-files and dot-directories are scanned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HiddenFiles {
+    /// Scan hidden files like any other (previous behavior)
+    #[default]
+    Include,
+    /// Leave out anything whose name starts with '.'
+    Skip,
+}
+
+/// Scan filters, configured under `[scan]` in config.toml and overridable per command.
+///
+/// Glob patterns without a '/' are matched against every path component (so
+/// `Extras` drops any `Extras/` directory and `*sample*` any sample file); patterns
+/// with a '/' are matched against the path relative to the scan root, where `**`
+/// spans directories. Matching ignores ASCII case.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanFilter {
+    /// Extensions treated as video without probing (case-insensitive, no dot)
+    pub extensions: Vec<String>,
+    /// Probe extensionless and unknown files and queue them if they hold real video
+    pub sniff: bool,
+    /// Only queue files matching at least one of these globs (everything when empty)
+    pub include: Vec<String>,
+    /// Never queue files or descend into directories matching any of these globs
+    pub exclude: Vec<String>,
+    /// Skip files smaller than this many MiB
+    pub min_size_mb: Option<u64>,
+    /// Skip files larger than this many MiB
+    pub max_size_mb: Option<u64>,
+    /// Skip files shorter than this (needs an ffprobe per file)
+    pub min_duration_secs: Option<f64>,
+    /// Directory levels below the root to descend (0 = only files directly in the root)
+    pub max_depth: Option<usize>,
+    pub hidden: HiddenFiles,
+}
+
+impl Default for ScanFilter {
+    fn default() -> Self {
+        Self {
+            extensions: DEFAULT_VIDEO_EXTENSIONS
+                .iter()
+                .map(|ext| ext.to_string())
+                .collect(),
+            sniff: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            min_size_mb: None,
+            max_size_mb: None,
+            min_duration_secs: None,
+            max_depth: None,
+            hidden: HiddenFiles::default(),
+        }
+    }
+}
+
+/// Why the scanner left a file or directory out of the queue
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum ExclusionReason {
+    Hidden,
+    ExcludePattern {
+        pattern: String,
+    },
+    NotIncluded,
+    TooSmall {
+        size_bytes: u64,
+        min_bytes: u64,
+    },
+    TooLarge {
+        size_bytes: u64,
+        max_bytes: u64,
+    },
+    TooShort {
+        duration_secs: f64,
+        min_secs: f64,
+    },
+    MaxDepth {
+        max_depth: usize,
+    },
+    /// Sniffed file that ffprobe could not read
+    NotMedia,
+    /// Sniffed file without any video stream
+    NoVideoStream,
+    /// The only video stream is cover art or a thumbnail
+    AttachedPictureOnly,
+    /// Sniffed file is a single image (e.g. an extensionless JPEG)
+    StillImage,
+}
+
+impl fmt::Display for ExclusionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hidden => write!(f, "hidden"),
+            Self::ExcludePattern { pattern } => write!(f, "matches exclude '{}'", pattern),
+            Self::NotIncluded => write!(f, "matches no include pattern"),
+            Self::TooSmall {
+                size_bytes,
+                min_bytes,
+            } => write!(
+                f,
+                "{} MiB is below the {} MiB minimum",
+                size_bytes / BYTES_PER_MB,
+                min_bytes / BYTES_PER_MB
+            ),
+            Self::TooLarge {
+                size_bytes,
+                max_bytes,
+            } => write!(
+                f,
+                "{} MiB is above the {} MiB maximum",
+                size_bytes / BYTES_PER_MB,
+                max_bytes / BYTES_PER_MB
+            ),
+            Self::TooShort {
+                duration_secs,
+                min_secs,
+            } => write!(
+                f,
+                "{:.0}s is shorter than the {:.0}s minimum",
+                duration_secs, min_secs
+            ),
+            Self::MaxDepth { max_depth } => write!(f, "deeper than max depth {}", max_depth),
+            Self::NotMedia => write!(f, "not a media file"),
+            Self::NoVideoStream => write!(f, "no video stream"),
+            Self::AttachedPictureOnly => write!(f, "only video stream is an attached picture"),
+            Self::StillImage => write!(f, "still image"),
+        }
+    }
+}
+
+impl ScanFilter {
+    /// Whether the file's extension is in the configured video list
+    pub fn has_video_extension(&self, path: &Path) -> bool {
+        extension_of(path).is_some_and(|ext| {
+            self.extensions
+                .iter()
+                .any(|known| known.trim_start_matches('.').eq_ignore_ascii_case(&ext))
+        })
+    }
+
+    /// Whether sniff mode should probe a file the extension list did not match
+    pub fn should_sniff(&self, path: &Path) -> bool {
+        self.sniff
+            && extension_of(path).is_none_or(|ext| !NEVER_SNIFF_EXTENSIONS.contains(&ext.as_str()))
+    }
+
+    /// Check a directory before descending into it (`depth` 1 = directly under the root)
+    pub fn check_dir(&self, rel_path: &Path, depth: usize) -> Option<ExclusionReason> {
+        if let Some(reason) = self.check_name(rel_path) {
+            return Some(reason);
+        }
+        match self.max_depth {
+            Some(max_depth) if depth > max_depth => Some(ExclusionReason::MaxDepth { max_depth }),
+            _ => None,
+        }
+    }
+
+    /// Check a video file; `rel_path` is relative to the scan root
+    pub fn check_file(&self, path: &Path, rel_path: &Path) -> Option<ExclusionReason> {
+        if let Some(reason) = self.check_name(rel_path) {
+            return Some(reason);
+        }
+
+        let rel = normalize(rel_path);
+        if !self.include.is_empty() && !self.include.iter().any(|p| pattern_matches(p, &rel)) {
+            return Some(ExclusionReason::NotIncluded);
+        }
+
+        if self.min_size_mb.is_some() || self.max_size_mb.is_some() {
+            if let Ok(size_bytes) = std::fs::metadata(path).map(|m| m.len()) {
+                if let Some(min_bytes) = self.min_size_mb.map(|mb| mb * BYTES_PER_MB) {
+                    if size_bytes < min_bytes {
+                        return Some(ExclusionReason::TooSmall {
+                            size_bytes,
+                            min_bytes,
+                        });
+                    }
+                }
+                if let Some(max_bytes) = self.max_size_mb.map(|mb| mb * BYTES_PER_MB) {
+                    if size_bytes > max_bytes {
+                        return Some(ExclusionReason::TooLarge {
+                            size_bytes,
+                            max_bytes,
+                        });
+                    }
+                }
+            }
+        }
+
+        // Probe last: it is by far the most expensive check
+        if let Some(min_secs) = self.min_duration_secs {
+            let duration = probe_cache::media_info(path).map(|info| info.container.duration);
+            if let Ok(Some(duration_secs)) = duration {
+                if duration_secs < min_secs {
+                    return Some(ExclusionReason::TooShort {
+                        duration_secs,
+                        min_secs,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Hidden-file and exclude checks shared by files and directories
+    fn check_name(&self, rel_path: &Path) -> Option<ExclusionReason> {
+        if self.hidden == HiddenFiles::Skip {
+            let hidden = rel_path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with('.'));
+            if hidden {
+                return Some(ExclusionReason::Hidden);
+            }
+        }
+
+        let rel = normalize(rel_path);
+        self.exclude
+            .iter()
+            .find(|p| pattern_matches(p, &rel))
+            .map(|pattern| ExclusionReason::ExcludePattern {
+                pattern: pattern.clone(),
+            })
+    }
+}
+
+/// Probe an unknown file and decide whether it holds real video
+pub fn sniff_file(path: &Path) -> Option<ExclusionReason> {
+    match probe_cache::media_info(path) {
+        Ok(info) => classify_media(&info),
+        Err(_) => Some(ExclusionReason::NotMedia),
+    }
+}
+
+/// Reject probed files without a real video stream (`None` means queue it)
+pub fn classify_media(info: &MediaInfo) -> Option<ExclusionReason> {
+    if info.primary_video().is_some() {
+        // ffprobe reports images as one-frame video through its image demuxers
+        let format = info.container.format_name.as_str();
+        if format == "image2" || format.ends_with("_pipe") {
+            return Some(ExclusionReason::StillImage);
+        }
+        return None;
+    }
+    if info.streams_of(StreamKind::Video).next().is_some() {
+        Some(ExclusionReason::AttachedPictureOnly)
+    } else {
+        Some(ExclusionReason::NoVideoStream)
+    }
+}
+
+fn extension_of(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+}
+
+fn normalize(rel_path: &Path) -> String {
+    rel_path.to_string_lossy().replace('\\', "/")
+}
+
+/// Apply a scan pattern to a root-relative path (see `ScanFilter` for the rules)
+pub fn pattern_matches(pattern: &str, rel_path: &str) -> bool {
+    let pattern = pattern.trim_end_matches('/');
+    if pattern.contains('/') {
+        glob_match(pattern.trim_start_matches('/'), rel_path)
+    } else {
+        rel_path
+            .split('/')
+            .any(|component| glob_match(pattern, component))
+    }
+}
+
+/// Match `text` against a glob: `*` and `?` stay within one path component,
+/// `**` spans components. Matching ignores ASCII case.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_ascii_lowercase().chars().collect();
+    let text: Vec<char> = text.to_ascii_lowercase().chars().collect();
+    glob_match_chars(&pattern, &text)
+}
+
+fn glob_match_chars(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            // `**/` may also match zero directories
+            let without_slash = rest.strip_prefix(&['/']).unwrap_or(rest);
+            glob_match_chars(without_slash, text)
+                || (0..=text.len()).any(|i| glob_match_chars(rest, &text[i..]))
+        }
+        Some('*') => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != '/')
+            .any(|i| glob_match_chars(&pattern[1..], &text[i..])),
+        Some('?') => {
+            text.first().is_some_and(|c| *c != '/') && glob_match_chars(&pattern[1..], &text[1..])
+        }
+        Some(c) => text.first() == Some(c) && glob_match_chars(&pattern[1..], &text[1..]),
+    }
+}
@@ -0,0 +1,540 @@
+// Segment mode: long encodes split at keyframes, so an interrupted job resumes from
+// its last finished segment instead of from zero
+
+use super::ffmpeg_cmd::{
+    build_audio_only_cmd, container_from_output, format_command, run_ffmpeg_once,
+    two_pass_log_prefix,
+};
+use super::log::write_debug_log;
+use super::profile::Profile;
+use super::types::{JobStatus, ProgressParser, VideoJob};
+use crate::engine::probe::{self, StreamKind};
+use crate::engine::vmaf::{cleanup_job_temp_dir, create_job_temp_dir};
+use crate::engine::worker::PidRegistry;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// `[segments]` settings in config.toml
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SegmentSettings {
+    /// Encode long files in segments that are kept across restarts
+    pub enabled: bool,
+    /// Target segment length; a segment starts on the first keyframe at least this
+    /// long after the previous one
+    pub length_secs: u64,
+}
+
+impl SegmentSettings {
+    /// Whether a file of `duration_s` is encoded in segments (it has to be worth two)
+    pub fn applies_to(&self, duration_s: Option<f64>) -> bool {
+        self.enabled
+            && self.length_secs > 0
+            && duration_s.is_some_and(|d| d >= 2.0 * self.length_secs as f64)
+    }
+}
+
+impl Default for SegmentSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            length_secs: 300,
+        }
+    }
+}
+
+/// A job's segments and which of them are finished, kept in `.enc_state`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SegmentPlan {
+    /// Fingerprint of the encode commands; finished segments made with other settings
+    /// are thrown away
+    pub key: String,
+    pub segments: Vec<Segment>,
+    /// The audio tracks are encoded in one piece, so segment boundaries can't leave
+    /// gaps in them (None: the source has no audio)
+    pub audio_done: Option<bool>,
+}
+
+/// Source time range of one segment, in microseconds from the start of the file
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+    pub start_us: u64,
+    /// None for the last segment, which runs to the end of the file
+    pub end_us: Option<u64>,
+    pub done: bool,
+}
+
+impl SegmentPlan {
+    /// Split a file at `keyframes` (sorted): each segment starts on the first keyframe
+    /// at least `length_us` after the previous start, and none starts in the last half
+    /// length of the file
+    pub fn new(
+        key: String,
+        keyframes: &[u64],
+        length_us: u64,
+        duration_us: u64,
+        has_audio: bool,
+    ) -> Self {
+        let mut starts = vec![0];
+        for &keyframe in keyframes {
+            let last = starts[starts.len() - 1];
+            if keyframe >= last + length_us && keyframe + length_us / 2 <= duration_us {
+                starts.push(keyframe);
+            }
+        }
+
+        let ends = starts.iter().skip(1).copied().map(Some).chain([None]);
+        let segments = starts
+            .iter()
+            .zip(ends)
+            .map(|(&start_us, end_us)| Segment {
+                start_us,
+                end_us,
+                done: false,
+            })
+            .collect();
+
+        Self {
+            key,
+            segments,
+            audio_done: has_audio.then_some(false),
+        }
+    }
+
+    /// Share of the source whose video is already encoded, 0-100
+    pub fn progress_pct(&self, duration_s: f64) -> f64 {
+        let duration_us = duration_s * 1e6;
+        if duration_us <= 0.0 {
+            return 0.0;
+        }
+        let done: f64 = self
+            .segments
+            .iter()
+            .filter(|s| s.done)
+            .map(|s| s.length_us(duration_us))
+            .sum();
+        (done / duration_us * 100.0).clamp(0.0, 100.0)
+    }
+
+    pub fn done_count(&self) -> usize {
+        self.segments.iter().filter(|s| s.done).count()
+    }
+}
+
+impl Segment {
+    fn length_us(&self, duration_us: f64) -> f64 {
+        self.end_us.map_or(duration_us, |end| end as f64) - self.start_us as f64
+    }
+}
+
+/// Encode `job` one segment at a time with `cmds` (its full-file encode, one command
+/// per pass), skipping segments an earlier run finished, then join the segments and
+/// the separately encoded audio into `job.output_path` without re-encoding.
+///
+/// Segments live in the job's temp dir and are removed once the output is written.
+pub(super) fn encode_in_segments(
+    job: &mut VideoJob,
+    cmds: &[Command],
+    profile: &Profile,
+    settings: &SegmentSettings,
+    silent: bool,
+    pid_registry: Option<&PidRegistry>,
+    callback: &mut dyn FnMut(&VideoJob, &ProgressParser),
+) -> Result<()> {
+    let dir = create_job_temp_dir(job)?;
+    let ext = container_from_output(job, profile);
+    let duration_s = job
+        .duration_s
+        .context("Segment mode needs the source duration")?;
+    let key = fingerprint(cmds, settings.length_secs);
+
+    let mut plan = match job.segments.take() {
+        Some(mut plan) if plan.key == key => {
+            // Segments whose files went missing are encoded again
+            for (idx, segment) in plan.segments.iter_mut().enumerate() {
+                segment.done &= segment_path(&dir, idx, &ext).exists();
+            }
+            if plan.audio_done == Some(true) && !audio_path(&dir, &ext).exists() {
+                plan.audio_done = Some(false);
+            }
+            let _ = write_debug_log(&format!(
+                "[segments] Resuming {}: {} of {} segments already encoded\n",
+                job.input_path.display(),
+                plan.done_count(),
+                plan.segments.len()
+            ));
+            plan
+        }
+        _ => {
+            // Segments from other settings (or none yet): start over
+            for entry in fs::read_dir(&dir)?.flatten() {
+                let _ = fs::remove_file(entry.path());
+            }
+            plan_segments(job, key, settings, duration_s)?
+        }
+    };
+    job.segments = Some(plan.clone());
+    job.progress_pct = plan.progress_pct(duration_s);
+    callback(job, &ProgressParser::new());
+
+    if plan.audio_done == Some(false) {
+        let cmd = build_audio_only_cmd(job, profile, &audio_path(&dir, &ext));
+        let at = (plan.progress_pct(duration_s), 0.0);
+        run_step(job, cmd, "audio", at, silent, pid_registry, callback)?;
+        plan.audio_done = Some(true);
+        job.segments = Some(plan.clone());
+        callback(job, &ProgressParser::new());
+    }
+
+    let duration_us = duration_s * 1e6;
+    let total = plan.segments.len();
+    let passes = cmds.len();
+    for idx in 0..total {
+        let segment = plan.segments[idx];
+        if segment.done {
+            continue;
+        }
+        let file = segment_path(&dir, idx, &ext);
+        let length_us = segment.length_us(duration_us);
+        for (pass, cmd) in cmds.iter().enumerate() {
+            let step = if passes > 1 {
+                format!("segment {} of {}, pass {}", idx + 1, total, pass + 1)
+            } else {
+                format!("segment {} of {}", idx + 1, total)
+            };
+            // ffmpeg reports time within the segment; place it in the whole file
+            let offset = (segment.start_us as f64 + pass as f64 * length_us / passes as f64)
+                / duration_us
+                * 100.0;
+            let cmd = segment_cmd(cmd, &job.output_path, &segment, &file);
+            let at = (offset, 1.0 / passes as f64);
+            run_step(job, cmd, &step, at, silent, pid_registry, callback)?;
+        }
+        plan.segments[idx].done = true;
+        job.segments = Some(plan.clone());
+        callback(job, &ProgressParser::new());
+    }
+
+    fs::write(dir.join("segments.txt"), concat_list(&plan, &ext))
+        .context("Failed to write the segment list")?;
+    let cmd = concat_cmd(job, &plan, &dir, &ext);
+    run_step(
+        job,
+        cmd,
+        "joining segments",
+        (100.0, 0.0),
+        silent,
+        pid_registry,
+        callback,
+    )?;
+
+    job.report_mut().segments = Some(total);
+    job.segments = None;
+    callback(job, &ProgressParser::new());
+    job.status = JobStatus::Done;
+    let _ = cleanup_job_temp_dir(&dir);
+    if passes == 2 {
+        if let Some(passlog_dir) = two_pass_log_prefix(job).parent() {
+            fs::remove_dir_all(passlog_dir).ok();
+        }
+    }
+    Ok(())
+}
+
+/// Run one step of a segmented encode; `at` is the progress offset and scale it reports
+/// with. A failure fails the job.
+fn run_step(
+    job: &mut VideoJob,
+    cmd: Command,
+    step: &str,
+    at: (f64, f64),
+    silent: bool,
+    pid_registry: Option<&PidRegistry>,
+    callback: &mut dyn FnMut(&VideoJob, &ProgressParser),
+) -> Result<()> {
+    let _ = write_debug_log(&format!(
+        "[segments] {} {}\n{}\n",
+        job.input_path.display(),
+        step,
+        format_command(&cmd)
+    ));
+    let (offset, scale) = at;
+    let (status, _, stderr) =
+        run_ffmpeg_once(job, cmd, silent, offset, scale, pid_registry, callback)?;
+    if status.success() {
+        return Ok(());
+    }
+
+    let lines: Vec<&str> = stderr.lines().collect();
+    let relevant_error = lines[lines.len().saturating_sub(10)..].join("\n");
+    job.status = JobStatus::Failed;
+    job.last_error = Some(format!(
+        "Encoding failed ({}) with status: {}\n\nFFmpeg error:\n{}",
+        step, status, relevant_error
+    ));
+    let _ = write_debug_log(&format!(
+        "✗ Encoding failed: {} ({})\nStatus: {}\nFFmpeg stderr:\n{}\n",
+        job.input_path.display(),
+        step,
+        status,
+        stderr
+    ));
+    bail!(
+        "Encoding failed: {}",
+        job.last_error.as_deref().unwrap_or("Unknown error")
+    )
+}
+
+/// How far past a wanted segment start ffprobe reads looking for a keyframe at first
+const KEYFRAME_WINDOW_SECS: u64 = 30;
+
+fn plan_segments(
+    job: &VideoJob,
+    key: String,
+    settings: &SegmentSettings,
+    duration_s: f64,
+) -> Result<SegmentPlan> {
+    let length_us = settings.length_secs * 1_000_000;
+    let duration_us = (duration_s * 1e6) as u64;
+    let start_time = probe_start_time(&job.input_path)?;
+    let keyframes = segment_keyframes(length_us, duration_us, |from_us, window_us| {
+        keyframes_between(&job.input_path, start_time, from_us, window_us)
+    })?;
+    let has_audio = probe::probe_media_info(&job.input_path)
+        .map(|info| info.streams_of(StreamKind::Audio).next().is_some())
+        .unwrap_or(true);
+
+    Ok(SegmentPlan::new(
+        key,
+        &keyframes,
+        length_us,
+        duration_us,
+        has_audio,
+    ))
+}
+
+/// The keyframes `SegmentPlan::new` would start segments on, found by reading only a
+/// window after each wanted start (widened while it holds no keyframe) instead of
+/// listing every packet in the file. `read(from_us, window_us)` lists the keyframes
+/// ffprobe sees reading from `from_us`.
+pub(super) fn segment_keyframes(
+    length_us: u64,
+    duration_us: u64,
+    mut read: impl FnMut(u64, u64) -> Result<Vec<u64>>,
+) -> Result<Vec<u64>> {
+    let mut keyframes = Vec::new();
+    let mut from_us = length_us;
+    while from_us + length_us / 2 <= duration_us {
+        let mut window_us = KEYFRAME_WINDOW_SECS * 1_000_000;
+        let next = loop {
+            let next = read(from_us, window_us)?
+                .into_iter()
+                .find(|&keyframe| keyframe >= from_us);
+            if next.is_some() || from_us + window_us >= duration_us {
+                break next;
+            }
+            window_us *= 2;
+        };
+        let Some(keyframe) = next else {
+            break;
+        };
+        keyframes.push(keyframe);
+        from_us = keyframe + length_us;
+    }
+    Ok(keyframes)
+}
+
+/// Keyframes ffprobe reads in `window_us` from `from_us` (both from the start of the
+/// file). The seek lands on the keyframe at or before `from_us`.
+fn keyframes_between(
+    input: &Path,
+    start_time: f64,
+    from_us: u64,
+    window_us: u64,
+) -> Result<Vec<u64>> {
+    let interval = format!(
+        "{:.6}%+{:.6}",
+        start_time + from_us as f64 / 1e6,
+        window_us as f64 / 1e6
+    );
+    let output = probe::run_ffprobe(
+        Command::new("ffprobe")
+            .args([
+                "-v",
+                "error",
+                "-select_streams",
+                "v:0",
+                "-read_intervals",
+                &interval,
+                "-show_entries",
+                "packet=pts_time,flags:format=start_time",
+                "-of",
+                "csv=p=0",
+            ])
+            .arg(input),
+    )
+    .context("Failed to run ffprobe")?;
+    if !output.status.success() {
+        bail!(
+            "ffprobe couldn't list keyframes: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(parse_keyframes(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// The file's `start_time`: timestamps, which `-read_intervals` seeks to, count from it
+fn probe_start_time(input: &Path) -> Result<f64> {
+    let output = probe::run_ffprobe(
+        Command::new("ffprobe")
+            .args([
+                "-v",
+                "error",
+                "-show_entries",
+                "format=start_time",
+                "-of",
+                "csv=p=0",
+            ])
+            .arg(input),
+    )
+    .context("Failed to run ffprobe")?;
+    if !output.status.success() {
+        bail!(
+            "ffprobe couldn't read the start time: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    // "N/A" for files without one
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .unwrap_or(0.0))
+}
+
+/// Keyframe times, in microseconds from the start of the file, from ffprobe's
+/// `pts_time,flags` packet lines and the `start_time` format line
+pub(super) fn parse_keyframes(csv: &str) -> Vec<u64> {
+    let mut start_time = 0.0;
+    let mut times = Vec::new();
+    for line in csv.lines().map(str::trim) {
+        match line.split_once(',') {
+            Some((pts, flags)) if flags.contains('K') => {
+                if let Ok(pts) = pts.parse::<f64>() {
+                    times.push(pts);
+                }
+            }
+            Some(_) => {}
+            None => start_time = line.parse().unwrap_or(start_time),
+        }
+    }
+
+    let mut keyframes: Vec<u64> = times
+        .into_iter()
+        .map(|pts| ((pts - start_time).max(0.0) * 1e6).round() as u64)
+        .collect();
+    keyframes.sort_unstable();
+    keyframes.dedup();
+    keyframes
+}
+
+/// `cmd` (a full-file encode writing `output`) cut down to `segment`'s video, writing
+/// `file`. The seek goes before the input, so decoding starts at the keyframe.
+pub(super) fn segment_cmd(cmd: &Command, output: &Path, segment: &Segment, file: &Path) -> Command {
+    let mut segment_cmd = Command::new(cmd.get_program());
+    for (key, value) in cmd.get_envs() {
+        match value {
+            Some(value) => segment_cmd.env(key, value),
+            None => segment_cmd.env_remove(key),
+        };
+    }
+
+    let args: Vec<&OsStr> = cmd.get_args().collect();
+    let Some((target, options)) = args.split_last() else {
+        return segment_cmd;
+    };
+    let mut seeked = false;
+    for arg in options {
+        if !seeked && *arg == "-i" {
+            segment_cmd.arg("-ss").arg(secs(segment.start_us));
+            if let Some(end_us) = segment.end_us {
+                segment_cmd.arg("-t").arg(secs(end_us - segment.start_us));
+            }
+            seeked = true;
+        }
+        segment_cmd.arg(arg);
+    }
+    segment_cmd.args(["-an", "-sn", "-dn", "-y"]);
+    // Pass 1 of a two-pass encode writes to the null muxer instead
+    if Path::new(target) == output {
+        segment_cmd.arg(file);
+    } else {
+        segment_cmd.arg(target);
+    }
+    segment_cmd
+}
+
+/// Input for ffmpeg's concat demuxer. Every segment but the last gets its planned
+/// length, so each one starts exactly where it did in the source.
+pub(super) fn concat_list(plan: &SegmentPlan, ext: &str) -> String {
+    let mut list = String::new();
+    for (idx, segment) in plan.segments.iter().enumerate() {
+        list.push_str(&format!("file '{}'\n", segment_name(idx, ext)));
+        if let Some(end_us) = segment.end_us {
+            list.push_str(&format!("duration {}\n", secs(end_us - segment.start_us)));
+        }
+    }
+    list
+}
+
+/// Join the segments, the audio and the source's metadata and chapters
+fn concat_cmd(job: &VideoJob, plan: &SegmentPlan, dir: &Path, ext: &str) -> Command {
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-f", "concat", "-safe", "0", "-i"])
+        .arg(dir.join("segments.txt"));
+    let source = if plan.audio_done.is_some() {
+        cmd.arg("-i").arg(audio_path(dir, ext));
+        "2"
+    } else {
+        "1"
+    };
+    cmd.arg("-i").arg(&job.input_path);
+    cmd.args(["-map", "0:v"]);
+    if plan.audio_done.is_some() {
+        cmd.args(["-map", "1:a"]);
+    }
+    cmd.args(["-map_metadata", source, "-map_chapters", source]);
+    cmd.args(["-c", "copy", "-progress", "-", "-nostats", "-y"]);
+    cmd.arg(&job.output_path);
+    cmd
+}
+
+fn fingerprint(cmds: &[Command], length_secs: u64) -> String {
+    let mut hasher = DefaultHasher::new();
+    for cmd in cmds {
+        format_command(cmd).hash(&mut hasher);
+    }
+    length_secs.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+fn segment_name(idx: usize, ext: &str) -> String {
+    format!("segment_{:04}.{}", idx, ext)
+}
+
+fn segment_path(dir: &Path, idx: usize, ext: &str) -> PathBuf {
+    dir.join(segment_name(idx, ext))
+}
+
+fn audio_path(dir: &Path, ext: &str) -> PathBuf {
+    dir.join(format!("audio.{}", ext))
+}
+
+/// Microseconds as ffmpeg seconds, exactly
+fn secs(us: u64) -> String {
+    format!("{}.{:06}", us / 1_000_000, us % 1_000_000)
+}
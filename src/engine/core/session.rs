@@ -0,0 +1,92 @@
+// Sessions: one queue built from several directories and ad-hoc files
+
+use super::scan::scan_with_filter;
+use super::scan_filter::ScanFilter;
+use anyhow::{Context, Result, bail};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Session used when no `--session` name is given
+pub const DEFAULT_SESSION: &str = "default";
+
+/// Directory holding a named session's `.enc_state` (`~/.local/share/ffdash/sessions/<name>` on Linux)
+pub fn session_dir(name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+        bail!("invalid session name '{}'", name);
+    }
+
+    Ok(data_dir()?.join("sessions").join(name))
+}
+
+/// ffdash's data directory (`~/.local/share/ffdash` on Linux and macOS)
+pub fn data_dir() -> Result<PathBuf> {
+    Ok(if cfg!(target_os = "macos") {
+        dirs::home_dir()
+            .context("Could not determine home directory")?
+            .join(".local")
+            .join("share")
+            .join("ffdash")
+    } else {
+        dirs::data_dir()
+            .context("Could not determine data directory")?
+            .join("ffdash")
+    })
+}
+
+/// Read a `--from-list` file: one path per line, blank lines and `#` comments ignored.
+/// Relative paths are resolved against the list file's directory.
+pub fn read_path_list(list: &Path) -> Result<Vec<PathBuf>> {
+    let contents = fs::read_to_string(list)
+        .with_context(|| format!("Failed to read path list: {}", list.display()))?;
+    let base = list.parent().unwrap_or(Path::new("."));
+
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| base.join(line))
+        .collect())
+}
+
+/// Expand session sources in order: directories are scanned with `filter`, files are
+/// queued as given (filters don't apply to files named explicitly).
+///
+/// Each file is paired with the source it came from; a file reached twice keeps its
+/// first origin.
+pub fn expand_sources(sources: &[PathBuf], filter: &ScanFilter) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let mut seen = HashSet::new();
+    let mut files = Vec::new();
+
+    for source in sources {
+        let found = if source.is_dir() {
+            scan_with_filter(source, filter)?.files
+        } else if source.is_file() {
+            vec![source.clone()]
+        } else {
+            bail!("{} does not exist", source.display());
+        };
+
+        for file in found {
+            if seen.insert(fs::canonicalize(&file).unwrap_or_else(|_| file.clone())) {
+                files.push((source.clone(), file));
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// Directory whose `.enc_queue` tracks jobs from `origin`: the directory itself, or an
+/// ad-hoc file's parent
+pub fn queue_root(origin: &Path) -> PathBuf {
+    if origin.is_file() {
+        origin
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .to_path_buf()
+    } else {
+        origin.to_path_buf()
+    }
+}
@@ -0,0 +1,67 @@
+// Scan-time rules for leaving already-efficient sources alone
+
+use crate::engine::probe::MediaInfo;
+use serde::{Deserialize, Serialize};
+
+/// Skip rules, configured under `[skip]` in config.toml. Everything is off by default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SkipPolicy {
+    /// Source video codecs (ffprobe names such as "av1", "vp9", "hevc") that are never re-encoded
+    pub codecs: Vec<String>,
+    /// Skip sources at or below this many bits per pixel (video bitrate / (width × height × fps))
+    pub max_bits_per_pixel: Option<f64>,
+    /// Skip sources at or below this bitrate (video stream, or the whole file when unknown)
+    pub max_bitrate_kbps: Option<u64>,
+}
+
+impl SkipPolicy {
+    /// Whether any rule is set (otherwise the full probe is not needed)
+    pub fn is_enabled(&self) -> bool {
+        !self.codecs.is_empty()
+            || self.max_bits_per_pixel.is_some()
+            || self.max_bitrate_kbps.is_some()
+    }
+
+    /// Why a probed source should be skipped, or `None` to encode it
+    pub fn skip_reason(&self, info: &MediaInfo) -> Option<String> {
+        let video = info.primary_video()?;
+
+        if let Some(codec) = video.codec_name.as_deref() {
+            if self.codecs.iter().any(|c| c.eq_ignore_ascii_case(codec)) {
+                return Some(format!("Source is already {}", codec));
+            }
+        }
+
+        let bitrate_bps = video.bitrate_bps.or(info.container.bitrate_bps)?;
+
+        if let Some(max_kbps) = self.max_bitrate_kbps {
+            let kbps = bitrate_bps / 1000;
+            if kbps <= max_kbps {
+                return Some(format!(
+                    "Source bitrate {} kbps is at or below {} kbps",
+                    kbps, max_kbps
+                ));
+            }
+        }
+
+        if let Some(max_bpp) = self.max_bits_per_pixel {
+            if let (Some(width), Some(height), Some(fps)) =
+                (video.width, video.height, video.frame_rate)
+            {
+                let pixels_per_sec = width as f64 * height as f64 * fps;
+                if pixels_per_sec > 0.0 {
+                    let bpp = bitrate_bps as f64 / pixels_per_sec;
+                    if bpp <= max_bpp {
+                        return Some(format!(
+                            "Source is {:.3} bits/pixel, at or below {}",
+                            bpp, max_bpp
+                        ));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
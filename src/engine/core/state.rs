@@ -1,9 +1,11 @@
+use super::output::{CollisionPolicy, OutputClaims, resolve_own_output};
 use super::types::{JobStatus, VideoJob};
+use crate::engine::vmaf::{cleanup_job_temp_dir, job_temp_dir};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{self, BufRead, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Persistent state stored in .enc_state file
 #[derive(Debug, Serialize, Deserialize)]
@@ -13,6 +15,10 @@ pub struct EncState {
     pub root_path: std::path::PathBuf,
     /// The actual profile configuration (takes precedence over selected_profile)
     pub profile_config: Option<super::profile::Profile>,
+    /// Directories and files queued into a session (empty for a single-directory queue,
+    /// whose state lives in that directory; a session's lives in its session directory)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<PathBuf>,
 }
 
 impl EncState {
@@ -28,6 +34,7 @@ impl EncState {
             selected_profile: profile,
             root_path: root,
             profile_config,
+            sources: Vec::new(),
         }
     }
 
@@ -37,26 +44,43 @@ impl EncState {
     }
 
     /// Save state to .enc_state file in root directory
+    ///
+    /// The new state is written to `.enc_state.tmp` and renamed over the old one, which
+    /// is first copied to `.enc_state.bak`, so a crash or full disk mid-write never
+    /// leaves a truncated `.enc_state`.
     pub fn save(&self, root: &Path) -> Result<()> {
         let state_path = root.join(".enc_state");
         let json = serde_json::to_string_pretty(&self).context("Failed to serialize state")?;
 
-        let mut file = File::create(&state_path).context("Failed to create .enc_state file")?;
-
-        file.write_all(json.as_bytes())
-            .context("Failed to write .enc_state file")?;
+        let tmp_path = root.join(".enc_state.tmp");
+        if let Err(e) = write_synced(&tmp_path, json.as_bytes()) {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e).context("Failed to write .enc_state file");
+        }
+        if state_path.exists() {
+            fs::copy(&state_path, root.join(".enc_state.bak"))
+                .context("Failed to back up .enc_state file")?;
+        }
+        fs::rename(&tmp_path, &state_path).context("Failed to replace .enc_state file")?;
 
         Ok(())
     }
 
     /// Load state from .enc_state file in root directory
-    /// Resets any Running/Failed jobs to Pending for resume
-    pub fn load(root: &Path) -> Result<Self> {
-        let state_path = root.join(".enc_state");
-        let file = File::open(&state_path).context("Failed to open .enc_state file")?;
-
-        let mut state: EncState =
-            serde_json::from_reader(file).context("Failed to parse .enc_state file")?;
+    /// Resets any Running/Failed jobs to Pending for resume, except jobs whose output
+    /// path is taken (those are resolved again with the configured `collision` policy)
+    pub fn load(root: &Path, collision: CollisionPolicy) -> Result<Self> {
+        let (mut state, recovered) = Self::read_with_recovery(root)?;
+        if recovered {
+            let _ = super::log::write_debug_log(
+                "[State] .enc_state was unreadable; recovered the queue from .enc_state.bak",
+            );
+            // Put the backup back, so the next save doesn't back up the broken file over it
+            let tmp_path = root.join(".enc_state.tmp");
+            if fs::copy(root.join(".enc_state.bak"), &tmp_path).is_ok() {
+                let _ = fs::rename(&tmp_path, root.join(".enc_state"));
+            }
+        }
 
         // Resume logic: reset Running/Failed jobs to Pending
         for job in &mut state.jobs {
@@ -64,25 +88,276 @@ impl EncState {
                 JobStatus::Running | JobStatus::Failed => {
                     // Postmortem note: resurrecting RUNNING jobs without rewinding once zeroed a staging disk
                     job.status = JobStatus::Pending;
-                    job.progress_pct = 0.0;
+                    // Segment mode keeps finished segments, so those stay counted
+                    job.progress_pct = match (&job.segments, job.duration_s) {
+                        (Some(plan), Some(duration_s)) => plan.progress_pct(duration_s),
+                        _ => 0.0,
+                    };
                     job.out_time_s = 0.0;
                 }
                 _ => {}
             }
         }
 
+        // Jobs failed for a taken output path would only fail again (or overwrite)
+        let mut claims = OutputClaims::new(collision);
+        for job in &mut state.jobs {
+            if job.status == JobStatus::Pending && resolve_own_output(job, collision) {
+                continue;
+            }
+            claims.claim(job);
+        }
+
         Ok(state)
     }
 
+    /// Load state exactly as saved, without the resume reset (for inspection).
+    /// A `.enc_state` that can't be parsed is read from `.enc_state.bak` instead.
+    pub fn load_read_only(root: &Path) -> Result<Self> {
+        Self::read_with_recovery(root).map(|(state, _)| state)
+    }
+
+    /// Read `.enc_state`, falling back to the backup when it exists but won't parse.
+    /// Returns whether the backup was used.
+    fn read_with_recovery(root: &Path) -> Result<(Self, bool)> {
+        let state_path = root.join(".enc_state");
+        let file = File::open(&state_path).context("Failed to open .enc_state file")?;
+
+        let error = match serde_json::from_reader(io::BufReader::new(file)) {
+            Ok(state) => return Ok((state, false)),
+            Err(e) => anyhow::Error::new(e).context("Failed to parse .enc_state file"),
+        };
+        File::open(root.join(".enc_state.bak"))
+            .ok()
+            .and_then(|file| serde_json::from_reader(io::BufReader::new(file)).ok())
+            .map(|state| (state, true))
+            .ok_or(error)
+    }
+
+    /// Copy Auto-VMAF calibrations from the `.enc_state` already saved in `root`
+    /// onto jobs for the same input and profile, so rebuilding the queue doesn't
+    /// throw away results from `ffdash calibrate --save` or an earlier run.
+    /// Call before `save`; returns how many jobs were restored.
+    pub fn restore_calibrations(&mut self, root: &Path) -> usize {
+        let Ok(previous) = Self::load_read_only(root) else {
+            return 0;
+        };
+
+        let mut restored = 0;
+        for job in &mut self.jobs {
+            if job.calibrated_quality.is_some() {
+                continue;
+            }
+            let stored = previous.jobs.iter().find(|old| {
+                old.calibrated_quality.is_some()
+                    && old.profile == job.profile
+                    && same_file(&old.input_path, &job.input_path)
+            });
+            if let Some(old) = stored {
+                job.vmaf_target = old.vmaf_target;
+                job.vmaf_result = old.vmaf_result;
+                job.calibrated_quality = old.calibrated_quality;
+                job.calibrated_hw = old.calibrated_hw;
+                restored += 1;
+            }
+        }
+        restored
+    }
+
+    /// Carry unfinished segment-mode encodes over from the `.enc_state` already saved in
+    /// `root` to pending jobs for the same input and profile. Those jobs take the old
+    /// job id, which names the `.ffdash_tmp/<id>/` folder holding the finished segments.
+    /// Segments no job takes over are deleted. Call before `save`; returns how many jobs
+    /// resume segments.
+    pub fn restore_segments(&mut self, root: &Path) -> usize {
+        let Ok(previous) = Self::load_read_only(root) else {
+            return 0;
+        };
+
+        let mut restored = 0;
+        let mut taken = Vec::new();
+        for job in &mut self.jobs {
+            if job.status != JobStatus::Pending || job.segments.is_some() {
+                continue;
+            }
+            let stored = previous.jobs.iter().find(|old| {
+                old.segments.is_some()
+                    && old.status != JobStatus::Done
+                    && old.profile == job.profile
+                    && same_file(&old.input_path, &job.input_path)
+            });
+            if let Some(old) = stored {
+                job.id = old.id;
+                job.segments = old.segments.clone();
+                taken.push(old.id);
+                restored += 1;
+            }
+        }
+
+        for old in &previous.jobs {
+            if old.segments.is_some() && !taken.contains(&old.id) {
+                let _ = cleanup_job_temp_dir(&job_temp_dir(old));
+            }
+        }
+        restored
+    }
+
+    /// Estimate wall-clock seconds left for Running/Calibrating/Pending jobs.
+    ///
+    /// Uses the same model as the dashboard queue ETA: remaining media time divided
+    /// by the average speed seen so far (1.0x when unknown), spread across workers.
+    /// Returns None when no unfinished job has a known duration.
+    pub fn estimated_remaining_secs(&self, max_workers: usize) -> Option<f64> {
+        let speeds: Vec<f64> = self
+            .jobs
+            .iter()
+            .filter(|j| matches!(j.status, JobStatus::Running | JobStatus::Done))
+            .filter_map(|j| j.smoothed_speed.or(j.speed))
+            .filter(|speed| *speed > 0.0)
+            .collect();
+        let avg_speed = if speeds.is_empty() {
+            1.0
+        } else {
+            speeds.iter().sum::<f64>() / speeds.len() as f64
+        };
+
+        let mut remaining_media = 0.0;
+        let mut unfinished = 0usize;
+        let mut known = 0usize;
+        for job in &self.jobs {
+            let remaining = match job.status {
+                JobStatus::Running | JobStatus::Calibrating => {
+                    job.duration_s.map(|d| (d - job.out_time_s).max(0.0))
+                }
+                JobStatus::Pending => job.duration_s,
+                _ => continue,
+            };
+            unfinished += 1;
+            if let Some(remaining) = remaining {
+                remaining_media += remaining;
+                known += 1;
+            }
+        }
+
+        if known == 0 {
+            return None;
+        }
+
+        let workers = max_workers.min(unfinished).max(1) as f64;
+        Some(remaining_media / avg_speed / workers)
+    }
+
+    /// Profile to encode `job` with: the one its rule picked, else the queue-wide config
+    pub fn profile_for(&self, job: &VideoJob) -> Option<super::profile::Profile> {
+        super::job_policy::rule_profile(job).or_else(|| self.profile_config.clone())
+    }
+
+    /// Reorder jobs to match `order` (matched by id) and take its priorities.
+    /// Jobs missing from `order` keep their relative order at the end.
+    pub fn apply_order(&mut self, order: &[VideoJob]) {
+        let ordered: std::collections::HashMap<_, _> = order
+            .iter()
+            .enumerate()
+            .map(|(idx, job)| (job.id, (idx, job.priority)))
+            .collect();
+        self.jobs
+            .sort_by_key(|job| ordered.get(&job.id).map_or(usize::MAX, |(idx, _)| *idx));
+        for job in &mut self.jobs {
+            if let Some((_, priority)) = ordered.get(&job.id) {
+                job.priority = *priority;
+            }
+        }
+    }
+
     /// Check if .enc_state exists in root directory
     pub fn exists(root: &Path) -> bool {
         root.join(".enc_state").exists()
     }
 
+    /// Find the next Pending job that can actually be encoded (highest priority, then queue order).
+    /// Pending jobs whose output already exists (and overwrite is off) are marked Skipped on the way.
+    pub fn next_encodable_job(&mut self) -> Option<usize> {
+        loop {
+            let idx = super::queue_order::next_pending(&self.jobs)?;
+
+            let job = &mut self.jobs[idx];
+            if job.output_path.exists() && !job.overwrite {
+                job.status = JobStatus::Skipped;
+                job.last_error = Some("Output exists and overwrite is disabled".to_string());
+                continue;
+            }
+
+            return Some(idx);
+        }
+    }
+
+    /// Whether this queue was built from several paths rather than one directory
+    pub fn is_session(&self) -> bool {
+        !self.sources.is_empty()
+    }
+
+    /// Directories that get an `.enc_queue`: `root` for jobs without an origin, plus the
+    /// queue root of every origin
+    pub fn queue_roots(&self, root: &Path) -> Vec<PathBuf> {
+        let mut roots: Vec<PathBuf> = Vec::new();
+        for job in &self.jobs {
+            let queue_root = match &job.origin {
+                Some(origin) => super::session::queue_root(origin),
+                None => root.to_path_buf(),
+            };
+            if !roots.contains(&queue_root) {
+                roots.push(queue_root);
+            }
+        }
+        roots
+    }
+
+    /// Write `.enc_queue` into every queue root (see `queue_roots`)
+    pub fn save_all_queue_status(&self, root: &Path) -> Result<()> {
+        for queue_root in self.queue_roots(root) {
+            self.save_queue_status(&queue_root)?;
+        }
+        Ok(())
+    }
+
     /// Save simple completion status to .enc_queue dotfile
     /// Format: lines starting with # are completed, others are pending
+    ///
+    /// Only jobs tracked in `root` are written (all jobs without an origin). When every
+    /// one is an ad-hoc file, the directory's marker belongs to another queue, so its
+    /// lines for other files are kept.
     pub fn save_queue_status(&self, root: &Path) -> Result<()> {
+        let jobs: Vec<&VideoJob> = self
+            .jobs
+            .iter()
+            .filter(|job| {
+                job.origin
+                    .as_ref()
+                    .is_none_or(|origin| super::session::queue_root(origin) == root)
+            })
+            .collect();
+
+        let ad_hoc_only = !jobs.is_empty()
+            && jobs
+                .iter()
+                .all(|job| job.origin.as_ref().is_some_and(|origin| origin != root));
         let queue_path = root.join(".enc_queue");
+        let kept_lines: Vec<String> = if ad_hoc_only {
+            let ours: std::collections::HashSet<&str> = jobs
+                .iter()
+                .filter_map(|job| job.input_path.file_name().and_then(|n| n.to_str()))
+                .collect();
+            std::fs::read_to_string(&queue_path)
+                .unwrap_or_default()
+                .lines()
+                .filter(|line| !line.trim().is_empty() && !line.starts_with("# VP9"))
+                .filter(|line| !ours.contains(queue_line_filename(line)))
+                .map(str::to_string)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
         let mut file = File::create(&queue_path).context("Failed to create .enc_queue file")?;
 
         writeln!(
@@ -90,7 +365,11 @@ impl EncState {
             "# VP9 Encoding Queue - Lines with # prefix are completed"
         )?;
 
-        for job in &self.jobs {
+        for line in kept_lines {
+            writeln!(file, "{}", line)?;
+        }
+
+        for job in jobs {
             let filename = job
                 .input_path
                 .file_name()
@@ -131,11 +410,7 @@ impl EncState {
             let trimmed = line.trim();
 
             if trimmed.starts_with('#') && !trimmed.starts_with("# VP9") {
-                // Extract filename from completed line "# filename"
-                let filename = trimmed.trim_start_matches('#').trim();
-                // Remove any "(skipped - output exists)" suffix
-                let filename = filename.split(" (skipped").next().unwrap_or(filename);
-                completed_files.insert(filename.to_string());
+                completed_files.insert(queue_line_filename(trimmed).to_string());
             }
         }
 
@@ -156,3 +431,29 @@ impl EncState {
         Ok(())
     }
 }
+
+/// Write `bytes` to `path` and flush them to disk before returning
+fn write_synced(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+/// Filename an `.enc_queue` line refers to
+fn queue_line_filename(line: &str) -> &str {
+    // Extract filename from completed line "# filename"
+    let filename = line.trim().trim_start_matches('#').trim();
+    // Remove any "(skipped - output exists)" suffix
+    filename.split(" (skipped").next().unwrap_or(filename)
+}
+
+/// Compare paths that may have been spelled differently (`./a.mkv` vs `/videos/a.mkv`)
+fn same_file(a: &Path, b: &Path) -> bool {
+    if a == b {
+        return true;
+    }
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
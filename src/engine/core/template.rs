@@ -0,0 +1,339 @@
+// Output filename patterns: `{token}` substitution and up-front validation
+
+use super::av1_config::Codec;
+use super::profile::Profile;
+use crate::engine::probe::MediaInfo;
+use anyhow::{Result, bail};
+use std::path::Path;
+
+/// Every token a filename pattern may use
+pub const PATTERN_TOKENS: &[&str] = &[
+    "basename", "filename", "profile", "ext", "parent", "relpath", "height", "width", "codec",
+    "encoder", "crf", "quality", "vmaf", "date", "hdr", "title", "year", "season", "episode",
+];
+
+/// What a pattern can refer to besides the input path and the profile
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PatternContext<'a> {
+    /// Probe of the source, for `{height}`, `{width}` and `{hdr}`
+    pub info: Option<&'a MediaInfo>,
+    /// Folders between the scan root and the source, for `{relpath}`
+    pub relpath: Option<&'a Path>,
+}
+
+/// Check that `pattern` only uses known tokens and has balanced braces
+pub fn validate_pattern(pattern: &str) -> Result<()> {
+    let mut rest = pattern;
+    while let Some(open) = rest.find(['{', '}']) {
+        if rest[open..].starts_with('}') {
+            bail!("unmatched '}}' in filename pattern \"{}\"", pattern);
+        }
+        let Some(len) = rest[open..].find('}') else {
+            bail!("unclosed '{{' in filename pattern \"{}\"", pattern);
+        };
+        let token = &rest[open + 1..open + len];
+        if !PATTERN_TOKENS.contains(&token) {
+            bail!(
+                "unknown token {{{}}} in filename pattern \"{}\" (supported: {})",
+                token,
+                pattern,
+                PATTERN_TOKENS
+                    .iter()
+                    .map(|t| format!("{{{}}}", t))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        rest = &rest[open + len + 1..];
+    }
+    Ok(())
+}
+
+/// Caveat about a valid pattern worth showing, e.g. a token that defeats skip-on-existing
+pub fn pattern_warning(pattern: &str) -> Option<&'static str> {
+    if pattern.contains("{date}") {
+        Some(
+            "{date} changes the output name every day, so a file encoded on an earlier day \
+             is encoded again instead of skipped as already done",
+        )
+    } else {
+        None
+    }
+}
+
+/// Substitute the tokens in `pattern` for `input`, without the container extension.
+///
+/// Tokens with no value (no year in the name, a source that wasn't probed) are left out,
+/// along with the brackets and separators they leave behind in the pattern text around
+/// them; token values are kept as they are. `/` in the result (from `{relpath}` or the
+/// pattern itself) makes subfolders. Unknown tokens are kept as text; callers validate
+/// patterns up front.
+pub(crate) fn render_pattern(
+    pattern: &str,
+    input: &Path,
+    profile: &Profile,
+    container: &str,
+    ctx: &PatternContext,
+) -> String {
+    let name = MediaName::parse(file_stem(input));
+    let mut pieces = Vec::new();
+    let mut rest = pattern;
+
+    while let Some(open) = rest.find('{') {
+        pieces.push(Piece::Text(rest[..open].to_string()));
+        let Some(len) = rest[open..].find('}') else {
+            rest = &rest[open..];
+            break;
+        };
+        let token = &rest[open + 1..open + len];
+        pieces.push(
+            match token_value(token, input, profile, container, ctx, &name) {
+                Some(value) if value.is_empty() => Piece::Gap,
+                Some(value) => Piece::Value(value),
+                None => Piece::Text(rest[open..=open + len].to_string()),
+            },
+        );
+        rest = &rest[open + len + 1..];
+    }
+    pieces.push(Piece::Text(rest.to_string()));
+    close_gaps(&mut pieces);
+
+    pieces_str(&pieces)
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// A pattern being rendered: its own text, token values, and tokens with no value
+enum Piece {
+    Text(String),
+    Value(String),
+    Gap,
+}
+
+impl Piece {
+    fn as_str(&self) -> &str {
+        match self {
+            Piece::Text(text) | Piece::Value(text) => text,
+            Piece::Gap => "",
+        }
+    }
+}
+
+const SEPARATORS: &[char] = &[' ', '-', '.', '_'];
+
+/// Drop what each gap leaves behind in the pattern text next to it: `()` and `[]` around
+/// nothing, a doubled separator, and separators at either end of a path segment or just
+/// inside a bracket. Token values are never changed.
+fn close_gaps(pieces: &mut Vec<Piece>) {
+    pieces.retain(|piece| !matches!(piece, Piece::Text(text) if text.is_empty()));
+    pieces.dedup_by(|a, b| matches!((a, b), (Piece::Gap, Piece::Gap)));
+
+    while let Some(gap) = pieces.iter().position(|piece| matches!(piece, Piece::Gap)) {
+        let is_text = |at: usize| matches!(pieces.get(at), Some(Piece::Text(_)));
+        let left_at = gap.checked_sub(1).filter(|&at| is_text(at));
+        let right_at = Some(gap + 1).filter(|&at| is_text(at));
+        let text_at = |at: Option<usize>| at.map_or("", |at| pieces[at].as_str());
+        let mut left = text_at(left_at).to_string();
+        let mut right = text_at(right_at).to_string();
+        let start = left_at.unwrap_or(gap);
+        let end = right_at.map_or(gap + 1, |at| at + 1);
+
+        // Whether nothing but a path separator comes before / after the two texts
+        let segment_before = pieces_str(&pieces[..start]);
+        let segment_after = pieces_str(&pieces[end..]);
+        let open_before = segment_before.is_empty() || segment_before.ends_with('/');
+        let open_after = segment_after.is_empty() || segment_after.starts_with('/');
+
+        while let Some(close) = [('(', ')'), ('[', ']')]
+            .iter()
+            .find(|(open, close)| {
+                left.trim_end().ends_with(*open) && right.trim_start().starts_with(*close)
+            })
+            .map(|(_, close)| *close)
+        {
+            let kept = left.trim_end();
+            left = kept[..kept.len() - 1].to_string();
+            right = right.trim_start()[close.len_utf8()..].to_string();
+        }
+
+        let left_rest = left.trim_end_matches(SEPARATORS);
+        let right_rest = right.trim_start_matches(SEPARATORS);
+        let at_start =
+            (left_rest.is_empty() && open_before) || left_rest.ends_with(['/', '(', '[']);
+        let at_end =
+            (right_rest.is_empty() && open_after) || right_rest.starts_with(['/', ')', ']']);
+        let (left_sep, right_sep) = (
+            &left[left_rest.len()..],
+            &right[..right.len() - right_rest.len()],
+        );
+        let sep = if at_start || at_end {
+            ""
+        } else if right_sep.len() > left_sep.len() {
+            right_sep
+        } else {
+            left_sep
+        };
+        let joined = format!("{}{}{}", left_rest, sep, right_rest);
+
+        // The texts either side become one, so a gap further on sees all of it
+        pieces.splice(start..end, [Piece::Text(joined)]);
+    }
+}
+
+fn pieces_str(pieces: &[Piece]) -> String {
+    pieces.iter().map(Piece::as_str).collect()
+}
+
+fn token_value(
+    token: &str,
+    input: &Path,
+    profile: &Profile,
+    container: &str,
+    ctx: &PatternContext,
+    name: &MediaName,
+) -> Option<String> {
+    let video = ctx.info.and_then(|info| info.primary_video());
+    let number = |n: Option<u32>| n.map(|n| n.to_string()).unwrap_or_default();
+    let padded = |n: Option<u32>| n.map(|n| format!("{:02}", n)).unwrap_or_default();
+
+    let value = match token {
+        "basename" => file_stem(input).to_string(),
+        "filename" => os_str(input.file_name()).unwrap_or("output").to_string(),
+        "profile" => profile.suffix.clone(),
+        "ext" => container.to_string(),
+        "parent" => os_str(input.parent().and_then(Path::file_name))
+            .unwrap_or_default()
+            .to_string(),
+        "relpath" => ctx
+            .relpath
+            .map(|rel| {
+                rel.components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .unwrap_or_default(),
+        "height" => number(video.and_then(|v| v.height)),
+        "width" => number(video.and_then(|v| v.width)),
+        "codec" => match profile.codec {
+            Codec::Av1(_) => "av1",
+            Codec::Vp9(_) => "vp9",
+        }
+        .to_string(),
+        "encoder" => profile.resolved_encoder_id(),
+        "crf" | "quality" => profile_quality(profile).to_string(),
+        "vmaf" => {
+            if profile.vmaf_enabled {
+                format!("{}", profile.vmaf_target)
+            } else {
+                String::new()
+            }
+        }
+        "date" => chrono::Local::now().format("%Y-%m-%d").to_string(),
+        "hdr" => {
+            if ctx.info.is_some_and(|info| info.is_hdr()) {
+                "HDR".to_string()
+            } else {
+                String::new()
+            }
+        }
+        "title" => name.title.clone(),
+        "year" => number(name.year),
+        "season" => padded(name.season),
+        "episode" => padded(name.episode),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// The quality value the encoder is given: CRF in software, the hardware quality otherwise
+fn profile_quality(profile: &Profile) -> u32 {
+    match &profile.codec {
+        _ if !profile.use_hardware_encoding => profile.crf,
+        Codec::Av1(av1) => av1.hw_cq,
+        Codec::Vp9(_) => profile.hw_global_quality,
+    }
+}
+
+fn file_stem(path: &Path) -> &str {
+    os_str(path.file_stem()).unwrap_or("output")
+}
+
+fn os_str(s: Option<&std::ffi::OsStr>) -> Option<&str> {
+    s.and_then(|s| s.to_str())
+}
+
+/// Title, year, season and episode read from a release-style filename, e.g.
+/// `Show.Name.S01E02.1080p` or `Movie Title (2019)`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaName {
+    pub title: String,
+    pub year: Option<u32>,
+    pub season: Option<u32>,
+    pub episode: Option<u32>,
+}
+
+impl MediaName {
+    pub fn parse(stem: &str) -> Self {
+        let words: Vec<&str> = stem
+            .split([' ', '.', '_'])
+            .filter(|w| !w.is_empty())
+            .collect();
+
+        let mut name = MediaName::default();
+        let mut episode_at = words.len();
+        // (word index, year, in brackets)
+        let mut years = Vec::new();
+        for (idx, word) in words.iter().enumerate() {
+            let bare = word.trim_matches(['(', ')', '[', ']']);
+            if let Some((season, episode)) = parse_episode(bare) {
+                name.season = Some(season);
+                name.episode = Some(episode);
+                episode_at = idx;
+                break;
+            }
+            // A leading number is the title ("1917 (2019)")
+            if idx > 0 && bare.len() == 4 {
+                if let Ok(year) = bare.parse::<u32>() {
+                    if (1900..=2099).contains(&year) {
+                        years.push((idx, year, bare.len() != word.len()));
+                    }
+                }
+            }
+        }
+
+        // "Blade Runner 2049 (2017)": a bracketed year wins, else the last one
+        let year = years
+            .iter()
+            .find(|(_, _, bracketed)| *bracketed)
+            .or(years.last());
+        name.year = year.map(|(_, year, _)| *year);
+
+        let title_end = year.map_or(episode_at, |(idx, _, _)| (*idx).min(episode_at));
+        name.title = words[..title_end]
+            .iter()
+            .copied()
+            .filter(|w| *w != "-")
+            .collect::<Vec<_>>()
+            .join(" ");
+        name
+    }
+}
+
+/// `S01E02` or `1x02` (case-insensitive); extra parts like `E03` are ignored
+fn parse_episode(word: &str) -> Option<(u32, u32)> {
+    let word = word.to_ascii_lowercase();
+    let (season, episode) = match word.strip_prefix('s') {
+        Some(rest) => rest.split_once('e')?,
+        None => word.split_once('x')?,
+    };
+    let episode_len = episode
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(episode.len());
+    if !(1..=2).contains(&season.len()) || !(1..=3).contains(&episode_len) {
+        return None;
+    }
+    Some((season.parse().ok()?, episode[..episode_len].parse().ok()?))
+}
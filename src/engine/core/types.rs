@@ -1,3 +1,5 @@
+use super::segments::SegmentPlan;
+use crate::engine::history::EncodeReport;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use uuid::Uuid;
@@ -20,9 +22,18 @@ pub struct VideoJob {
     pub profile: String,
     pub status: JobStatus,
 
+    #[serde(default)]
+    pub profile_rule: Option<String>, // Config rule that picked `profile`, if any
+
     #[serde(default)]
     pub overwrite: bool, // Whether to overwrite existing output file
 
+    #[serde(default)]
+    pub priority: i32, // Higher runs first; equal priorities run in queue order
+
+    #[serde(default)]
+    pub origin: Option<PathBuf>, // Directory or file this job was queued from
+
     // Derived / runtime
     pub duration_s: Option<f64>,
     pub progress_pct: f64,
@@ -53,12 +64,21 @@ pub struct VideoJob {
     #[serde(default)]
     pub calibrated_quality: Option<u32>, // Calibrated quality setting (CRF or global_quality)
     #[serde(default)]
+    pub calibrated_hw: Option<bool>, // Whether calibrated_quality is a hardware quality, not a CRF
+    #[serde(default)]
     pub vmaf_partial_scores: Vec<f32>, // Individual window scores for progressive averaging
 
+    // Segment mode: segments finished so far, kept so an interrupted encode resumes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segments: Option<SegmentPlan>,
+
     #[serde(skip)]
     pub calibrating_total_steps: Option<u32>, // Total calibration windows across attempts
     #[serde(skip)]
     pub calibrating_completed_steps: u32, // Completed calibration windows
+
+    #[serde(skip)] // Encoder, timings and source of the last run, for the history
+    pub report: Option<Box<EncodeReport>>,
 }
 
 impl VideoJob {
@@ -70,7 +90,10 @@ impl VideoJob {
             output_path,
             profile,
             status: JobStatus::Pending,
+            profile_rule: None,
             overwrite: false, // Default to no overwrite
+            priority: 0,
+            origin: None,
             duration_s: None,
             progress_pct: 0.0,
             out_time_s: 0.0,
@@ -87,11 +110,19 @@ impl VideoJob {
             vmaf_target: None,
             vmaf_result: None,
             calibrated_quality: None,
+            calibrated_hw: None,
             vmaf_partial_scores: Vec::new(),
+            segments: None,
             calibrating_total_steps: None,
             calibrating_completed_steps: 0,
+            report: None,
         }
     }
+
+    /// The report of the current run, started if there is none
+    pub fn report_mut(&mut self) -> &mut EncodeReport {
+        self.report.get_or_insert_with(Default::default)
+    }
 }
 
 /// Parser for ffmpeg progress output (key=value format)
@@ -0,0 +1,171 @@
+// Newline-delimited JSON event stream for scripting and monitoring wrappers
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use super::VideoJob;
+use super::worker::WorkerMessage;
+
+/// One line of the event stream: the worker message plus enough job context
+/// that a consumer doesn't have to track job IDs itself.
+#[derive(Serialize)]
+struct EventRecord<'a> {
+    timestamp: String,
+    #[serde(flatten)]
+    message: &'a WorkerMessage,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    input_path: Option<&'a Path>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_path: Option<&'a Path>,
+}
+
+/// Writes every `WorkerMessage` as one JSON object per line (NDJSON)
+pub struct EventSink {
+    writer: Box<dyn Write + Send>,
+}
+
+impl EventSink {
+    /// Stream events to stdout
+    pub fn stdout() -> Self {
+        Self::from_writer(std::io::stdout())
+    }
+
+    /// Stream events to a file or FIFO (appends to existing regular files)
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open event stream {}", path.display()))?;
+        Ok(Self::from_writer(file))
+    }
+
+    pub fn from_writer<W: Write + Send + 'static>(writer: W) -> Self {
+        Self {
+            writer: Box::new(writer),
+        }
+    }
+
+    /// Serialize one message, flushing so readers on a pipe see it immediately
+    pub fn emit(&mut self, msg: &WorkerMessage, job: Option<&VideoJob>) -> Result<()> {
+        let record = EventRecord {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            message: msg,
+            input_path: job.map(|j| j.input_path.as_path()),
+            output_path: job.map(|j| j.output_path.as_path()),
+        };
+
+        let line = serde_json::to_string(&record).context("Failed to serialize event")?;
+        writeln!(self.writer, "{}", line).context("Failed to write event")?;
+        self.writer
+            .flush()
+            .context("Failed to flush event stream")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::JobStatus;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn lines(buf: &SharedBuf) -> Vec<serde_json::Value> {
+        let bytes = buf.0.lock().unwrap().clone();
+        String::from_utf8(bytes)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).expect("each line should be valid JSON"))
+            .collect()
+    }
+
+    #[test]
+    fn test_emit_writes_one_tagged_object_per_line() {
+        let buf = SharedBuf::default();
+        let mut sink = EventSink::from_writer(buf.clone());
+        let job = VideoJob::new(
+            PathBuf::from("/videos/a.mp4"),
+            PathBuf::from("/videos/a.webm"),
+            "vp9-good".to_string(),
+        );
+
+        sink.emit(&WorkerMessage::JobStarted { job_id: job.id }, Some(&job))
+            .unwrap();
+        sink.emit(
+            &WorkerMessage::ProgressUpdate {
+                job_id: job.id,
+                progress_pct: 42.5,
+                out_time_s: 10.0,
+                fps: Some(30.0),
+                speed: None,
+                bitrate_kbps: None,
+                size_bytes: Some(1024),
+                vmaf_result: None,
+                vmaf_target: None,
+                status: JobStatus::Running,
+            },
+            Some(&job),
+        )
+        .unwrap();
+        sink.emit(&WorkerMessage::WorkerIdle { worker_id: 3 }, None)
+            .unwrap();
+
+        let events = lines(&buf);
+        assert_eq!(events.len(), 3);
+
+        assert_eq!(events[0]["event"], "job_started");
+        assert_eq!(events[0]["job_id"], job.id.to_string());
+        assert_eq!(events[0]["input_path"], "/videos/a.mp4");
+        assert!(events[0]["timestamp"].is_string());
+
+        assert_eq!(events[1]["event"], "progress_update");
+        assert_eq!(events[1]["progress_pct"], 42.5);
+        assert_eq!(events[1]["status"], "Running");
+        assert_eq!(events[1]["size_bytes"], 1024);
+
+        assert_eq!(events[2]["event"], "worker_idle");
+        assert_eq!(events[2]["worker_id"], 3);
+        assert!(events[2].get("input_path").is_none());
+    }
+
+    #[test]
+    fn test_emit_calibration_step() {
+        let buf = SharedBuf::default();
+        let mut sink = EventSink::from_writer(buf.clone());
+        let job_id = uuid::Uuid::new_v4();
+
+        sink.emit(
+            &WorkerMessage::CalibrationStep {
+                job_id,
+                completed_steps: 2,
+                total_steps: 6,
+                vmaf_result: Some(94.1),
+            },
+            None,
+        )
+        .unwrap();
+
+        let events = lines(&buf);
+        assert_eq!(events[0]["event"], "calibration_step");
+        assert_eq!(events[0]["completed_steps"], 2);
+        assert_eq!(events[0]["total_steps"], 6);
+    }
+}
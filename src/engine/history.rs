@@ -0,0 +1,368 @@
+// Encode history: every finished or failed job, appended to `history.jsonl` in the data
+// dir so the record outlives queues and `.enc_state`
+
+use crate::engine::probe::{MediaInfo, StreamKind};
+use crate::engine::{JobStatus, Profile, VideoJob, data_dir};
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Lines of the error kept in a failed job's record
+const ERROR_TAIL_LINES: usize = 20;
+
+/// What the engine learned while encoding a job, for its history record
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EncodeReport {
+    pub source: Option<SourceSummary>,
+    /// The profile the encode ran with, calibrated quality included
+    pub profile: Option<Profile>,
+    /// Quality picked by Auto-VMAF calibration, fresh or stored
+    pub calibrated_quality: Option<u32>,
+    /// Video encoder of the command that ran last
+    pub encoder: Option<String>,
+    /// Encoder that failed to initialize before `encoder` was tried (QSV → VAAPI)
+    pub fallback_from: Option<String>,
+    /// Number of segments, for encodes in segment mode
+    pub segments: Option<usize>,
+    pub timings: PhaseTimings,
+}
+
+/// Wall-clock seconds spent in each phase of a job
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PhaseTimings {
+    /// Auto-VMAF calibration, when it ran
+    pub calibration_secs: Option<f64>,
+    pub encode_secs: f64,
+    /// Checking the partial output and moving it into place
+    pub finish_secs: f64,
+    pub total_secs: f64,
+}
+
+/// The parts of a source probe worth keeping next to an encode
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SourceSummary {
+    pub container: String,
+    pub duration_s: Option<f64>,
+    pub size_bytes: Option<u64>,
+    pub bitrate_bps: Option<u64>,
+    pub video_codec: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub frame_rate: Option<f64>,
+    pub bit_depth: Option<u32>,
+    pub hdr: bool,
+    pub audio_streams: usize,
+    pub subtitle_streams: usize,
+}
+
+impl SourceSummary {
+    pub fn from_info(info: &MediaInfo) -> Self {
+        let video = info.primary_video();
+        Self {
+            container: info.container.format_name.clone(),
+            duration_s: info.container.duration,
+            size_bytes: info.container.size_bytes,
+            bitrate_bps: info.container.bitrate_bps,
+            video_codec: video.and_then(|v| v.codec_name.clone()),
+            width: video.and_then(|v| v.width),
+            height: video.and_then(|v| v.height),
+            frame_rate: video.and_then(|v| v.frame_rate),
+            bit_depth: video.and_then(|v| v.bit_depth),
+            hdr: info.is_hdr(),
+            audio_streams: info.streams_of(StreamKind::Audio).count(),
+            subtitle_streams: info.streams_of(StreamKind::Subtitle).count(),
+        }
+    }
+}
+
+/// How a recorded job ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryStatus {
+    Done,
+    Failed,
+}
+
+/// One line of `history.jsonl`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    /// Local time the job ended (RFC 3339)
+    pub finished_at: String,
+    pub status: HistoryStatus,
+    pub input_path: PathBuf,
+    pub output_path: PathBuf,
+    pub profile: String,
+    #[serde(default)]
+    pub profile_rule: Option<String>,
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(default)]
+    pub source: Option<SourceSummary>,
+    /// Every profile setting the encode used. Kept as plain JSON so records written
+    /// by older versions still load after `Profile` changes.
+    #[serde(default)]
+    pub profile_settings: Option<serde_json::Value>,
+    #[serde(default)]
+    pub encoder: Option<String>,
+    #[serde(default)]
+    pub fallback_from: Option<String>,
+    #[serde(default)]
+    pub segments: Option<usize>,
+    #[serde(default)]
+    pub timings: PhaseTimings,
+    #[serde(default)]
+    pub input_bytes: Option<u64>,
+    #[serde(default)]
+    pub output_bytes: Option<u64>,
+    #[serde(default)]
+    pub vmaf_target: Option<f32>,
+    #[serde(default)]
+    pub vmaf_result: Option<f32>,
+    #[serde(default)]
+    pub calibrated_quality: Option<u32>,
+    /// Last lines of the error, for failed jobs
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl HistoryRecord {
+    /// Record for a job that just finished or failed (None for any other status)
+    pub fn from_job(job: &VideoJob) -> Option<Self> {
+        let status = match job.status {
+            JobStatus::Done => HistoryStatus::Done,
+            JobStatus::Failed => HistoryStatus::Failed,
+            _ => return None,
+        };
+        let report = job.report.as_deref().cloned().unwrap_or_default();
+        let absolute =
+            |path: &Path| std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let file_size = |path: &Path| fs::metadata(path).ok().map(|m| m.len());
+
+        Some(Self {
+            finished_at: chrono::Local::now().to_rfc3339(),
+            status,
+            input_path: absolute(&job.input_path),
+            output_path: absolute(&job.output_path),
+            profile: job.profile.clone(),
+            profile_rule: job.profile_rule.clone(),
+            attempts: job.attempts,
+            input_bytes: report
+                .source
+                .as_ref()
+                .and_then(|s| s.size_bytes)
+                .or_else(|| file_size(&job.input_path)),
+            output_bytes: match status {
+                HistoryStatus::Done => file_size(&job.output_path).or(job.size_bytes),
+                HistoryStatus::Failed => None,
+            },
+            source: report.source,
+            profile_settings: report.profile.and_then(|p| serde_json::to_value(p).ok()),
+            encoder: report.encoder,
+            fallback_from: report.fallback_from,
+            segments: report.segments,
+            timings: report.timings,
+            vmaf_target: job.vmaf_target,
+            vmaf_result: job.vmaf_result,
+            calibrated_quality: report.calibrated_quality.or(job.calibrated_quality),
+            error: match status {
+                HistoryStatus::Done => None,
+                HistoryStatus::Failed => job.last_error.as_deref().map(error_tail),
+            },
+        })
+    }
+
+    /// Local date the job ended
+    pub fn finished_date(&self) -> Option<NaiveDate> {
+        chrono::DateTime::parse_from_rfc3339(&self.finished_at)
+            .ok()
+            .map(|t| t.with_timezone(&chrono::Local).date_naive())
+    }
+}
+
+/// Filters for `ffdash history`; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct HistoryQuery {
+    /// Source or output file, or a directory containing them
+    pub path: Option<PathBuf>,
+    /// First day to include
+    pub since: Option<NaiveDate>,
+    /// Last day to include
+    pub until: Option<NaiveDate>,
+    pub status: Option<HistoryStatus>,
+    /// Profile name (case-insensitive)
+    pub profile: Option<String>,
+}
+
+impl HistoryQuery {
+    pub fn matches(&self, record: &HistoryRecord) -> bool {
+        let path_ok = self.path.as_ref().is_none_or(|path| {
+            record.input_path.starts_with(path) || record.output_path.starts_with(path)
+        });
+        let date_ok = (self.since.is_none() && self.until.is_none())
+            || record.finished_date().is_some_and(|date| {
+                self.since.is_none_or(|since| date >= since)
+                    && self.until.is_none_or(|until| date <= until)
+            });
+        path_ok
+            && date_ok
+            && self.status.is_none_or(|status| record.status == status)
+            && self
+                .profile
+                .as_ref()
+                .is_none_or(|profile| record.profile.eq_ignore_ascii_case(profile))
+    }
+}
+
+/// Path of the history file (`~/.local/share/ffdash/history.jsonl` on Linux)
+pub fn history_path() -> Result<PathBuf> {
+    Ok(data_dir()?.join("history.jsonl"))
+}
+
+/// Append `record` to the history file
+pub fn append(record: &HistoryRecord) -> Result<()> {
+    append_to(&history_path()?, record)
+}
+
+/// Append a record for `job` to the history file if it has finished or failed
+pub fn record_job(job: &VideoJob) -> Result<()> {
+    match HistoryRecord::from_job(job) {
+        Some(record) => append(&record),
+        None => Ok(()),
+    }
+}
+
+/// Append `record` to `path` as one line. The line goes out in a single append-mode
+/// write, so records from several ffdash instances don't interleave.
+pub fn append_to(path: &Path, record: &HistoryRecord) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let mut line = serde_json::to_string(record).context("Failed to serialize history record")?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Every record in `path`, oldest first. A missing file is an empty history; lines
+/// that don't parse (a write cut short by a crash) are skipped.
+pub fn read_from(path: &Path) -> Result<Vec<HistoryRecord>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+fn error_tail(error: &str) -> String {
+    let lines: Vec<&str> = error.lines().collect();
+    lines[lines.len().saturating_sub(ERROR_TAIL_LINES)..].join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finished_job(name: &str, status: JobStatus) -> VideoJob {
+        let mut job = VideoJob::new(
+            PathBuf::from(format!("/media/shows/{}.mkv", name)),
+            PathBuf::from(format!("/media/out/{}.webm", name)),
+            "vp9-good".to_string(),
+        );
+        job.status = status;
+        job
+    }
+
+    #[test]
+    fn test_history_records_round_trip_and_filter() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("history.jsonl");
+        assert!(read_from(&path).unwrap().is_empty());
+
+        let mut done = finished_job("a", JobStatus::Done);
+        done.report = Some(Box::new(EncodeReport {
+            encoder: Some("vp9_vaapi".to_string()),
+            fallback_from: Some("vp9_qsv".to_string()),
+            ..Default::default()
+        }));
+        let mut failed = finished_job("b", JobStatus::Failed);
+        failed.profile = "av1-svt".to_string();
+        let error: Vec<String> = (1..=30).map(|n| format!("line {}", n)).collect();
+        failed.last_error = Some(error.join("\n"));
+        assert!(HistoryRecord::from_job(&finished_job("c", JobStatus::Running)).is_none());
+
+        for job in [&done, &failed] {
+            append_to(&path, &HistoryRecord::from_job(job).unwrap()).unwrap();
+        }
+        // A line cut short by a crash doesn't hide the others
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"finished_at\":").unwrap();
+
+        let records = read_from(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].encoder.as_deref(), Some("vp9_vaapi"));
+        assert_eq!(records[0].fallback_from.as_deref(), Some("vp9_qsv"));
+        assert_eq!(records[0].error, None);
+        let tail = records[1].error.as_deref().unwrap();
+        assert_eq!(tail.lines().count(), ERROR_TAIL_LINES);
+        assert!(tail.ends_with("line 30"));
+
+        let count = |query: HistoryQuery| records.iter().filter(|r| query.matches(r)).count();
+        let today = chrono::Local::now().date_naive();
+        assert_eq!(count(HistoryQuery::default()), 2);
+        assert_eq!(
+            count(HistoryQuery {
+                status: Some(HistoryStatus::Failed),
+                ..Default::default()
+            }),
+            1
+        );
+        assert_eq!(
+            count(HistoryQuery {
+                profile: Some("VP9-GOOD".to_string()),
+                ..Default::default()
+            }),
+            1
+        );
+        assert_eq!(
+            count(HistoryQuery {
+                path: Some(PathBuf::from("/media/out")),
+                ..Default::default()
+            }),
+            2
+        );
+        assert_eq!(
+            count(HistoryQuery {
+                path: Some(PathBuf::from("/media/shows/a.mkv")),
+                ..Default::default()
+            }),
+            1
+        );
+        assert_eq!(
+            count(HistoryQuery {
+                since: today.succ_opt(),
+                ..Default::default()
+            }),
+            0
+        );
+        assert_eq!(
+            count(HistoryQuery {
+                since: Some(today),
+                until: Some(today),
+                ..Default::default()
+            }),
+            2
+        );
+    }
+}
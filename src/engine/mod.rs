@@ -1,10 +1,15 @@
 // Core encoding engine - independent of UI
 
+pub mod batch;
 pub mod core;
+pub mod events;
 pub mod hardware;
+pub mod history;
 pub mod probe;
+pub mod probe_cache;
 pub mod validate;
 pub mod vmaf;
+pub mod watch;
 pub mod worker;
 
 #[cfg(feature = "dev-tools")]
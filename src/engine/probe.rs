@@ -1,8 +1,118 @@
 // Input probing using ffprobe
 
 use serde::{Deserialize, Serialize};
+use std::io::{self, Read};
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Output, Stdio};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+/// ffprobe limits, configured under `[probe]` in config.toml
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProbeSettings {
+    /// Files probed in parallel while building a queue (unset = CPU count, at most 8)
+    pub workers: Option<usize>,
+    /// Kill an ffprobe that takes longer than this (0 = wait forever)
+    pub timeout_secs: u64,
+    /// Reuse results from the on-disk probe cache for unchanged files
+    pub cache: bool,
+}
+
+impl ProbeSettings {
+    const DEFAULT: Self = Self {
+        workers: None,
+        timeout_secs: 30,
+        cache: true,
+    };
+
+    /// Worker count with the automatic default applied
+    pub fn effective_workers(&self) -> usize {
+        self.workers
+            .unwrap_or_else(|| {
+                std::thread::available_parallelism()
+                    .map(|n| n.get().min(8))
+                    .unwrap_or(4)
+            })
+            .max(1)
+    }
+}
+
+impl Default for ProbeSettings {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+static SETTINGS: RwLock<ProbeSettings> = RwLock::new(ProbeSettings::DEFAULT);
+
+/// Apply `[probe]` settings for every later ffprobe run in this process
+pub fn configure(settings: ProbeSettings) {
+    if let Ok(mut current) = SETTINGS.write() {
+        *current = settings;
+    }
+}
+
+/// The settings passed to `configure` (defaults until then)
+pub fn settings() -> ProbeSettings {
+    SETTINGS.read().map(|s| *s).unwrap_or_default()
+}
+
+/// Run an ffprobe command to completion, killing it once it outlives the configured
+/// timeout so a hung file on a flaky mount can't stall a scan
+pub fn run_ffprobe(cmd: &mut Command) -> io::Result<Output> {
+    let timeout_secs = settings().timeout_secs;
+    if timeout_secs == 0 {
+        return cmd.output();
+    }
+    run_with_timeout(cmd, Duration::from_secs(timeout_secs))
+}
+
+fn run_with_timeout(cmd: &mut Command, timeout: Duration) -> io::Result<Output> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Drain both pipes on their own threads so a chatty child can't block on a full pipe
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let mut poll = Duration::from_millis(1);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("timed out after {}s", timeout.as_secs()),
+            ));
+        }
+        std::thread::sleep(poll);
+        poll = (poll * 2).min(Duration::from_millis(50));
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InputInfo {
@@ -32,20 +142,21 @@ impl Default for InputInfo {
 /// Probe input file using ffprobe to get video metadata
 pub fn probe_input_info(input_path: &Path) -> Result<InputInfo, String> {
     // Run ffprobe to get JSON output with video stream info
-    let output = Command::new("ffprobe")
-        .args([
-            "-v",
-            "quiet",
-            "-print_format",
-            "json",
-            "-show_format",
-            "-show_streams",
-            "-select_streams",
-            "v:0", // First video stream only
-        ])
-        .arg(input_path)
-        .output()
-        .map_err(|e| format!("Failed to run ffprobe: {}", e))?;
+    let output = run_ffprobe(
+        Command::new("ffprobe")
+            .args([
+                "-v",
+                "quiet",
+                "-print_format",
+                "json",
+                "-show_format",
+                "-show_streams",
+                "-select_streams",
+                "v:0", // First video stream only
+            ])
+            .arg(input_path),
+    )
+    .map_err(|e| format!("Failed to run ffprobe: {}", e))?;
 
     if !output.status.success() {
         return Err(format!(
@@ -122,6 +233,342 @@ pub fn probe_input_info(input_path: &Path) -> Result<InputInfo, String> {
     })
 }
 
+/// Full inventory of a media file: container, every stream and chapters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaInfo {
+    pub container: ContainerInfo,
+    pub streams: Vec<StreamInfo>,
+    pub chapters: Vec<ChapterInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerInfo {
+    pub format_name: String,
+    pub format_long_name: Option<String>,
+    pub duration: Option<f64>,
+    pub size_bytes: Option<u64>,
+    pub bitrate_bps: Option<u64>,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamKind {
+    Video,
+    Audio,
+    Subtitle,
+    Attachment,
+    Data,
+    Unknown,
+}
+
+/// Disposition flags ffprobe reports per stream (only the ones we act on)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Disposition {
+    pub default: bool,
+    pub forced: bool,
+    pub attached_pic: bool,
+    pub hearing_impaired: bool,
+    pub visual_impaired: bool,
+    pub comment: bool,
+    pub original: bool,
+    pub dub: bool,
+}
+
+/// HDR signalling: transfer function plus any static metadata side data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HdrInfo {
+    /// "pq" (HDR10/HDR10+/Dolby Vision) or "hlg"
+    pub transfer: String,
+    /// Mastering display luminance range in nits (min, max)
+    pub mastering_luminance: Option<(f64, f64)>,
+    /// MaxCLL / MaxFALL in nits
+    pub max_cll: Option<u32>,
+    pub max_fall: Option<u32>,
+    pub dolby_vision: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamInfo {
+    pub index: u32,
+    pub kind: StreamKind,
+    pub codec_name: Option<String>,
+    pub codec_long_name: Option<String>,
+    pub profile: Option<String>,
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub disposition: Disposition,
+    pub bitrate_bps: Option<u64>,
+
+    // Video
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub frame_rate: Option<f64>,
+    pub pix_fmt: Option<String>,
+    pub bit_depth: Option<u32>,
+    pub color_transfer: Option<String>,
+    pub color_primaries: Option<String>,
+    pub hdr: Option<HdrInfo>,
+
+    // Audio
+    pub channels: Option<u32>,
+    pub channel_layout: Option<String>,
+    pub sample_rate: Option<u32>,
+
+    // Attachments (fonts, cover art in mkv)
+    pub filename: Option<String>,
+    pub mime_type: Option<String>,
+}
+
+impl StreamInfo {
+    /// Cover art / thumbnails are reported as video streams but aren't real video
+    pub fn is_attached_picture(&self) -> bool {
+        self.disposition.attached_pic
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterInfo {
+    pub id: i64,
+    pub start: f64,
+    pub end: f64,
+    pub title: Option<String>,
+}
+
+impl MediaInfo {
+    /// Build from `ffprobe -print_format json -show_format -show_streams -show_chapters` output
+    pub fn from_ffprobe_json(json: &serde_json::Value) -> Self {
+        let format = &json["format"];
+        let container = ContainerInfo {
+            format_name: format["format_name"]
+                .as_str()
+                .unwrap_or("unknown")
+                .to_string(),
+            format_long_name: json_string(&format["format_long_name"]),
+            duration: json_f64(&format["duration"]),
+            size_bytes: json_u64(&format["size"]),
+            bitrate_bps: json_u64(&format["bit_rate"]),
+            title: json_string(&format["tags"]["title"]),
+        };
+
+        let streams = json["streams"]
+            .as_array()
+            .map(|streams| streams.iter().map(parse_stream).collect())
+            .unwrap_or_default();
+
+        let chapters = json["chapters"]
+            .as_array()
+            .map(|chapters| {
+                chapters
+                    .iter()
+                    .map(|c| ChapterInfo {
+                        id: c["id"].as_i64().unwrap_or(0),
+                        start: json_f64(&c["start_time"]).unwrap_or(0.0),
+                        end: json_f64(&c["end_time"]).unwrap_or(0.0),
+                        title: json_string(&c["tags"]["title"]),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            container,
+            streams,
+            chapters,
+        }
+    }
+
+    /// Streams of one kind, in file order
+    pub fn streams_of(&self, kind: StreamKind) -> impl Iterator<Item = &StreamInfo> {
+        self.streams.iter().filter(move |s| s.kind == kind)
+    }
+
+    /// First real video stream (skips cover art / attached pictures)
+    pub fn primary_video(&self) -> Option<&StreamInfo> {
+        self.streams_of(StreamKind::Video)
+            .find(|s| !s.is_attached_picture())
+    }
+
+    pub fn is_hdr(&self) -> bool {
+        self.primary_video().is_some_and(|v| v.hdr.is_some())
+    }
+}
+
+/// Probe every stream, chapter and the container of a media file
+pub fn probe_media_info(input_path: &Path) -> Result<MediaInfo, String> {
+    let output = run_ffprobe(
+        Command::new("ffprobe")
+            .args([
+                "-v",
+                "quiet",
+                "-print_format",
+                "json",
+                "-show_format",
+                "-show_streams",
+                "-show_chapters",
+            ])
+            .arg(input_path),
+    )
+    .map_err(|e| format!("Failed to run ffprobe: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "ffprobe failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Failed to parse ffprobe JSON: {}", e))?;
+
+    Ok(MediaInfo::from_ffprobe_json(&json))
+}
+
+fn parse_stream(stream: &serde_json::Value) -> StreamInfo {
+    let kind = match stream["codec_type"].as_str() {
+        Some("video") => StreamKind::Video,
+        Some("audio") => StreamKind::Audio,
+        Some("subtitle") => StreamKind::Subtitle,
+        Some("attachment") => StreamKind::Attachment,
+        Some("data") => StreamKind::Data,
+        _ => StreamKind::Unknown,
+    };
+
+    let flag = |name: &str| stream["disposition"][name].as_i64() == Some(1);
+    let disposition = Disposition {
+        default: flag("default"),
+        forced: flag("forced"),
+        attached_pic: flag("attached_pic"),
+        hearing_impaired: flag("hearing_impaired"),
+        visual_impaired: flag("visual_impaired"),
+        comment: flag("comment"),
+        original: flag("original"),
+        dub: flag("dub"),
+    };
+
+    let tags = &stream["tags"];
+    // Matroska muxers store per-track bitrate as a BPS tag instead of bit_rate
+    let bitrate_bps = json_u64(&stream["bit_rate"])
+        .or_else(|| json_u64(&tags["BPS"]))
+        .or_else(|| json_u64(&tags["BPS-eng"]));
+
+    let pix_fmt = json_string(&stream["pix_fmt"]);
+    let bit_depth = json_u64(&stream["bits_per_raw_sample"])
+        .map(|v| v as u32)
+        .or_else(|| pix_fmt.as_deref().and_then(bit_depth_from_pix_fmt));
+
+    let frame_rate = if kind == StreamKind::Video {
+        stream["r_frame_rate"]
+            .as_str()
+            .and_then(parse_fraction)
+            .or_else(|| stream["avg_frame_rate"].as_str().and_then(parse_fraction))
+    } else {
+        None
+    };
+
+    let color_transfer = json_string(&stream["color_transfer"]);
+
+    StreamInfo {
+        index: stream["index"].as_u64().unwrap_or(0) as u32,
+        kind,
+        codec_name: json_string(&stream["codec_name"]),
+        codec_long_name: json_string(&stream["codec_long_name"]),
+        profile: json_string(&stream["profile"]),
+        language: json_string(&tags["language"]),
+        title: json_string(&tags["title"]),
+        disposition,
+        bitrate_bps,
+        width: stream["width"].as_u64().map(|v| v as u32),
+        height: stream["height"].as_u64().map(|v| v as u32),
+        frame_rate,
+        bit_depth: if kind == StreamKind::Video {
+            bit_depth
+        } else {
+            None
+        },
+        pix_fmt,
+        hdr: parse_hdr(stream, color_transfer.as_deref()),
+        color_transfer,
+        color_primaries: json_string(&stream["color_primaries"]),
+        channels: stream["channels"].as_u64().map(|v| v as u32),
+        channel_layout: json_string(&stream["channel_layout"]),
+        sample_rate: json_u64(&stream["sample_rate"]).map(|v| v as u32),
+        filename: json_string(&tags["filename"]),
+        mime_type: json_string(&tags["mimetype"]),
+    }
+}
+
+/// HDR is signalled by a PQ/HLG transfer; side data adds the static metadata
+fn parse_hdr(stream: &serde_json::Value, color_transfer: Option<&str>) -> Option<HdrInfo> {
+    let transfer = match color_transfer {
+        Some("smpte2084") => "pq",
+        Some("arib-std-b67") => "hlg",
+        _ => return None,
+    };
+
+    let mut hdr = HdrInfo {
+        transfer: transfer.to_string(),
+        mastering_luminance: None,
+        max_cll: None,
+        max_fall: None,
+        dolby_vision: false,
+    };
+
+    for side_data in stream["side_data_list"].as_array().into_iter().flatten() {
+        match side_data["side_data_type"].as_str() {
+            Some("Mastering display metadata") => {
+                let luminance = |key: &str| side_data[key].as_str().and_then(parse_fraction);
+                if let (Some(min), Some(max)) =
+                    (luminance("min_luminance"), luminance("max_luminance"))
+                {
+                    hdr.mastering_luminance = Some((min, max));
+                }
+            }
+            Some("Content light level metadata") => {
+                hdr.max_cll = side_data["max_content"].as_u64().map(|v| v as u32);
+                hdr.max_fall = side_data["max_average"].as_u64().map(|v| v as u32);
+            }
+            Some("DOVI configuration record") => hdr.dolby_vision = true,
+            _ => {}
+        }
+    }
+
+    Some(hdr)
+}
+
+/// Infer bit depth from pixel formats like yuv420p10le / p010le
+fn bit_depth_from_pix_fmt(pix_fmt: &str) -> Option<u32> {
+    if pix_fmt.contains("12") {
+        Some(12)
+    } else if pix_fmt.contains("10") {
+        Some(10)
+    } else if pix_fmt.starts_with("yuv")
+        || pix_fmt.starts_with("nv12")
+        || pix_fmt.starts_with("rgb")
+    {
+        Some(8)
+    } else {
+        None
+    }
+}
+
+fn json_string(value: &serde_json::Value) -> Option<String> {
+    value.as_str().map(|s| s.to_string())
+}
+
+/// ffprobe reports most numbers as strings; accept either form
+fn json_u64(value: &serde_json::Value) -> Option<u64> {
+    value
+        .as_u64()
+        .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
+fn json_f64(value: &serde_json::Value) -> Option<f64> {
+    value
+        .as_f64()
+        .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
 /// Parse a fraction string like "30000/1001" to f64
 fn parse_fraction(s: &str) -> Option<f64> {
     let parts: Vec<&str> = s.split('/').collect();
@@ -143,6 +590,20 @@ fn parse_fraction(s: &str) -> Option<f64> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_run_with_timeout_kills_hung_process() {
+        let started = Instant::now();
+        let err = run_with_timeout(Command::new("sleep").arg("5"), Duration::from_millis(100))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(4));
+
+        let output =
+            run_with_timeout(Command::new("echo").arg("ok"), Duration::from_secs(5)).unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "ok");
+    }
+
     #[test]
     fn test_parse_fraction() {
         assert_eq!(parse_fraction("30/1"), Some(30.0));
@@ -166,4 +627,145 @@ mod tests {
         assert_eq!(parse_fraction("invalid"), None);
         assert_eq!(parse_fraction("30/0"), None);
     }
+
+    fn sample_ffprobe_json() -> serde_json::Value {
+        serde_json::json!({
+            "streams": [
+                {
+                    "index": 0,
+                    "codec_name": "hevc",
+                    "codec_type": "video",
+                    "profile": "Main 10",
+                    "width": 3840,
+                    "height": 2160,
+                    "pix_fmt": "yuv420p10le",
+                    "color_transfer": "smpte2084",
+                    "color_primaries": "bt2020",
+                    "r_frame_rate": "24000/1001",
+                    "disposition": { "default": 1, "attached_pic": 0 },
+                    "side_data_list": [
+                        {
+                            "side_data_type": "Mastering display metadata",
+                            "min_luminance": "50/10000",
+                            "max_luminance": "10000000/10000"
+                        },
+                        {
+                            "side_data_type": "Content light level metadata",
+                            "max_content": 1000,
+                            "max_average": 400
+                        }
+                    ],
+                    "tags": { "BPS": "40000000" }
+                },
+                {
+                    "index": 1,
+                    "codec_name": "truehd",
+                    "codec_type": "audio",
+                    "channels": 8,
+                    "channel_layout": "7.1",
+                    "sample_rate": "48000",
+                    "disposition": { "default": 1 },
+                    "tags": { "language": "eng", "title": "Atmos" }
+                },
+                {
+                    "index": 2,
+                    "codec_name": "subrip",
+                    "codec_type": "subtitle",
+                    "disposition": { "forced": 1 },
+                    "tags": { "language": "ger" }
+                },
+                {
+                    "index": 3,
+                    "codec_name": "mjpeg",
+                    "codec_type": "video",
+                    "width": 600,
+                    "height": 900,
+                    "pix_fmt": "yuvj420p",
+                    "r_frame_rate": "90000/1",
+                    "disposition": { "attached_pic": 1 }
+                },
+                {
+                    "index": 4,
+                    "codec_type": "attachment",
+                    "tags": { "filename": "font.ttf", "mimetype": "font/ttf" }
+                }
+            ],
+            "chapters": [
+                { "id": 1, "start_time": "0.000000", "end_time": "300.500000", "tags": { "title": "Opening" } }
+            ],
+            "format": {
+                "format_name": "matroska,webm",
+                "duration": "5400.250000",
+                "size": "12345678",
+                "bit_rate": "18000000",
+                "tags": { "title": "Sample Film" }
+            }
+        })
+    }
+
+    #[test]
+    fn test_media_info_parses_every_stream_kind() {
+        let info = MediaInfo::from_ffprobe_json(&sample_ffprobe_json());
+
+        assert_eq!(info.streams.len(), 5);
+        assert_eq!(info.container.format_name, "matroska,webm");
+        assert_eq!(info.container.duration, Some(5400.25));
+        assert_eq!(info.container.title.as_deref(), Some("Sample Film"));
+        assert_eq!(info.chapters.len(), 1);
+        assert_eq!(info.chapters[0].title.as_deref(), Some("Opening"));
+        assert_eq!(info.chapters[0].end, 300.5);
+
+        let video = info.primary_video().expect("primary video");
+        assert_eq!(video.index, 0);
+        assert_eq!(video.bit_depth, Some(10));
+        assert_eq!(video.bitrate_bps, Some(40_000_000));
+        assert!((video.frame_rate.unwrap() - 23.976).abs() < 0.001);
+        assert!(video.disposition.default);
+
+        let audio = info.streams_of(StreamKind::Audio).next().unwrap();
+        assert_eq!(audio.channels, Some(8));
+        assert_eq!(audio.channel_layout.as_deref(), Some("7.1"));
+        assert_eq!(audio.language.as_deref(), Some("eng"));
+        assert_eq!(audio.title.as_deref(), Some("Atmos"));
+        assert_eq!(audio.sample_rate, Some(48000));
+
+        let subtitle = info.streams_of(StreamKind::Subtitle).next().unwrap();
+        assert!(subtitle.disposition.forced);
+
+        let cover = &info.streams[3];
+        assert!(cover.is_attached_picture());
+        assert_eq!(cover.bit_depth, Some(8));
+
+        let attachment = info.streams_of(StreamKind::Attachment).next().unwrap();
+        assert_eq!(attachment.filename.as_deref(), Some("font.ttf"));
+        assert_eq!(attachment.mime_type.as_deref(), Some("font/ttf"));
+    }
+
+    #[test]
+    fn test_media_info_hdr_side_data() {
+        let info = MediaInfo::from_ffprobe_json(&sample_ffprobe_json());
+        assert!(info.is_hdr());
+
+        let hdr = info.primary_video().unwrap().hdr.clone().unwrap();
+        assert_eq!(hdr.transfer, "pq");
+        assert_eq!(hdr.mastering_luminance, Some((0.005, 1000.0)));
+        assert_eq!(hdr.max_cll, Some(1000));
+        assert_eq!(hdr.max_fall, Some(400));
+        assert!(!hdr.dolby_vision);
+    }
+
+    #[test]
+    fn test_primary_video_skips_cover_art_only_files() {
+        let json = serde_json::json!({
+            "streams": [
+                { "index": 0, "codec_type": "audio", "codec_name": "flac" },
+                { "index": 1, "codec_type": "video", "codec_name": "png", "disposition": { "attached_pic": 1 } }
+            ],
+            "format": { "format_name": "flac" }
+        });
+
+        let info = MediaInfo::from_ffprobe_json(&json);
+        assert!(info.primary_video().is_none());
+        assert!(!info.is_hdr());
+    }
 }
@@ -0,0 +1,273 @@
+// Persistent ffprobe results, so rescans of unchanged libraries skip ffprobe
+
+use crate::engine::probe::{self, MediaInfo, probe_media_info};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+/// Bumped whenever `MediaInfo` changes shape; older caches are discarded
+const CACHE_VERSION: u32 = 1;
+
+/// The process-wide cache, loaded on first use
+static CACHE: Mutex<Option<ProbeCache>> = Mutex::new(None);
+
+/// What a cached probe was taken from; a changed size or mtime means a stale entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub size: u64,
+    pub mtime_secs: u64,
+    pub mtime_nanos: u32,
+}
+
+impl FileStamp {
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let mtime = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            size: metadata.len(),
+            mtime_secs: mtime.as_secs(),
+            mtime_nanos: mtime.subsec_nanos(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    #[serde(flatten)]
+    stamp: FileStamp,
+    info: MediaInfo,
+}
+
+/// Probe results keyed by canonical path
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProbeCache {
+    version: u32,
+    entries: HashMap<String, CacheEntry>,
+    /// Entries added since the last save
+    #[serde(skip)]
+    changed: Vec<String>,
+}
+
+impl ProbeCache {
+    /// Get the path to the cache file (`~/.cache/ffdash/probe_cache.json` on Linux)
+    pub fn cache_path() -> Result<PathBuf> {
+        let cache_dir = if cfg!(target_os = "macos") {
+            dirs::home_dir()
+                .context("Could not determine home directory")?
+                .join(".cache")
+                .join("ffdash")
+        } else {
+            // XDG_CACHE_HOME on Linux, the local app data folder on Windows
+            dirs::cache_dir()
+                .context("Could not determine cache directory")?
+                .join("ffdash")
+        };
+
+        Ok(cache_dir.join("probe_cache.json"))
+    }
+
+    /// Load the cache, starting empty if it is missing, unreadable or from another version
+    pub fn load_from(path: &Path) -> Self {
+        fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<ProbeCache>(&bytes).ok())
+            .filter(|cache| cache.version == CACHE_VERSION)
+            .unwrap_or_else(|| Self {
+                version: CACHE_VERSION,
+                ..Self::default()
+            })
+    }
+
+    /// Write entries added since the last save, merged over whatever is on disk now so
+    /// two instances scanning different libraries don't drop each other's results
+    pub fn save_to(&mut self, path: &Path) -> Result<()> {
+        if self.changed.is_empty() {
+            return Ok(());
+        }
+
+        let mut on_disk = Self::load_from(path);
+        for key in self.changed.drain(..) {
+            if let Some(entry) = self.entries.get(&key) {
+                on_disk.entries.insert(key, entry.clone());
+            }
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create cache directory: {}", parent.display())
+            })?;
+        }
+
+        // Write to a temp file and rename so a crash never leaves a truncated cache
+        let contents = serde_json::to_vec(&on_disk).context("Failed to serialize probe cache")?;
+        let temp_path = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        fs::write(&temp_path, contents)
+            .with_context(|| format!("Failed to write probe cache: {}", temp_path.display()))?;
+        fs::rename(&temp_path, path)
+            .with_context(|| format!("Failed to replace probe cache: {}", path.display()))?;
+
+        self.entries = on_disk.entries;
+        Ok(())
+    }
+
+    /// Cached info for `key`, if it was probed from a file with this exact stamp
+    pub fn get(&self, key: &str, stamp: FileStamp) -> Option<&MediaInfo> {
+        self.entries
+            .get(key)
+            .filter(|entry| entry.stamp == stamp)
+            .map(|entry| &entry.info)
+    }
+
+    pub fn insert(&mut self, key: String, stamp: FileStamp, info: MediaInfo) {
+        self.entries.insert(key.clone(), CacheEntry { stamp, info });
+        self.changed.push(key);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// `probe_media_info` through the persistent cache (`[probe] cache = false` bypasses it).
+///
+/// Failed probes are never cached, so files still being copied are retried next scan.
+pub fn media_info(path: &Path) -> Result<MediaInfo, String> {
+    if !probe::settings().cache {
+        return probe_media_info(path);
+    }
+
+    let key = fs::canonicalize(path).map(|p| p.to_string_lossy().into_owned());
+    let (Ok(key), Some(stamp)) = (key, FileStamp::of(path)) else {
+        return probe_media_info(path);
+    };
+
+    if let Some(info) = with_cache(|cache| cache.get(&key, stamp).cloned()).flatten() {
+        return Ok(info);
+    }
+
+    // Probe without holding the lock so the scan's probe workers run in parallel
+    let info = probe_media_info(path)?;
+    with_cache(|cache| cache.insert(key, stamp, info.clone()));
+    Ok(info)
+}
+
+/// Cached info for an unchanged file, without ever running ffprobe
+pub fn cached_media_info(path: &Path) -> Option<MediaInfo> {
+    if !probe::settings().cache {
+        return None;
+    }
+    let key = fs::canonicalize(path).ok()?.to_string_lossy().into_owned();
+    let stamp = FileStamp::of(path)?;
+    with_cache(|cache| cache.get(&key, stamp).cloned()).flatten()
+}
+
+/// Save new results to disk; called once a scan has finished building its jobs
+pub fn flush() {
+    let Ok(path) = ProbeCache::cache_path() else {
+        return;
+    };
+    if let Ok(mut guard) = CACHE.lock() {
+        if let Some(cache) = guard.as_mut() {
+            // A cache that can't be written only costs a slower next scan
+            let _ = cache.save_to(&path);
+        }
+    }
+}
+
+fn with_cache<T>(f: impl FnOnce(&mut ProbeCache) -> T) -> Option<T> {
+    let mut guard = CACHE.lock().ok()?;
+    if guard.is_none() {
+        let path = ProbeCache::cache_path().ok()?;
+        *guard = Some(ProbeCache::load_from(&path));
+    }
+    guard.as_mut().map(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_info(duration: f64) -> MediaInfo {
+        MediaInfo::from_ffprobe_json(&serde_json::json!({
+            "format": {"format_name": "matroska,webm", "duration": duration.to_string()},
+            "streams": [{
+                "index": 0,
+                "codec_type": "video",
+                "codec_name": "hevc",
+                "width": 3840,
+                "height": 2160,
+                "pix_fmt": "yuv420p10le",
+                "color_transfer": "smpte2084"
+            }]
+        }))
+    }
+
+    #[test]
+    fn test_stale_entries_miss() {
+        let mut cache = ProbeCache::default();
+        let stamp = FileStamp {
+            size: 100,
+            mtime_secs: 1_700_000_000,
+            mtime_nanos: 5,
+        };
+        cache.insert("/media/a.mkv".to_string(), stamp, sample_info(60.0));
+
+        assert!(cache.get("/media/a.mkv", stamp).is_some());
+        assert!(cache.get("/media/b.mkv", stamp).is_none());
+        let resized = FileStamp { size: 101, ..stamp };
+        assert!(cache.get("/media/a.mkv", resized).is_none());
+        let touched = FileStamp {
+            mtime_nanos: 6,
+            ..stamp
+        };
+        assert!(cache.get("/media/a.mkv", touched).is_none());
+    }
+
+    #[test]
+    fn test_save_round_trips_and_merges_with_disk() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested/probe_cache.json");
+        let stamp = FileStamp {
+            size: 1,
+            mtime_secs: 2,
+            mtime_nanos: 3,
+        };
+
+        let mut first = ProbeCache::load_from(&path);
+        assert!(first.is_empty());
+        first.insert("/a.mkv".to_string(), stamp, sample_info(60.0));
+        first.save_to(&path).unwrap();
+
+        // A second instance that loaded before the first saved keeps both entries
+        let mut second = ProbeCache::default();
+        second.insert("/b.mkv".to_string(), stamp, sample_info(90.0));
+        second.save_to(&path).unwrap();
+        assert_eq!(second.len(), 2);
+
+        let reloaded = ProbeCache::load_from(&path);
+        assert_eq!(reloaded.len(), 2);
+        let info = reloaded.get("/a.mkv", stamp).unwrap();
+        assert_eq!(info.container.duration, Some(60.0));
+        assert!(info.is_hdr());
+        assert_eq!(info.primary_video().unwrap().bit_depth, Some(10));
+    }
+
+    #[test]
+    fn test_other_versions_are_discarded() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("probe_cache.json");
+        fs::write(&path, r#"{"version": 0, "entries": {}}"#).unwrap();
+        assert_eq!(ProbeCache::load_from(&path).version, CACHE_VERSION);
+
+        fs::write(&path, "not json").unwrap();
+        assert!(ProbeCache::load_from(&path).is_empty());
+    }
+}
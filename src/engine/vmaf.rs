@@ -4,7 +4,7 @@
 //! which is used by the Auto-VAMF feature to calibrate encoding quality settings.
 
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::sync::OnceLock;
@@ -70,10 +70,22 @@ pub fn select_vmaf_model(output_height: u32) -> &'static str {
     }
 }
 
-/// VMAF JSON output structure (partial - we only need the pooled mean)
+/// VMAF JSON output structure (partial - pooled mean plus per-frame scores)
 #[derive(Debug, Deserialize)]
 struct VmafOutput {
     pooled_metrics: PooledMetrics,
+    #[serde(default)]
+    frames: Vec<VmafFrame>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VmafFrame {
+    metrics: VmafFrameMetrics,
+}
+
+#[derive(Debug, Deserialize)]
+struct VmafFrameMetrics {
+    vmaf: f64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -91,52 +103,45 @@ struct VmafMetrics {
 /// Reads the JSON output from libvmaf and extracts the pooled mean VMAF score.
 /// Returns an error if the file doesn't exist, is malformed, or missing expected fields.
 pub fn parse_vmaf_score(log_path: &Path) -> Result<f32> {
+    Ok(read_vmaf_log(log_path)?.pooled_metrics.vmaf.mean as f32)
+}
+
+/// Parse the pooled mean and the per-frame VMAF scores from a JSON log file
+///
+/// Frames skipped by `n_subsample` are not present in the log.
+pub fn parse_vmaf_frame_scores(log_path: &Path) -> Result<(f32, Vec<f32>)> {
+    let output = read_vmaf_log(log_path)?;
+    let frames = output
+        .frames
+        .iter()
+        .map(|f| f.metrics.vmaf as f32)
+        .collect();
+    Ok((output.pooled_metrics.vmaf.mean as f32, frames))
+}
+
+fn read_vmaf_log(log_path: &Path) -> Result<VmafOutput> {
     let content = std::fs::read_to_string(log_path)
         .with_context(|| format!("Failed to read VMAF log: {}", log_path.display()))?;
 
-    let vmaf_output: VmafOutput = serde_json::from_str(&content)
-        .with_context(|| format!("Failed to parse VMAF JSON: {}", log_path.display()))?;
-
-    Ok(vmaf_output.pooled_metrics.vmaf.mean as f32)
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse VMAF JSON: {}", log_path.display()))
 }
 
-/// Build ffmpeg command for VMAF evaluation
-///
-/// Constructs a command that compares a source video segment with an encoded version,
-/// computing VMAF score and writing results to JSON.
-///
-/// # Arguments
-/// * `source_path` - Path to original source video
-/// * `encoded_path` - Path to encoded test window
-/// * `window_start` - Start time of window in seconds
-/// * `window_duration` - Duration of window in seconds
-/// * `output_height` - Output video height (for model selection)
-/// * `n_subsample` - Frame subsampling rate (e.g., 30 = evaluate every 30th frame)
-/// * `log_path` - Where to write VMAF JSON results
+/// Build the libvmaf filtergraph shared by calibration and `score_encode`
 ///
-/// # Returns
-/// A configured Command ready to execute
-#[allow(clippy::too_many_arguments)]
-pub fn build_vmaf_cmd(
-    source_path: &Path,
-    encoded_path: &Path,
-    window_start: f64,
-    window_duration: f64,
-    encode_fps: u32,
+/// `[0:v]` = source (reference), `[1:v]` = encoded (distorted).
+/// Both legs are normalized to the encoded output's fps/height to avoid frame misalignment.
+fn vmaf_filtergraph(
+    fps: Option<f64>,
     output_height: u32,
     n_subsample: u32,
     log_path: &Path,
-    hw_config: Option<&HwEncodingConfig>,
-    use_hw_decode: bool,
-) -> Command {
+) -> String {
     let model = select_vmaf_model(output_height);
 
-    // Build filtergraph for VMAF comparison
-    // [0:v] = source (reference), [1:v] = encoded (distorted)
-    // Normalize fps/scale on both legs to match the encoded output and avoid frame misalignment
     let mut norm_filters = Vec::new();
-    if encode_fps > 0 {
-        norm_filters.push(format!("fps=fps={}", encode_fps));
+    if let Some(fps) = fps.filter(|f| *f > 0.0) {
+        norm_filters.push(format!("fps=fps={}", fps));
     }
     if output_height > 0 {
         norm_filters.push(format!("scale=-2:{}", output_height));
@@ -154,7 +159,7 @@ pub fn build_vmaf_cmd(
         .replace('[', "\\[")
         .replace(']', "\\]");
 
-    let filtergraph = format!(
+    format!(
         "[0:v]{norm}[ref];\
          [1:v]{norm}[dist];\
          [dist][ref]libvmaf=model={model}:log_fmt=json:log_path={log}:n_subsample={sub}",
@@ -162,6 +167,43 @@ pub fn build_vmaf_cmd(
         model = model,
         log = escaped_log_path,
         sub = n_subsample,
+    )
+}
+
+/// Build ffmpeg command for VMAF evaluation
+///
+/// Constructs a command that compares a source video segment with an encoded version,
+/// computing VMAF score and writing results to JSON.
+///
+/// # Arguments
+/// * `source_path` - Path to original source video
+/// * `encoded_path` - Path to encoded test window
+/// * `window_start` - Start time of window in seconds
+/// * `window_duration` - Duration of window in seconds
+/// * `output_height` - Output video height (for model selection)
+/// * `n_subsample` - Frame subsampling rate (e.g., 30 = evaluate every 30th frame)
+/// * `log_path` - Where to write VMAF JSON results
+///
+/// # Returns
+/// A configured Command ready to execute
+#[allow(clippy::too_many_arguments)]
+pub fn build_vmaf_cmd(
+    source_path: &Path,
+    encoded_path: &Path,
+    window_start: f64,
+    window_duration: f64,
+    encode_fps: u32,
+    output_height: u32,
+    n_subsample: u32,
+    log_path: &Path,
+    hw_config: Option<&HwEncodingConfig>,
+    use_hw_decode: bool,
+) -> Command {
+    let filtergraph = vmaf_filtergraph(
+        (encode_fps > 0).then_some(encode_fps as f64),
+        output_height,
+        n_subsample,
+        log_path,
     );
 
     let mut cmd = Command::new("ffmpeg");
@@ -315,6 +357,237 @@ pub fn run_vmaf_evaluation(
     anyhow::bail!("VMAF evaluation failed after all attempts")
 }
 
+/// How `score_encode` samples the source/encoded pair
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VmafSampling {
+    /// Score every (subsampled) frame of the whole file
+    Full,
+    /// Score the same windows calibration would pick for this duration
+    Windows {
+        window_duration: u32,
+        budget_sec: u32,
+    },
+}
+
+/// Score for one sampled window (the whole file in `VmafSampling::Full`)
+#[derive(Debug, Clone, Serialize)]
+pub struct VmafWindowScore {
+    pub start: f64,
+    pub duration: f64,
+    pub mean: f32,
+    pub min: f32,
+    pub frames: usize,
+}
+
+/// Result of scoring an existing encode against its source
+#[derive(Debug, Clone, Serialize)]
+pub struct VmafReport {
+    pub source: PathBuf,
+    pub encoded: PathBuf,
+    /// libvmaf model, picked from the normalized height
+    pub model: String,
+    /// Height both legs were scaled to (the encoded output's height)
+    pub height: u32,
+    /// Frame rate both legs were resampled to (the encoded output's rate)
+    pub fps: Option<f64>,
+    pub n_subsample: u32,
+    /// Frame-weighted mean across all windows
+    pub mean: f32,
+    /// Worst scored frame
+    pub min: f32,
+    /// Requested low percentile (5.0 = score that 95% of frames meet or beat)
+    pub percentile: f32,
+    pub percentile_score: f32,
+    /// Number of frames libvmaf scored (after subsampling)
+    pub frames: usize,
+    pub windows: Vec<VmafWindowScore>,
+}
+
+/// Build ffmpeg command for scoring a finished encode against its source
+///
+/// Unlike `build_vmaf_cmd`, the encoded input is a full-length output, so a window
+/// seeks both inputs to the same timestamp. `None` compares the whole files.
+pub fn build_vmaf_compare_cmd(
+    source_path: &Path,
+    encoded_path: &Path,
+    window: Option<(f64, f64)>,
+    fps: Option<f64>,
+    output_height: u32,
+    n_subsample: u32,
+    log_path: &Path,
+) -> Command {
+    let filtergraph = vmaf_filtergraph(fps, output_height, n_subsample, log_path);
+
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-hide_banner", "-y"]);
+
+    for input in [source_path, encoded_path] {
+        if let Some((start, duration)) = window {
+            cmd.arg("-ss")
+                .arg(start.to_string())
+                .arg("-t")
+                .arg(duration.to_string());
+        }
+        cmd.arg("-i").arg(input);
+    }
+
+    cmd.arg("-lavfi")
+        .arg(&filtergraph)
+        .arg("-vsync")
+        .arg("0")
+        .args(["-f", "null", "-"]);
+
+    cmd
+}
+
+/// Score `encoded` against `source` without re-encoding anything
+///
+/// Both legs are normalized to the encoded file's height and frame rate, the same
+/// way calibration normalizes test windows, so scaled or frame-rate-changed
+/// encodes compare frame-for-frame. `on_window(index, total)` is called before
+/// each window is scored.
+pub fn score_encode<F>(
+    source_path: &Path,
+    encoded_path: &Path,
+    sampling: VmafSampling,
+    n_subsample: u32,
+    percentile: f32,
+    mut on_window: F,
+) -> Result<VmafReport>
+where
+    F: FnMut(usize, usize),
+{
+    if !vmaf_filter_available() {
+        anyhow::bail!("ffmpeg does not have the libvmaf filter");
+    }
+
+    let info = crate::engine::probe::probe_media_info(encoded_path)
+        .map_err(anyhow::Error::msg)
+        .with_context(|| format!("Failed to probe {}", encoded_path.display()))?;
+    let video = info
+        .primary_video()
+        .with_context(|| format!("No video stream in {}", encoded_path.display()))?;
+    let height = video.height.unwrap_or(0);
+    let fps = video.frame_rate;
+    let encoded_duration = info.container.duration;
+
+    let windows: Vec<Option<(f64, f64)>> = match sampling {
+        VmafSampling::Full => vec![None],
+        VmafSampling::Windows {
+            window_duration,
+            budget_sec,
+        } => {
+            let source_duration = probe_duration(source_path)?;
+            let duration = encoded_duration.map_or(source_duration, |d| d.min(source_duration));
+            select_windows(duration, window_duration, budget_sec)
+                .into_iter()
+                .map(Some)
+                .collect()
+        }
+    };
+
+    let temp_dir = std::env::temp_dir().join(format!("ffdash_vmaf_{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&temp_dir)
+        .with_context(|| format!("Failed to create temp dir: {}", temp_dir.display()))?;
+
+    let mut window_scores = Vec::with_capacity(windows.len());
+    let mut frame_scores = Vec::new();
+    let mut run = || -> Result<()> {
+        for (idx, window) in windows.iter().enumerate() {
+            on_window(idx, windows.len());
+
+            let log_path = temp_dir.join(format!("vmaf_{}.json", idx));
+            let mut cmd = build_vmaf_compare_cmd(
+                source_path,
+                encoded_path,
+                *window,
+                fps,
+                height,
+                n_subsample,
+                &log_path,
+            );
+            let output = cmd.output().context("Failed to execute VMAF evaluation")?;
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let tail: Vec<&str> = stderr.lines().rev().take(5).collect();
+                anyhow::bail!(
+                    "VMAF evaluation failed: {}",
+                    tail.into_iter().rev().collect::<Vec<_>>().join("\n")
+                );
+            }
+
+            let (mean, frames) = parse_vmaf_frame_scores(&log_path)?;
+            let (start, duration) = window.unwrap_or((0.0, encoded_duration.unwrap_or(0.0)));
+            window_scores.push(VmafWindowScore {
+                start,
+                duration,
+                mean,
+                min: frames.iter().copied().reduce(f32::min).unwrap_or(mean),
+                frames: frames.len(),
+            });
+            frame_scores.extend(frames);
+        }
+        Ok(())
+    };
+    let result = run();
+    let _ = cleanup_job_temp_dir(&temp_dir);
+    result?;
+
+    let (mean, min, percentile_score) = aggregate_scores(&window_scores, &frame_scores, percentile);
+
+    Ok(VmafReport {
+        source: source_path.to_path_buf(),
+        encoded: encoded_path.to_path_buf(),
+        model: select_vmaf_model(height).to_string(),
+        height,
+        fps,
+        n_subsample,
+        mean,
+        min,
+        percentile,
+        percentile_score,
+        frames: frame_scores.len(),
+        windows: window_scores,
+    })
+}
+
+/// Nearest-rank percentile of a set of scores (`percentile` in 0..=100)
+pub fn score_percentile(scores: &[f32], percentile: f32) -> Option<f32> {
+    if scores.is_empty() {
+        return None;
+    }
+    let mut sorted = scores.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let rank = ((percentile.clamp(0.0, 100.0) / 100.0) * sorted.len() as f32).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+/// Combine per-window results into (mean, min, percentile score)
+///
+/// Uses the per-frame scores when libvmaf logged them, otherwise falls back to
+/// the pooled window means.
+fn aggregate_scores(
+    windows: &[VmafWindowScore],
+    frame_scores: &[f32],
+    percentile: f32,
+) -> (f32, f32, f32) {
+    if !frame_scores.is_empty() {
+        let mean = frame_scores.iter().sum::<f32>() / frame_scores.len() as f32;
+        let min = frame_scores.iter().copied().fold(f32::INFINITY, f32::min);
+        let pct = score_percentile(frame_scores, percentile).unwrap_or(min);
+        return (mean, min, pct);
+    }
+
+    let means: Vec<f32> = windows.iter().map(|w| w.mean).collect();
+    if means.is_empty() {
+        return (0.0, 0.0, 0.0);
+    }
+    let mean = means.iter().sum::<f32>() / means.len() as f32;
+    let min = means.iter().copied().fold(f32::INFINITY, f32::min);
+    let pct = score_percentile(&means, percentile).unwrap_or(min);
+    (mean, min, pct)
+}
+
 /// Calculate number of windows based on video duration
 ///
 /// Dynamically scales window count based on video length:
@@ -442,6 +715,12 @@ pub fn select_windows(duration_s: f64, window_duration: u32, budget_sec: u32) ->
     windows
 }
 
+/// `.ffdash_tmp/<job_id>/` next to the job's input, without creating it
+pub fn job_temp_dir(job: &VideoJob) -> PathBuf {
+    let parent = job.input_path.parent().unwrap_or_else(|| Path::new("."));
+    parent.join(".ffdash_tmp").join(job.id.to_string())
+}
+
 /// Create temporary directory for job-specific files
 ///
 /// Creates `.ffdash_tmp/<job_id>/` under the parent directory of the input file.
@@ -453,10 +732,7 @@ pub fn select_windows(duration_s: f64, window_duration: u32, budget_sec: u32) ->
 /// # Returns
 /// Path to the created temp directory
 pub fn create_job_temp_dir(job: &VideoJob) -> Result<PathBuf> {
-    let parent = job.input_path.parent().unwrap_or_else(|| Path::new("."));
-
-    let temp_root = parent.join(".ffdash_tmp");
-    let job_temp = temp_root.join(job.id.to_string());
+    let job_temp = job_temp_dir(job);
 
     std::fs::create_dir_all(&job_temp)
         .with_context(|| format!("Failed to create temp dir: {}", job_temp.display()))?;
@@ -1382,4 +1658,96 @@ mod tests {
         let result = parse_vmaf_score(&log);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_parse_vmaf_frame_scores() {
+        let temp = tempfile::tempdir().unwrap();
+        let log = temp.path().join("frames.json");
+        std::fs::write(
+            &log,
+            r#"{
+            "frames": [
+                { "frameNum": 0, "metrics": { "vmaf": 95.0 } },
+                { "frameNum": 30, "metrics": { "vmaf": 81.5 } }
+            ],
+            "pooled_metrics": { "vmaf": { "mean": 88.25 } }
+        }"#,
+        )
+        .unwrap();
+
+        let (mean, frames) = parse_vmaf_frame_scores(&log).unwrap();
+        assert!((mean - 88.25).abs() < 0.01);
+        assert_eq!(frames, vec![95.0, 81.5]);
+    }
+
+    #[test]
+    fn test_score_percentile_nearest_rank() {
+        let scores: Vec<f32> = (1..=100).map(|v| v as f32).collect();
+        assert_eq!(score_percentile(&scores, 5.0), Some(5.0));
+        assert_eq!(score_percentile(&scores, 0.0), Some(1.0));
+        assert_eq!(score_percentile(&scores, 100.0), Some(100.0));
+        assert_eq!(score_percentile(&[], 5.0), None);
+    }
+
+    #[test]
+    fn test_aggregate_scores_prefers_frames_over_window_means() {
+        let windows = vec![
+            VmafWindowScore {
+                start: 0.0,
+                duration: 10.0,
+                mean: 90.0,
+                min: 80.0,
+                frames: 2,
+            },
+            VmafWindowScore {
+                start: 60.0,
+                duration: 10.0,
+                mean: 96.0,
+                min: 94.0,
+                frames: 2,
+            },
+        ];
+
+        let (mean, min, pct) = aggregate_scores(&windows, &[80.0, 100.0, 94.0, 98.0], 25.0);
+        assert!((mean - 93.0).abs() < 0.01);
+        assert_eq!(min, 80.0);
+        assert_eq!(pct, 80.0);
+
+        let (mean, min, _) = aggregate_scores(&windows, &[], 5.0);
+        assert!((mean - 93.0).abs() < 0.01);
+        assert_eq!(min, 90.0);
+    }
+
+    #[test]
+    fn test_build_vmaf_compare_cmd_seeks_both_inputs() {
+        let cmd = build_vmaf_compare_cmd(
+            Path::new("/src/a.mkv"),
+            Path::new("/out/a.webm"),
+            Some((120.0, 10.0)),
+            Some(23.976),
+            720,
+            5,
+            Path::new("/tmp/vmaf.json"),
+        );
+        let args: Vec<String> = cmd
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+
+        assert_eq!(args.iter().filter(|a| *a == "-ss").count(), 2);
+        let graph = args.iter().find(|a| a.contains("libvmaf")).unwrap();
+        assert!(graph.contains("fps=fps=23.976,scale=-2:720,format=yuv420p"));
+        assert!(graph.contains("n_subsample=5"));
+
+        let full = build_vmaf_compare_cmd(
+            Path::new("/src/a.mkv"),
+            Path::new("/out/a.webm"),
+            None,
+            None,
+            1080,
+            1,
+            Path::new("/tmp/vmaf.json"),
+        );
+        assert!(!full.get_args().any(|a| a == "-ss"));
+    }
 }
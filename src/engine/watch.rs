@@ -0,0 +1,268 @@
+// Watch mode: queue new files in a directory once they have finished copying
+
+use crate::engine::{ScanFilter, VideoJob, scan_streaming_filtered};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Watch mode timing, configured under `[watch]` in config.toml
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchSettings {
+    /// A new file is queued once its size and mtime have not changed for this long
+    pub settle_secs: u64,
+    /// How often the watched directory is rescanned
+    pub interval_secs: u64,
+}
+
+impl WatchSettings {
+    pub fn settle(&self) -> Duration {
+        Duration::from_secs(self.settle_secs)
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.max(1))
+    }
+}
+
+impl Default for WatchSettings {
+    fn default() -> Self {
+        Self {
+            settle_secs: 10,
+            interval_secs: 5,
+        }
+    }
+}
+
+/// Where a watcher gets its candidate files from. Polling rescans the directory; an
+/// event-driven source (inotify and friends) only has to produce the same list.
+pub trait FileSource: Send {
+    fn list(&mut self) -> Result<Vec<PathBuf>>;
+}
+
+/// Rescans a directory with the scan filters on every poll
+pub struct PollSource {
+    root: PathBuf,
+    filter: ScanFilter,
+}
+
+impl PollSource {
+    pub fn new(root: &Path, filter: ScanFilter) -> Self {
+        Self {
+            root: std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf()),
+            filter,
+        }
+    }
+}
+
+impl FileSource for PollSource {
+    fn list(&mut self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        scan_streaming_filtered(&self.root, &self.filter, |path| files.push(path), |_, _| {})?;
+        Ok(files)
+    }
+}
+
+/// Size and mtime of a file when it was last seen changing
+struct Sighting {
+    size: u64,
+    mtime: Option<SystemTime>,
+    since: Instant,
+}
+
+/// Reports each new file once it has stopped growing
+pub struct Watcher {
+    source: Box<dyn FileSource>,
+    settle: Duration,
+    seen: HashMap<PathBuf, Sighting>,
+    known: HashSet<PathBuf>,
+}
+
+impl Watcher {
+    pub fn new(source: impl FileSource + 'static, settle: Duration) -> Self {
+        Self {
+            source: Box::new(source),
+            settle,
+            seen: HashMap::new(),
+            known: HashSet::new(),
+        }
+    }
+
+    /// Watch `root` by polling it with the scan filters
+    pub fn polling(root: &Path, filter: ScanFilter, settle: Duration) -> Self {
+        Self::new(PollSource::new(root, filter), settle)
+    }
+
+    /// Never report `path`: it is already queued, or it is one of our outputs
+    pub fn ignore(&mut self, path: &Path) {
+        self.seen.remove(&key(path));
+        self.known.insert(key(path));
+    }
+
+    /// Ignore a job's input and output
+    pub fn ignore_job(&mut self, job: &VideoJob) {
+        self.ignore(&job.input_path);
+        self.ignore(&job.output_path);
+    }
+
+    /// Files that have been unchanged for the settle time since they first appeared
+    pub fn poll(&mut self) -> Result<Vec<PathBuf>> {
+        self.poll_at(Instant::now())
+    }
+
+    /// Build a job for every settled file; their outputs are ignored from then on
+    pub fn poll_jobs(&mut self, build: impl Fn(PathBuf) -> VideoJob) -> Result<Vec<VideoJob>> {
+        let jobs: Vec<VideoJob> = self.poll()?.into_iter().map(build).collect();
+        for job in &jobs {
+            self.ignore(&job.output_path);
+        }
+        Ok(jobs)
+    }
+
+    fn poll_at(&mut self, now: Instant) -> Result<Vec<PathBuf>> {
+        let mut ready = Vec::new();
+        let mut present = HashSet::new();
+
+        for path in self.source.list()? {
+            let key = key(&path);
+            if self.known.contains(&key) {
+                continue;
+            }
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            let (size, mtime) = (metadata.len(), metadata.modified().ok());
+            present.insert(key.clone());
+
+            match self.seen.get(&key) {
+                Some(last) if last.size == size && last.mtime == mtime => {
+                    if now.duration_since(last.since) >= self.settle {
+                        ready.push(path);
+                    }
+                }
+                _ => {
+                    self.seen.insert(
+                        key,
+                        Sighting {
+                            size,
+                            mtime,
+                            since: now,
+                        },
+                    );
+                }
+            }
+        }
+
+        // Forget files that went away before settling
+        self.seen.retain(|key, _| present.contains(key));
+        for path in &ready {
+            self.ignore(path);
+        }
+        Ok(ready)
+    }
+}
+
+/// Poll `watcher` every `interval` on a background thread and hand each new job to
+/// `send`; stops once `send` returns false. Failed polls (an unmounted share, say) are
+/// retried on the next interval.
+pub fn spawn<B, S>(mut watcher: Watcher, interval: Duration, build: B, mut send: S)
+where
+    B: Fn(PathBuf) -> VideoJob + Send + 'static,
+    S: FnMut(VideoJob) -> bool + Send + 'static,
+{
+    thread::spawn(move || {
+        loop {
+            thread::sleep(interval);
+            let Ok(jobs) = watcher.poll_jobs(&build) else {
+                continue;
+            };
+            for job in jobs {
+                if !send(job) {
+                    return;
+                }
+            }
+        }
+    });
+}
+
+fn key(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Lists whatever the test put in it
+    #[derive(Default)]
+    struct FakeSource(Arc<Mutex<Vec<PathBuf>>>);
+
+    impl FileSource for FakeSource {
+        fn list(&mut self) -> Result<Vec<PathBuf>> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+    }
+
+    #[test]
+    fn test_files_are_reported_once_settled() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (copied, growing) = (
+            temp_dir.path().join("copied.mkv"),
+            temp_dir.path().join("growing.mkv"),
+        );
+        fs::write(&copied, b"done").unwrap();
+        fs::write(&growing, b"a").unwrap();
+
+        let source = FakeSource::default();
+        *source.0.lock().unwrap() = vec![copied.clone(), growing.clone()];
+        let mut watcher = Watcher::new(source, Duration::from_secs(10));
+        let start = Instant::now();
+        let mut poll = |secs| watcher.poll_at(start + Duration::from_secs(secs)).unwrap();
+
+        assert!(poll(0).is_empty());
+        fs::write(&growing, b"ab").unwrap();
+        assert!(poll(5).is_empty());
+
+        // The copied file settles; the growing one restarted its clock when it grew
+        assert_eq!(poll(10), vec![copied]);
+        assert!(poll(12).is_empty());
+        assert_eq!(poll(15), vec![growing]);
+
+        // Reported files are never reported again
+        assert!(poll(60).is_empty());
+    }
+
+    #[test]
+    fn test_ignored_and_vanished_files_are_not_reported() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (queued, output, gone) = (
+            temp_dir.path().join("queued.mkv"),
+            temp_dir.path().join("queued.webm"),
+            temp_dir.path().join("gone.mkv"),
+        );
+        for path in [&queued, &output, &gone] {
+            fs::write(path, b"x").unwrap();
+        }
+
+        let source = FakeSource::default();
+        *source.0.lock().unwrap() = vec![queued.clone(), output.clone(), gone.clone()];
+        let mut watcher = Watcher::new(source, Duration::ZERO);
+        watcher.ignore_job(&VideoJob::new(queued, output, "vp9-good".to_string()));
+        let start = Instant::now();
+        let mut poll = |secs| watcher.poll_at(start + Duration::from_secs(secs)).unwrap();
+
+        assert!(poll(0).is_empty());
+        fs::remove_file(&gone).unwrap();
+        assert!(poll(1).is_empty());
+
+        // A file reappearing under the same name starts over
+        fs::write(&gone, b"again").unwrap();
+        assert!(poll(2).is_empty());
+        assert_eq!(poll(3), vec![gone]);
+    }
+}
@@ -1,19 +1,22 @@
 // Worker pool for parallel video encoding
 
 use anyhow::Result;
+use serde::Serialize;
 use std::collections::HashSet;
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use uuid::Uuid;
 
+use super::history::EncodeReport;
 use super::{
-    HwEncodingConfig, JobStatus, Profile, ProgressParser, VideoJob,
+    HwEncodingConfig, JobStatus, Profile, ProgressParser, SegmentPlan, VideoJob,
     encode_job_with_callback_and_profile,
 };
 
 /// Message from worker to main thread
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
 pub enum WorkerMessage {
     /// Job started encoding
     JobStarted { job_id: Uuid },
@@ -32,6 +35,28 @@ pub enum WorkerMessage {
         status: JobStatus,
     },
 
+    /// Auto-VMAF calibration finished evaluating one window
+    CalibrationStep {
+        job_id: Uuid,
+        completed_steps: u32,
+        total_steps: u32,
+        vmaf_result: Option<f32>,
+    },
+
+    /// Segment mode: a segment (or the audio) finished, or the plan changed. None once
+    /// the segments are joined.
+    SegmentsUpdated {
+        job_id: Uuid,
+        segments: Option<SegmentPlan>,
+    },
+
+    /// What the finished encode did (encoder, timings, source), sent just before
+    /// JobCompleted or JobFailed
+    JobReport {
+        job_id: Uuid,
+        report: Box<EncodeReport>,
+    },
+
     /// Job completed successfully
     JobCompleted { job_id: Uuid },
 
@@ -42,6 +67,22 @@ pub enum WorkerMessage {
     WorkerIdle { worker_id: usize },
 }
 
+impl WorkerMessage {
+    /// The job this message refers to (None for worker-level messages)
+    pub fn job_id(&self) -> Option<Uuid> {
+        match self {
+            WorkerMessage::JobStarted { job_id }
+            | WorkerMessage::ProgressUpdate { job_id, .. }
+            | WorkerMessage::CalibrationStep { job_id, .. }
+            | WorkerMessage::SegmentsUpdated { job_id, .. }
+            | WorkerMessage::JobReport { job_id, .. }
+            | WorkerMessage::JobCompleted { job_id }
+            | WorkerMessage::JobFailed { job_id, .. } => Some(*job_id),
+            WorkerMessage::WorkerIdle { .. } => None,
+        }
+    }
+}
+
 /// Type alias for the PID registry shared between workers
 pub type PidRegistry = Arc<Mutex<HashSet<u32>>>;
 
@@ -112,7 +153,32 @@ impl WorkerPool {
             // Create progress callback that sends updates via channel
             let tx_progress = tx.clone();
             let job_id = job.id;
+            let mut last_calibration_step = None;
+            let mut last_segments = job.segments.clone();
             let progress_callback = move |job: &VideoJob, _parser: &ProgressParser| {
+                if job.status == JobStatus::Calibrating {
+                    if let Some(total_steps) = job.calibrating_total_steps {
+                        let step = job.calibrating_completed_steps;
+                        if last_calibration_step != Some(step) {
+                            last_calibration_step = Some(step);
+                            let _ = tx_progress.send(WorkerMessage::CalibrationStep {
+                                job_id,
+                                completed_steps: step,
+                                total_steps,
+                                vmaf_result: job.vmaf_result,
+                            });
+                        }
+                    }
+                }
+
+                if job.segments != last_segments {
+                    last_segments = job.segments.clone();
+                    let _ = tx_progress.send(WorkerMessage::SegmentsUpdated {
+                        job_id,
+                        segments: job.segments.clone(),
+                    });
+                }
+
                 let _ = tx_progress.send(WorkerMessage::ProgressUpdate {
                     job_id,
                     progress_pct: job.progress_pct,
@@ -137,6 +203,13 @@ impl WorkerPool {
                 progress_callback,
             );
 
+            if let Some(report) = job.report.take() {
+                let _ = tx.send(WorkerMessage::JobReport {
+                    job_id: job.id,
+                    report,
+                });
+            }
+
             // Send completion or failure message
             match result {
                 Ok(_) => {
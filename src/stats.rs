@@ -2,6 +2,7 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 use std::time::Instant;
@@ -25,6 +26,36 @@ pub struct LifetimeStats {
 
     /// Last updated timestamp (ISO 8601)
     pub last_updated: Option<String>,
+
+    /// Per-day buckets keyed by local date (YYYY-MM-DD).
+    /// Stats files from before buckets existed only have the totals above.
+    #[serde(default)]
+    pub days: BTreeMap<String, StatsBucket>,
+}
+
+/// Counters for one period (a day, a month, or a `--since` range)
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct StatsBucket {
+    pub jobs_completed: u64,
+    pub jobs_failed: u64,
+    pub input_bytes: u64,
+    pub output_bytes: u64,
+    pub encode_time_secs: f64,
+}
+
+impl StatsBucket {
+    /// Bytes saved (negative when outputs grew)
+    pub fn saved_bytes(&self) -> i64 {
+        self.input_bytes as i64 - self.output_bytes as i64
+    }
+
+    fn add(&mut self, other: &StatsBucket) {
+        self.jobs_completed += other.jobs_completed;
+        self.jobs_failed += other.jobs_failed;
+        self.input_bytes += other.input_bytes;
+        self.output_bytes += other.output_bytes;
+        self.encode_time_secs += other.encode_time_secs;
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -153,6 +184,63 @@ impl LifetimeStats {
         Ok(())
     }
 
+    /// Record a finished job in the totals and today's bucket
+    pub fn record_completed(&mut self, input_bytes: u64, output_bytes: u64, encode_time_secs: f64) {
+        self.total_input_bytes += input_bytes;
+        self.total_output_bytes += output_bytes;
+        self.total_encode_time_secs += encode_time_secs;
+        self.total_jobs_completed += 1;
+
+        let today = self.today();
+        today.jobs_completed += 1;
+        today.input_bytes += input_bytes;
+        today.output_bytes += output_bytes;
+        today.encode_time_secs += encode_time_secs;
+    }
+
+    /// Record a failed job in the totals and today's bucket
+    pub fn record_failed(&mut self) {
+        self.total_jobs_failed += 1;
+        self.today().jobs_failed += 1;
+    }
+
+    fn today(&mut self) -> &mut StatsBucket {
+        let now = chrono::Local::now();
+        self.last_updated = Some(now.to_rfc3339());
+        self.days
+            .entry(now.format("%Y-%m-%d").to_string())
+            .or_default()
+    }
+
+    /// Day buckets on or after `since` (all of them when None), oldest first
+    pub fn days_since(&self, since: Option<chrono::NaiveDate>) -> Vec<(&str, &StatsBucket)> {
+        let since = since.map(|d| d.format("%Y-%m-%d").to_string());
+        self.days
+            .iter()
+            .filter(|(day, _)| since.as_ref().is_none_or(|s| day.as_str() >= s.as_str()))
+            .map(|(day, bucket)| (day.as_str(), bucket))
+            .collect()
+    }
+
+    /// Sum of the day buckets on or after `since`
+    pub fn total_since(&self, since: Option<chrono::NaiveDate>) -> StatsBucket {
+        let mut total = StatsBucket::default();
+        for (_, bucket) in self.days_since(since) {
+            total.add(bucket);
+        }
+        total
+    }
+
+    /// Day buckets on or after `since` rolled up by month (YYYY-MM)
+    pub fn monthly_since(&self, since: Option<chrono::NaiveDate>) -> BTreeMap<String, StatsBucket> {
+        let mut months: BTreeMap<String, StatsBucket> = BTreeMap::new();
+        for (day, bucket) in self.days_since(since) {
+            let month = day.get(..7).unwrap_or(day).to_string();
+            months.entry(month).or_default().add(bucket);
+        }
+        months
+    }
+
     /// Calculate compression ratio (input / output)
     pub fn compression_ratio(&self) -> f64 {
         if self.total_output_bytes == 0 {
@@ -306,4 +394,65 @@ mod tests {
         stats.total_output_bytes = 0;
         assert_eq!(stats.compression_ratio(), 0.0);
     }
+
+    #[test]
+    fn test_record_updates_totals_and_today() {
+        let mut stats = LifetimeStats::default();
+        stats.record_completed(1000, 400, 12.5);
+        stats.record_completed(500, 100, 2.5);
+        stats.record_failed();
+
+        assert_eq!(stats.total_jobs_completed, 2);
+        assert_eq!(stats.total_jobs_failed, 1);
+        assert_eq!(stats.total_input_bytes, 1500);
+        assert!(stats.last_updated.is_some());
+
+        assert_eq!(stats.days.len(), 1);
+        let today = stats.days.values().next().unwrap();
+        assert_eq!(today.jobs_completed, 2);
+        assert_eq!(today.jobs_failed, 1);
+        assert_eq!(today.saved_bytes(), 1000);
+        assert_eq!(today.encode_time_secs, 15.0);
+    }
+
+    #[test]
+    fn test_since_and_monthly_rollup() {
+        let mut stats = LifetimeStats::default();
+        let bucket = |input: u64, output: u64| StatsBucket {
+            jobs_completed: 1,
+            input_bytes: input,
+            output_bytes: output,
+            ..StatsBucket::default()
+        };
+        stats.days.insert("2026-08-30".to_string(), bucket(100, 50));
+        stats.days.insert("2026-09-01".to_string(), bucket(200, 50));
+        stats
+            .days
+            .insert("2026-09-15".to_string(), bucket(300, 100));
+
+        assert_eq!(stats.total_since(None).jobs_completed, 3);
+
+        let since = chrono::NaiveDate::from_ymd_opt(2026, 9, 1);
+        let total = stats.total_since(since);
+        assert_eq!(total.jobs_completed, 2);
+        assert_eq!(total.saved_bytes(), 350);
+
+        let months = stats.monthly_since(None);
+        assert_eq!(
+            months.keys().collect::<Vec<_>>(),
+            vec!["2026-08", "2026-09"]
+        );
+        assert_eq!(months["2026-09"].input_bytes, 500);
+    }
+
+    #[test]
+    fn test_load_stats_without_day_buckets() {
+        let stats: LifetimeStats = serde_json::from_str(
+            r#"{"total_input_bytes":10,"total_output_bytes":5,"total_encode_time_secs":1.0,
+                "total_jobs_completed":1,"total_jobs_failed":0,"last_updated":null}"#,
+        )
+        .unwrap();
+        assert_eq!(stats.total_jobs_completed, 1);
+        assert!(stats.days.is_empty());
+    }
 }
@@ -0,0 +1,85 @@
+// "Add path" prompt for queueing another directory or file
+
+use crate::ui::state::AddPathState;
+use ratatui::{
+    Frame,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+pub struct AddPathModal;
+
+impl AddPathModal {
+    pub fn render(frame: &mut Frame, state: &AddPathState) {
+        let area = frame.area();
+
+        // Wide enough for typical paths, centered
+        let modal_width = 70.min(area.width.saturating_sub(4));
+        let modal_height = 8.min(area.height.saturating_sub(2));
+
+        let modal_area = Rect {
+            x: (area.width.saturating_sub(modal_width)) / 2,
+            y: (area.height.saturating_sub(modal_height)) / 2,
+            width: modal_width,
+            height: modal_height,
+        };
+
+        // Clear background
+        frame.render_widget(Clear, modal_area);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(" Add Path ")
+            .title_alignment(Alignment::Center)
+            .style(Style::default().bg(Color::Black));
+
+        let inner = block.inner(modal_area);
+        frame.render_widget(block, modal_area);
+
+        // Show the end of long paths, where the user is typing
+        let visible = inner.width.saturating_sub(4) as usize;
+        let chars: Vec<char> = state.input.chars().collect();
+        let shown: String = chars[chars.len().saturating_sub(visible)..]
+            .iter()
+            .collect();
+
+        let status = match &state.error {
+            Some(error) => Span::styled(error.clone(), Style::default().fg(Color::Red)),
+            None => Span::styled(
+                "Directory or video file to add to this session",
+                Style::default().fg(Color::Gray),
+            ),
+        };
+
+        let lines = vec![
+            Line::from(status),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("> ", Style::default().fg(Color::Yellow)),
+                Span::styled(shown, Style::default().fg(Color::White)),
+                Span::styled("█", Style::default().fg(Color::Yellow)),
+            ]),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled(
+                    "[Enter]",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" Add   "),
+                Span::styled(
+                    "[Esc]",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" Cancel"),
+            ]),
+        ];
+
+        let paragraph = Paragraph::new(lines).alignment(Alignment::Center);
+        frame.render_widget(paragraph, inner);
+    }
+}
@@ -147,7 +147,10 @@ impl ConfigScreen {
             height: 1,
         };
         state.filename_pattern_area = Some(pattern_area);
-        let pattern_style = if state.focus == ConfigFocus::FilenamePattern {
+        let pattern_error = crate::engine::validate_pattern(&state.filename_pattern).err();
+        let pattern_style = if pattern_error.is_some() {
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        } else if state.focus == ConfigFocus::FilenamePattern {
             Style::default()
                 .fg(Color::Yellow)
                 .add_modifier(Modifier::BOLD)
@@ -159,10 +162,22 @@ impl ConfigScreen {
         } else {
             state.filename_pattern.clone()
         };
-        let pattern_line = Line::from(vec![
+        let mut pattern_spans = vec![
             Span::raw("Output Pattern: "),
             Span::styled(pattern_text, pattern_style),
-        ]);
+        ];
+        if let Some(e) = pattern_error {
+            pattern_spans.push(Span::styled(
+                format!("  ✗ {}", e),
+                Style::default().fg(Color::Red),
+            ));
+        } else if let Some(warning) = crate::engine::pattern_warning(&state.filename_pattern) {
+            pattern_spans.push(Span::styled(
+                format!("  ⚠ {}", warning),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        let pattern_line = Line::from(pattern_spans);
         frame.render_widget(Paragraph::new(pattern_line), pattern_area);
         y += 1;
 
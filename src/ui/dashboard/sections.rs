@@ -406,7 +406,14 @@ impl Dashboard {
     ) {
         use crate::engine::JobStatus;
 
-        let block = Block::default().borders(Borders::ALL).title("Active Jobs");
+        let mut title = match state.queue_sort {
+            Some(sort) => format!("Active Jobs (sorted by {})", sort.label()),
+            None => "Active Jobs".to_string(),
+        };
+        if let Some(dir) = &state.watching {
+            title.push_str(&format!(" · watching {}", dir.display()));
+        }
+        let block = Block::default().borders(Borders::ALL).title(title);
 
         let inner = block.inner(area);
         let rows_visible = inner
@@ -555,16 +562,45 @@ impl Dashboard {
                 let progress_pct = job.progress_pct.min(100.0) as u16;
                 let progress_bar = Self::render_progress_bar(progress_pct, progress_state, 20);
 
+                // Jobs whose profile came from a config rule name the rule after the file,
+                // and pinned jobs are marked so the run order makes sense
+                let mut source = vec![Span::raw(filename)];
+                if job.priority > 0 && job.status == JobStatus::Pending {
+                    source.push(Span::styled(
+                        "  [pinned]",
+                        Style::default().fg(Color::Yellow),
+                    ));
+                }
+                if let Some(rule) = &job.profile_rule {
+                    source.push(Span::styled(
+                        format!("  [{}]", rule),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+                let source = Line::from(source);
+
+                // Skipped and failed jobs show why in place of the (empty) bar
+                let progress_cell = match (&job.status, &job.last_error) {
+                    (JobStatus::Skipped, Some(reason)) => {
+                        Cell::from(reason.as_str()).style(Style::default().fg(Color::Blue))
+                    }
+                    (JobStatus::Failed, Some(reason)) if job.progress_pct == 0.0 => {
+                        let first_line = reason.lines().next().unwrap_or_default();
+                        Cell::from(first_line.to_string()).style(Style::default().fg(Color::Red))
+                    }
+                    _ => Cell::from(progress_bar),
+                };
+
                 // Build row cells
                 let mut cells = vec![
                     Cell::from(format!("{}", idx + 1)),
                     Cell::from(format!("{} {}", status_icon, status_text))
                         .style(Style::default().fg(status_color)),
-                    Cell::from(filename),
+                    Cell::from(source),
                     Cell::from(Line::from(in_size).right_aligned()),
                     Cell::from(Line::from(out_size).right_aligned()),
                     Cell::from(Line::from(speed).right_aligned()),
-                    Cell::from(progress_bar),
+                    progress_cell,
                     Cell::from(eta.clone()),
                 ];
 
@@ -757,7 +793,10 @@ mod tests {
             output_path: PathBuf::from("/test/output.webm"),
             profile: "test".to_string(),
             status,
+            profile_rule: None,
             overwrite: false,
+            priority: 0,
+            origin: None,
             duration_s,
             progress_pct: 0.0,
             out_time_s,
@@ -774,9 +813,12 @@ mod tests {
             vmaf_target: None,
             vmaf_result: None,
             calibrated_quality: None,
+            calibrated_hw: None,
             vmaf_partial_scores: Vec::new(),
+            segments: None,
             calibrating_total_steps: None,
             calibrating_completed_steps: 0,
+            report: None,
         }
     }
 
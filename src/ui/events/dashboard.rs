@@ -18,6 +18,45 @@ pub(super) fn handle_dashboard_key(key: KeyEvent, state: &mut AppState) {
                 .count();
             state.current_screen = Screen::Stats;
         }
+        // Move selected job up/down the queue
+        KeyCode::Up if key.modifiers.contains(KeyModifiers::SHIFT) => move_selected(state, true),
+        KeyCode::Down if key.modifiers.contains(KeyModifiers::SHIFT) => move_selected(state, false),
+        KeyCode::Char('K') => move_selected(state, true),
+        KeyCode::Char('J') => move_selected(state, false),
+        // Pin selected job to run next (again to unpin)
+        KeyCode::Char('p') | KeyCode::Char('P') => {
+            if let Some(selected) = state.dashboard.table_state.selected() {
+                crate::engine::toggle_pin(&mut state.dashboard.jobs, selected);
+                sync_queue_order(state);
+            }
+        }
+        // Sort queue, cycling name → size → duration → est. savings
+        KeyCode::Char('o') | KeyCode::Char('O') => {
+            let sort = state
+                .dashboard
+                .queue_sort
+                .map_or(crate::engine::QueueSort::default(), |sort| sort.next());
+            let selected_id = state
+                .dashboard
+                .table_state
+                .selected()
+                .and_then(|idx| state.dashboard.jobs.get(idx))
+                .map(|job| job.id);
+
+            crate::engine::sort_jobs(&mut state.dashboard.jobs, sort);
+            state.dashboard.queue_sort = Some(sort);
+
+            // Keep the same job selected
+            if let Some(id) = selected_id {
+                let idx = state.dashboard.jobs.iter().position(|j| j.id == id);
+                state.dashboard.table_state.select(idx);
+            }
+            sync_queue_order(state);
+        }
+        // Add a directory or file to the queue
+        KeyCode::Char('a') | KeyCode::Char('A') => {
+            state.add_path = Some(Default::default());
+        }
         // Navigate table
         KeyCode::Up => {
             let selected = state.dashboard.table_state.selected();
@@ -214,6 +253,95 @@ pub(super) fn handle_dashboard_key(key: KeyEvent, state: &mut AppState) {
     }
 }
 
+pub(super) fn handle_add_path_key(key: KeyEvent, state: &mut AppState) {
+    let Some(prompt) = state.add_path.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Esc => state.add_path = None,
+        KeyCode::Enter => submit_add_path(state),
+        KeyCode::Backspace => {
+            prompt.input.pop();
+            prompt.error = None;
+        }
+        KeyCode::Char(c) => {
+            prompt.input.push(c);
+            prompt.error = None;
+        }
+        _ => {}
+    }
+}
+
+/// Queue the prompt's path. The first path added turns a single-directory queue into a
+/// session, whose state moves to the session directory.
+fn submit_add_path(state: &mut AppState) {
+    let Some(prompt) = state.add_path.as_mut() else {
+        return;
+    };
+    let input = prompt.input.trim();
+    let path = match input.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().map_or_else(|| PathBuf::from(input), |home| home.join(rest)),
+        None => PathBuf::from(input),
+    };
+    if input.is_empty() || !path.exists() {
+        prompt.error = Some(format!("Not found: {}", input));
+        return;
+    }
+    let path = std::path::absolute(&path).unwrap_or(path);
+
+    if state.sources.is_empty() {
+        let session_dir = match crate::engine::session_dir(&state.session_name) {
+            Ok(dir) => dir,
+            Err(e) => {
+                prompt.error = Some(e.to_string());
+                return;
+            }
+        };
+        if let Err(e) = std::fs::create_dir_all(&session_dir) {
+            prompt.error = Some(format!("Failed to create session: {}", e));
+            return;
+        }
+        match crate::engine::QueueLock::acquire(&session_dir) {
+            Ok(lock) => state.queue_lock = Some(lock),
+            Err(e) => {
+                prompt.error = Some(e.to_string());
+                return;
+            }
+        }
+        if let Some(root) = state.root_path.replace(session_dir) {
+            state.sources.push(std::path::absolute(&root).unwrap_or(root));
+        }
+    }
+    if !state.sources.contains(&path) {
+        state.sources.push(path.clone());
+    }
+
+    state.add_path = None;
+    state.requested_scan = Some(path);
+}
+
+/// Swap the selected job with its neighbour, keeping it selected
+fn move_selected(state: &mut AppState, up: bool) {
+    let Some(selected) = state.dashboard.table_state.selected() else {
+        return;
+    };
+    if let Some(moved) = crate::engine::move_job(&mut state.dashboard.jobs, selected, up) {
+        state.dashboard.table_state.select(Some(moved));
+        state.dashboard.queue_sort = None;
+        sync_queue_order(state);
+    }
+}
+
+/// Mirror the dashboard's queue order and priorities into `.enc_state` and persist it
+fn sync_queue_order(state: &mut AppState) {
+    if let Some(ref mut enc_state) = state.enc_state {
+        enc_state.apply_order(&state.dashboard.jobs);
+    }
+    if let (Some(enc_state), Some(root)) = (&state.enc_state, &state.root_path) {
+        let _ = enc_state.save(root); // Ignore write errors (matches existing pattern)
+    }
+}
+
 pub(super) fn handle_dashboard_mouse(mouse: MouseEvent, state: &mut AppState) {
     let dashboard = &mut state.dashboard;
 
@@ -9,18 +9,19 @@ use crossterm::{
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use ratatui::{Terminal, backend::CrosstermBackend};
+use std::collections::HashSet;
 use std::io;
 use std::path::PathBuf;
 use std::rc::Rc;
-use std::sync::mpsc::{self, Receiver};
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
 use std::time::{Duration, Instant};
 
 use crate::ui::{
-    ConfigScreen, Dashboard, HelpModal, QuitModal, StatsScreen,
+    AddPathModal, ConfigScreen, Dashboard, HelpModal, QuitModal, StatsScreen,
     focus::ConfigFocus,
     help::{HelpModalState, HelpSection},
-    state::{AppState, QuitConfirmationState, Screen},
+    state::{AppState, QuitConfirmationState, Screen, WatchState},
 };
 
 mod config;
@@ -31,28 +32,63 @@ mod stats;
 mod workers;
 
 struct ScanConfig {
-    root: PathBuf,
+    /// Directories to scan and files to queue, in order
+    sources: Vec<PathBuf>,
+    /// Inputs already in the queue, which are not queued again
+    known_inputs: HashSet<PathBuf>,
+    /// Outputs already written by queued jobs, which new jobs must not reuse
+    claims: crate::engine::OutputClaims,
     profile: String,
     overwrite: bool,
     custom_output_dir: Option<String>,
     custom_pattern: Option<String>,
     custom_container: Option<String>,
+    filter: crate::engine::ScanFilter,
+    policy: crate::engine::JobPolicy,
+}
+
+impl ScanConfig {
+    fn build_job(&self, path: PathBuf) -> crate::engine::VideoJob {
+        crate::engine::build_job_with_policy(
+            path,
+            &self.profile,
+            self.overwrite,
+            self.custom_output_dir.as_deref(),
+            self.custom_pattern.as_deref(),
+            self.custom_container.as_deref(),
+            &self.policy,
+        )
+    }
 }
 
 fn spawn_scan_thread(config: ScanConfig, tx: mpsc::Sender<UiEvent>) {
     thread::spawn(move || {
-        let result = crate::engine::scan_streaming(&config.root, |path| {
-            let job = crate::engine::build_job_from_path(
-                path,
-                &config.profile,
-                config.overwrite,
-                config.custom_output_dir.as_deref(),
-                config.custom_pattern.as_deref(),
-                config.custom_container.as_deref(),
-            );
-
-            let _ = tx.send(UiEvent::ScanJob(Box::new(job)));
-        });
+        let build = |path| config.build_job(path);
+        let mut known = config.known_inputs.clone();
+        let mut claims = config.claims.clone();
+        let mut send = |mut job: crate::engine::VideoJob, origin: &PathBuf| {
+            if known.insert(job.input_path.clone()) {
+                claims.claim(&mut job);
+                job.origin = Some(origin.clone());
+                let _ = tx.send(UiEvent::ScanJob(Box::new(job)));
+            }
+        };
+
+        let mut result = Ok(());
+        for source in &config.sources {
+            if source.is_file() {
+                // Files named explicitly skip the scan filters
+                send(build(source.clone()), source);
+                continue;
+            }
+            // Jobs are probed in parallel but still arrive in walk order
+            result = crate::engine::scan_jobs_streaming(source, &config.filter, build, |job| {
+                send(job, source)
+            });
+            if result.is_err() {
+                break;
+            }
+        }
 
         match result {
             Ok(_) => {
@@ -67,11 +103,12 @@ fn spawn_scan_thread(config: ScanConfig, tx: mpsc::Sender<UiEvent>) {
 
 // Event types sent from dedicated event thread to main loop
 enum UiEvent {
-    Input(Event),                          // Keyboard, mouse, or other terminal events
-    Tick,                                  // Periodic update for rendering and metrics
-    ScanJob(Box<crate::engine::VideoJob>), // Discovered job during initial scan
-    ScanFinished,                          // Initial scan completed
-    ScanFailed(String),                    // Initial scan failed
+    Input(Event),                           // Keyboard, mouse, or other terminal events
+    Tick,                                   // Periodic update for rendering and metrics
+    ScanJob(Box<crate::engine::VideoJob>),  // Discovered job during initial scan
+    WatchJob(Box<crate::engine::VideoJob>), // New file in the --watch directory
+    ScanFinished,                           // Initial scan completed
+    ScanFailed(String),                     // Initial scan failed
 }
 
 /// Spawn a dedicated thread for event polling.
@@ -107,27 +144,33 @@ fn spawn_event_thread(tx: mpsc::Sender<UiEvent>) {
 }
 
 pub fn run_ui() -> io::Result<()> {
-    run_ui_with_options(None, None, None, &crate::config::Config::default())
+    run_ui_with_options(
+        Vec::new(),
+        crate::engine::DEFAULT_SESSION.to_string(),
+        None,
+        None,
+        None,
+        &crate::config::Config::default(),
+        None,
+    )
 }
 
 pub fn run_ui_with_options(
-    directory: Option<std::path::PathBuf>,
+    paths: Vec<std::path::PathBuf>,
+    session: String,
+    watch: Option<std::path::PathBuf>,
     autostart: Option<bool>,
     scan_on_launch: Option<bool>,
     config: &crate::config::Config,
+    event_sink: Option<crate::engine::events::EventSink>,
 ) -> io::Result<()> {
-    // Setup terminal with alternate screen (full terminal)
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-
-    // Enter alternate screen and enable mouse capture
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-
     // Create app state
-    let mut app_state = AppState::default();
+    let mut app_state = AppState {
+        event_sink,
+        scan_filter: config.scan.clone(),
+        job_policy: config.job_policy(),
+        ..AppState::default()
+    };
 
     // Load default profile on startup (using config preferences)
     config::initialize_default_profile(&mut app_state, config);
@@ -150,9 +193,45 @@ pub fn run_ui_with_options(
     // Determine root directory
     // Priority: CLI arg > current directory
     // (default_directory from config is ignored - app always works on current dir unless told otherwise)
-    let root = directory.unwrap_or_else(|| {
-        std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."))
-    });
+    // Several paths, or any file, make a session whose state lives in the session directory.
+    // A named session given no paths resumes with the sources it saved.
+    let resume = paths.is_empty() && session != crate::engine::DEFAULT_SESSION;
+    let is_session = resume || paths.len() > 1 || paths.iter().any(|path| path.is_file());
+    let root = if is_session {
+        let dir = crate::engine::session_dir(&session).map_err(io::Error::other)?;
+        std::fs::create_dir_all(&dir)?;
+        app_state.sources = if resume {
+            // Read-only: another instance may still own the session (checked below)
+            crate::engine::EncState::load_read_only(&dir)
+                .map(|saved| saved.sources)
+                .unwrap_or_default()
+        } else {
+            paths
+                .into_iter()
+                .map(|path| std::path::absolute(&path).unwrap_or(path))
+                .collect()
+        };
+        dir
+    } else {
+        paths.into_iter().next().unwrap_or_else(|| {
+            std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."))
+        })
+    };
+    app_state.session_name = session;
+
+    // Refuse to share a queue with another running instance
+    app_state.queue_lock =
+        Some(crate::engine::QueueLock::acquire(&root).map_err(io::Error::other)?);
+
+    // Setup terminal with alternate screen (full terminal)
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+
+    // Enter alternate screen and enable mouse capture
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
 
     // Determine whether to scan on launch (CLI flag > config > default)
     let should_scan = scan_on_launch.unwrap_or(config.startup.scan_on_launch);
@@ -166,35 +245,18 @@ pub fn run_ui_with_options(
     spawn_event_thread(event_tx.clone());
 
     if should_scan {
-        let container_options = ["webm", "mp4", "mkv", "avi"];
-        let custom_container = app_state
-            .config
-            .container_dropdown_state
-            .selected()
-            .and_then(|idx| container_options.get(idx))
-            .copied()
-            .unwrap_or("webm")
-            .to_string();
-
-        let scan_config = ScanConfig {
-            root: root.clone(),
-            profile: config.defaults.profile.clone(),
-            overwrite: app_state.config.overwrite,
-            custom_output_dir: if app_state.config.output_dir.is_empty() {
-                None
-            } else {
-                Some(app_state.config.output_dir.clone())
-            },
-            custom_pattern: Some(app_state.config.filename_pattern.clone()),
-            custom_container: Some(custom_container),
+        let sources = if app_state.sources.is_empty() {
+            vec![root.clone()]
+        } else {
+            app_state.sources.clone()
         };
+        let scan_config = scan_config(&app_state, config.defaults.profile.clone(), sources);
 
         // Initialize enc_state so skip toggles stay in sync while jobs stream in
-        app_state.enc_state = Some(crate::engine::EncState::new(
-            Vec::new(),
-            scan_config.profile.clone(),
-            root.clone(),
-        ));
+        let mut enc_state =
+            crate::engine::EncState::new(Vec::new(), scan_config.profile.clone(), root.clone());
+        enc_state.sources = app_state.sources.clone();
+        app_state.enc_state = Some(enc_state);
 
         app_state.scan_in_progress = true;
         app_state.pending_autostart = should_autostart;
@@ -202,8 +264,20 @@ pub fn run_ui_with_options(
         spawn_scan_thread(scan_config, event_tx.clone());
     }
 
+    if let Some(dir) = watch {
+        app_state.watch = Some(WatchState {
+            dir,
+            settings: config.watch,
+            autostart: should_autostart,
+        });
+        // Otherwise the watcher starts once the initial scan has queued what is there
+        if !should_scan {
+            start_watch(&mut app_state, &event_tx);
+        }
+    }
+
     // Main loop
-    let result = run_app(&mut terminal, &mut app_state, event_rx);
+    let result = run_app(&mut terminal, &mut app_state, event_rx, event_tx);
 
     // Restore terminal: leave alternate screen and disable mouse capture
     disable_raw_mode()?;
@@ -217,16 +291,149 @@ pub fn run_ui_with_options(
     result
 }
 
+/// Scan settings from the current config, for `sources`
+fn scan_config(state: &AppState, profile: String, sources: Vec<PathBuf>) -> ScanConfig {
+    let container_options = ["webm", "mp4", "mkv", "avi"];
+    let custom_container = state
+        .config
+        .container_dropdown_state
+        .selected()
+        .and_then(|idx| container_options.get(idx))
+        .copied()
+        .unwrap_or("webm")
+        .to_string();
+
+    ScanConfig {
+        policy: state.job_policy.clone().with_scan_roots(sources.clone()),
+        sources,
+        known_inputs: state
+            .dashboard
+            .jobs
+            .iter()
+            .map(|job| job.input_path.clone())
+            .collect(),
+        claims: crate::engine::OutputClaims::with_jobs(
+            state.job_policy.collision,
+            &state.dashboard.jobs,
+        ),
+        profile,
+        overwrite: state.config.overwrite,
+        custom_output_dir: if state.config.output_dir.is_empty() {
+            None
+        } else {
+            Some(state.config.output_dir.clone())
+        },
+        custom_pattern: Some(state.config.filename_pattern.clone()),
+        custom_container: Some(custom_container),
+        filter: state.scan_filter.clone(),
+    }
+}
+
+/// Scan a path added from the dashboard prompt, streaming its jobs onto the queue
+fn start_added_path_scan(state: &mut AppState, path: PathBuf, tx: &Sender<UiEvent>) {
+    let profile = state
+        .config
+        .current_profile_name
+        .clone()
+        .unwrap_or_else(|| "YouTube 4K".to_string());
+
+    if state.enc_state.is_none() {
+        if let Some(root) = &state.root_path {
+            state.enc_state = Some(crate::engine::EncState::new(
+                state.dashboard.jobs.clone(),
+                profile.clone(),
+                root.clone(),
+            ));
+        }
+    }
+    if let Some(ref mut enc_state) = state.enc_state {
+        enc_state.sources = state.sources.clone();
+        if let Some(root) = &state.root_path {
+            enc_state.root_path = root.clone();
+        }
+    }
+
+    let scan_config = scan_config(state, profile, vec![path]);
+    state.scan_in_progress = true;
+    spawn_scan_thread(scan_config, tx.clone());
+}
+
+/// Start polling the `--watch` directory for new files (once)
+fn start_watch(state: &mut AppState, tx: &Sender<UiEvent>) {
+    let Some(watch) = state.watch.clone() else {
+        return;
+    };
+    if state.dashboard.watching.is_some() {
+        return;
+    }
+
+    let profile = state
+        .config
+        .current_profile_name
+        .clone()
+        .unwrap_or_else(|| "YouTube 4K".to_string());
+    let scan_config = scan_config(state, profile, vec![watch.dir.clone()]);
+
+    let mut watcher = crate::engine::watch::Watcher::polling(
+        &watch.dir,
+        scan_config.filter.clone(),
+        watch.settings.settle(),
+    );
+    for job in &state.dashboard.jobs {
+        watcher.ignore_job(job);
+    }
+
+    let tx = tx.clone();
+    crate::engine::watch::spawn(
+        watcher,
+        watch.settings.interval(),
+        move |path| scan_config.build_job(path),
+        move |job| tx.send(UiEvent::WatchJob(Box::new(job))).is_ok(),
+    );
+    state.dashboard.watching = Some(watch.dir);
+}
+
+/// Queue jobs for files that arrived in the watched directory and dispatch them
+fn add_watched_jobs(state: &mut AppState, jobs: Vec<crate::engine::VideoJob>) {
+    let mut claims =
+        crate::engine::OutputClaims::with_jobs(state.job_policy.collision, &state.dashboard.jobs);
+    for mut job in jobs {
+        // Skip files queued by a rescan since, and outputs of our own jobs
+        let known = state.dashboard.jobs.iter().any(|existing| {
+            existing.input_path == job.input_path || existing.output_path == job.input_path
+        });
+        if !known {
+            claims.claim(&mut job);
+            add_scanned_job(state, job);
+        }
+    }
+
+    // add_scanned_job already queued them in enc_state; just persist it
+    if let (Some(enc_state), Some(root)) = (&state.enc_state, &state.root_path) {
+        let _ = enc_state.save(root);
+    }
+
+    if state.worker_pool.is_some() {
+        for _ in 0..state.config.max_workers {
+            workers::spawn_next_job(state);
+        }
+    } else if state.watch.as_ref().is_some_and(|watch| watch.autostart) {
+        let _ = workers::start_encoding_from_loaded_jobs(state);
+    }
+}
+
 fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     state: &mut AppState,
     event_rx: Receiver<UiEvent>,
+    event_tx: Sender<UiEvent>,
 ) -> io::Result<()> {
     loop {
         // Collect all pending events so we can coalesce tick bursts and keep inputs snappy
         let mut pending_ticks: u64 = 0;
         let mut pending_inputs: Vec<Event> = Vec::new();
         let mut pending_scan_jobs: Vec<crate::engine::VideoJob> = Vec::new();
+        let mut pending_watch_jobs: Vec<crate::engine::VideoJob> = Vec::new();
         let mut scan_finished = false;
         let mut scan_error: Option<String> = None;
 
@@ -236,6 +443,7 @@ fn run_app<B: ratatui::backend::Backend>(
                 UiEvent::Tick => pending_ticks += 1,
                 UiEvent::Input(ev) => pending_inputs.push(ev),
                 UiEvent::ScanJob(job) => pending_scan_jobs.push(*job),
+                UiEvent::WatchJob(job) => pending_watch_jobs.push(*job),
                 UiEvent::ScanFinished => scan_finished = true,
                 UiEvent::ScanFailed(err) => scan_error = Some(err),
             },
@@ -250,6 +458,7 @@ fn run_app<B: ratatui::backend::Backend>(
                 UiEvent::Tick => pending_ticks += 1,
                 UiEvent::Input(ev) => pending_inputs.push(ev),
                 UiEvent::ScanJob(job) => pending_scan_jobs.push(*job),
+                UiEvent::WatchJob(job) => pending_watch_jobs.push(*job),
                 UiEvent::ScanFinished => scan_finished = true,
                 UiEvent::ScanFailed(err) => scan_error = Some(err),
             }
@@ -265,6 +474,7 @@ fn run_app<B: ratatui::backend::Backend>(
             state.scan_in_progress = false;
             state.pending_autostart = false;
             // Error is displayed in UI status, no need for console output
+            start_watch(state, &event_tx);
         }
 
         if scan_finished {
@@ -279,7 +489,11 @@ fn run_app<B: ratatui::backend::Backend>(
             }
 
             // If autostart was requested, kick it off now that jobs are loaded
-            if state.pending_autostart && !state.dashboard.jobs.is_empty() {
+            if state.worker_pool.is_some() {
+                // Already encoding: pick up jobs from an added path
+                workers::spawn_next_job(state);
+                state.pending_autostart = false;
+            } else if state.pending_autostart && !state.dashboard.jobs.is_empty() {
                 if let Err(_e) = workers::start_encoding_from_loaded_jobs(state) {
                     // Error will be visible in UI status, user can start manually
                 }
@@ -287,6 +501,12 @@ fn run_app<B: ratatui::backend::Backend>(
             } else {
                 state.pending_autostart = false;
             }
+
+            start_watch(state, &event_tx);
+        }
+
+        if !pending_watch_jobs.is_empty() {
+            add_watched_jobs(state, pending_watch_jobs);
         }
 
         // Process input events first so user commands are never stuck behind a tick backlog
@@ -306,6 +526,10 @@ fn run_app<B: ratatui::backend::Backend>(
             }
         }
 
+        if let Some(path) = state.requested_scan.take() {
+            start_added_path_scan(state, path, &event_tx);
+        }
+
         if pending_ticks > 0 {
             // Update metrics on tick (~60 FPS)
             let now = Instant::now();
@@ -320,7 +544,15 @@ fn run_app<B: ratatui::backend::Backend>(
             // Process at most 10 messages per frame to keep UI responsive
             for _ in 0..10 {
                 match pool.receiver().try_recv() {
-                    Ok(msg) => workers::handle_worker_message(msg, state),
+                    Ok(msg) => {
+                        if let Some(sink) = state.event_sink.as_mut() {
+                            let job = msg
+                                .job_id()
+                                .and_then(|id| state.dashboard.jobs.iter().find(|j| j.id == id));
+                            let _ = sink.emit(&msg, job);
+                        }
+                        workers::handle_worker_message(msg, state)
+                    }
                     Err(_) => break, // No more messages
                 }
             }
@@ -358,6 +590,10 @@ fn run_app<B: ratatui::backend::Backend>(
                 HelpModal::render(frame, help_state);
             }
 
+            if let Some(ref add_path) = state.add_path {
+                AddPathModal::render(frame, add_path);
+            }
+
             // Render quit confirmation modal on top of everything
             if let Some(ref quit_state) = state.quit_confirmation {
                 QuitModal::render(frame, quit_state);
@@ -405,6 +641,12 @@ fn handle_key(key: KeyEvent, state: &mut AppState) -> bool {
         }
     }
 
+    // The add path prompt takes every key while it's open
+    if state.add_path.is_some() {
+        dashboard::handle_add_path_key(key, state);
+        return false;
+    }
+
     // Check if help modal is open - handle help keys first
     if state.help_modal.is_some() {
         help::handle_help_key(key, state);
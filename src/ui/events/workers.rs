@@ -100,6 +100,25 @@ pub(super) fn handle_worker_message(
                 }
             }
         }
+        WorkerMessage::SegmentsUpdated { job_id, segments } => {
+            if let Some(job) = state.dashboard.jobs.iter_mut().find(|j| j.id == job_id) {
+                job.segments = segments.clone();
+            }
+            // Save right away, so finished segments survive a crash or reboot
+            if let Some(ref mut enc_state) = state.enc_state {
+                if let Some(job) = enc_state.jobs.iter_mut().find(|j| j.id == job_id) {
+                    job.segments = segments;
+                }
+                if let Some(ref root) = state.root_path {
+                    let _ = enc_state.save(root);
+                }
+            }
+        }
+        WorkerMessage::JobReport { job_id, report } => {
+            if let Some(job) = state.dashboard.jobs.iter_mut().find(|j| j.id == job_id) {
+                job.report = Some(report);
+            }
+        }
         WorkerMessage::JobCompleted { job_id } => {
             // Mark job as Done
             if let Some(job) = state.dashboard.jobs.iter_mut().find(|j| j.id == job_id) {
@@ -121,16 +140,17 @@ pub(super) fn handle_worker_message(
                         state.stats.session.encode_time_secs += encode_time;
 
                         // Update lifetime stats
-                        state.stats.lifetime.total_input_bytes += input_size;
-                        state.stats.lifetime.total_output_bytes += output_size;
-                        state.stats.lifetime.total_encode_time_secs += encode_time;
-                        state.stats.lifetime.total_jobs_completed += 1;
-                        state.stats.lifetime.last_updated = Some(chrono::Utc::now().to_rfc3339());
+                        state
+                            .stats
+                            .lifetime
+                            .record_completed(input_size, output_size, encode_time);
 
                         // Save lifetime stats to disk
                         let _ = state.stats.lifetime.save();
                     }
                 }
+
+                let _ = crate::engine::history::record_job(job);
             }
             if let Some(ref mut enc_state) = state.enc_state {
                 if let Some(job) = enc_state.jobs.iter_mut().find(|j| j.id == job_id) {
@@ -139,7 +159,7 @@ pub(super) fn handle_worker_message(
                 }
                 // Save .enc_queue status
                 if let Some(ref root) = state.root_path {
-                    let _ = enc_state.save_queue_status(root);
+                    let _ = enc_state.save_all_queue_status(root);
                 }
             }
             // Don't spawn next job here - wait for WorkerIdle message to avoid race condition
@@ -152,9 +172,10 @@ pub(super) fn handle_worker_message(
 
                 // Update stats
                 state.stats.session.jobs_failed += 1;
-                state.stats.lifetime.total_jobs_failed += 1;
-                state.stats.lifetime.last_updated = Some(chrono::Utc::now().to_rfc3339());
+                state.stats.lifetime.record_failed();
                 let _ = state.stats.lifetime.save();
+
+                let _ = crate::engine::history::record_job(job);
             }
             if let Some(ref mut enc_state) = state.enc_state {
                 if let Some(job) = enc_state.jobs.iter_mut().find(|j| j.id == job_id) {
@@ -164,6 +185,9 @@ pub(super) fn handle_worker_message(
             }
             // Don't spawn next job here - wait for WorkerIdle message to avoid race condition
         }
+        WorkerMessage::CalibrationStep { .. } => {
+            // Calibration progress is already reflected via ProgressUpdate
+        }
         WorkerMessage::WorkerIdle { worker_id: _ } => {
             // Worker is idle and ready for more work
             spawn_next_job(state);
@@ -182,43 +206,25 @@ pub(super) fn spawn_next_job(state: &mut AppState) {
 
         // Find next encodable pending job
         if let Some(ref mut enc_state) = state.enc_state {
-            // Loop through all pending jobs to find one that can actually be encoded
-            let encodable_job_idx = loop {
-                let next_job = enc_state
-                    .jobs
-                    .iter()
-                    .enumerate()
-                    .find(|(_, j)| j.status == JobStatus::Pending);
-
-                match next_job {
-                    Some((idx, _)) => {
-                        let mut job = enc_state.jobs[idx].clone();
-
-                        // Check if output exists and overwrite is disabled
-                        // If so, skip this job and try the next one
-                        if job.output_path.exists() && !job.overwrite {
-                            job.status = JobStatus::Skipped;
-                            job.last_error =
-                                Some("Output exists and overwrite is disabled".to_string());
-                            enc_state.jobs[idx] = job.clone();
-                            state.dashboard.jobs[idx] = job;
-                            // Continue loop to find next encodable job
-                            continue;
-                        }
-
-                        // Found an encodable job
-                        break Some(idx);
-                    }
-                    None => {
-                        // No more pending jobs
-                        break None;
-                    }
+            // Highest priority first; jobs whose output already exists get skipped on the way
+            let encodable_job_idx = enc_state.next_encodable_job();
+
+            // Mirror those skips onto the dashboard
+            for (shown, job) in state.dashboard.jobs.iter_mut().zip(&enc_state.jobs) {
+                if job.status == JobStatus::Skipped && shown.status == JobStatus::Pending {
+                    shown.status = JobStatus::Skipped;
+                    shown.last_error = job.last_error.clone();
                 }
-            };
+            }
 
             // Spawn worker with the encodable job
             if let Some(idx) = encodable_job_idx {
                 let job = enc_state.jobs[idx].clone();
+                // Claim it now, so the next call doesn't pick it again before JobStarted arrives
+                enc_state.jobs[idx].status = JobStatus::Running;
+                if let Some(shown) = state.dashboard.jobs.iter_mut().find(|j| j.id == job.id) {
+                    shown.status = JobStatus::Running;
+                }
 
                 // Build hardware encoding config if enabled AND available
                 let hw_config = if state.config.use_hardware_encoding
@@ -255,18 +261,27 @@ pub(super) fn spawn_next_job(state: &mut AppState) {
                     None
                 };
 
-                // Get profile from enc_state if available
-                let profile = state
-                    .enc_state
-                    .as_ref()
-                    .and_then(|es| es.profile_config.clone());
+                // Get profile from enc_state if available (rule-matched jobs use their own
+                // profile, including its hardware settings)
+                let profile = state.enc_state.as_ref().and_then(|es| es.profile_for(&job));
+                let hw_config = if job.profile_rule.is_some() {
+                    None
+                } else {
+                    hw_config
+                };
 
-                // Spawn worker for this job
+                // Spawn worker for this job; hand it back to the queue if that fails
+                let job_id = job.id;
                 if pool
                     .spawn_worker_with_profile(idx, job, hw_config, profile)
-                    .is_ok()
+                    .is_err()
                 {
-                    // Job will be marked as Running when JobStarted message arrives
+                    if let Some(ref mut enc_state) = state.enc_state {
+                        enc_state.jobs[idx].status = JobStatus::Pending;
+                    }
+                    if let Some(shown) = state.dashboard.jobs.iter_mut().find(|j| j.id == job_id) {
+                        shown.status = JobStatus::Pending;
+                    }
                 }
             }
         }
@@ -284,10 +299,17 @@ pub(super) fn rescan_directory(
     state.dashboard.jobs.clear();
     state.enc_state = None;
 
-    // Scan for video files
-    let files = engine::scan(&directory).map_err(|e| format!("Failed to scan directory: {}", e))?;
+    // Get profile name from config
+    let profile_name = state
+        .config
+        .current_profile_name
+        .clone()
+        .unwrap_or_else(|| "YouTube 4K".to_string());
+
+    // Scan for video files and build a fresh job queue (respect overwrite setting)
+    let jobs = build_source_jobs(state, &directory, &profile_name)?;
 
-    if files.is_empty() {
+    if jobs.is_empty() {
         // Clear state file if no videos found
         let state_path = directory.join(".enc_state");
         if state_path.exists() {
@@ -296,12 +318,50 @@ pub(super) fn rescan_directory(
         return Err("No video files found in directory".to_string());
     }
 
-    // Get profile name from config
-    let profile_name = state
-        .config
-        .current_profile_name
-        .clone()
-        .unwrap_or_else(|| "YouTube 4K".to_string());
+    // Create new enc_state (don't merge with old one, apart from calibrations and segments)
+    let mut enc_state = engine::EncState::new(jobs, profile_name, directory.clone());
+    enc_state.sources = state.sources.clone();
+    enc_state.restore_calibrations(&directory);
+    enc_state.restore_segments(&directory);
+
+    // Save new state
+    enc_state
+        .save(&directory)
+        .map_err(|e| format!("Failed to save .enc_state: {}", e))?;
+
+    // Update app state
+    state.dashboard.jobs = enc_state.jobs.clone();
+    state.enc_state = Some(enc_state);
+    state.root_path = Some(directory);
+
+    // Reset table selection to first job
+    if !state.dashboard.jobs.is_empty() {
+        state.dashboard.table_state.select(Some(0));
+    } else {
+        state.dashboard.table_state.select(None);
+    }
+
+    Ok(())
+}
+
+/// Scan the session's sources (or `directory` alone) and build a job per video found,
+/// tagged with the source it came from
+fn build_source_jobs(
+    state: &AppState,
+    directory: &std::path::Path,
+    profile_name: &str,
+) -> Result<Vec<crate::engine::VideoJob>, String> {
+    use crate::engine;
+
+    let sources = if state.sources.is_empty() {
+        vec![directory.to_path_buf()]
+    } else {
+        state.sources.clone()
+    };
+    let (origins, files): (Vec<_>, Vec<_>) = engine::expand_sources(&sources, &state.scan_filter)
+        .map_err(|e| format!("Failed to scan directory: {}", e))?
+        .into_iter()
+        .unzip();
 
     // Get custom pattern and container from config
     let custom_pattern = Some(state.config.filename_pattern.as_str());
@@ -320,37 +380,19 @@ pub(super) fn rescan_directory(
         Some(state.config.output_dir.as_str())
     };
 
-    // Build fresh job queue (respect overwrite setting)
-    let jobs = engine::build_job_queue(
+    let mut jobs = engine::build_job_queue_with_policy(
         files,
-        &profile_name,
+        profile_name,
         state.config.overwrite,
         custom_output_dir,
         custom_pattern,
         custom_container,
+        &state.job_policy.clone().with_scan_roots(sources),
     );
-
-    // Create new enc_state (don't merge with old one)
-    let enc_state = engine::EncState::new(jobs.clone(), profile_name, directory.clone());
-
-    // Save new state
-    enc_state
-        .save(&directory)
-        .map_err(|e| format!("Failed to save .enc_state: {}", e))?;
-
-    // Update app state
-    state.dashboard.jobs = jobs;
-    state.enc_state = Some(enc_state);
-    state.root_path = Some(directory);
-
-    // Reset table selection to first job
-    if !state.dashboard.jobs.is_empty() {
-        state.dashboard.table_state.select(Some(0));
-    } else {
-        state.dashboard.table_state.select(None);
+    for (job, origin) in jobs.iter_mut().zip(origins) {
+        job.origin = Some(origin);
     }
-
-    Ok(())
+    Ok(jobs)
 }
 
 /// Start encoding from already-loaded jobs (used for autostart)
@@ -378,12 +420,18 @@ pub(super) fn start_encoding_from_loaded_jobs(state: &mut AppState) -> Result<()
     let profile = crate::engine::Profile::from_config(profile_name.clone(), &state.config);
 
     // Create enc_state from already-loaded jobs
-    let enc_state = crate::engine::EncState::new_with_profile(
+    let mut enc_state = crate::engine::EncState::new_with_profile(
         state.dashboard.jobs.clone(),
         profile_name,
         root_path.clone(),
         Some(profile),
     );
+    enc_state.sources = state.sources.clone();
+    let restored =
+        enc_state.restore_calibrations(&root_path) + enc_state.restore_segments(&root_path);
+    if restored > 0 {
+        state.dashboard.jobs = enc_state.jobs.clone();
+    }
 
     // Save initial state
     enc_state
@@ -425,46 +473,22 @@ pub(super) fn start_encoding(
 
     // Always rebuild jobs to ensure all current settings (filename pattern, container, profile changes) are applied
     // This means skip selections are lost, but ensures output filenames match current config
-    let files = engine::scan(&directory).map_err(|e| format!("Failed to scan directory: {}", e))?;
+    let jobs = build_source_jobs(state, &directory, &profile_name)?;
 
-    if files.is_empty() {
+    if jobs.is_empty() {
         return Err("No video files found in directory".to_string());
     }
 
-    // Get custom pattern and container from config
-    let custom_pattern = Some(state.config.filename_pattern.as_str());
-    let container_options = ["webm", "mp4", "mkv", "avi"];
-    let container_idx = state
-        .config
-        .container_dropdown_state
-        .selected()
-        .unwrap_or(0);
-    let custom_container = Some(container_options[container_idx]);
-
-    // Get output directory from config (None means use input file's directory)
-    let custom_output_dir = if state.config.output_dir.is_empty() {
-        None
-    } else {
-        Some(state.config.output_dir.as_str())
-    };
-
-    // Build job queue (respect overwrite setting)
-    let jobs = engine::build_job_queue(
-        files,
-        &profile_name,
-        state.config.overwrite,
-        custom_output_dir,
-        custom_pattern,
-        custom_container,
-    );
-
-    // Create enc_state with jobs (preserving any skip status)
-    let enc_state = engine::EncState::new_with_profile(
-        jobs.clone(),
+    // Create enc_state with jobs (preserving any skip status and stored calibrations)
+    let mut enc_state = engine::EncState::new_with_profile(
+        jobs,
         profile_name,
         directory.clone(),
         Some(profile),
     );
+    enc_state.sources = state.sources.clone();
+    enc_state.restore_calibrations(&directory);
+    enc_state.restore_segments(&directory);
 
     // Save initial state
     enc_state
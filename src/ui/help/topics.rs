@@ -365,6 +365,11 @@ impl HelpModal {
             )]),
             Line::from("  Template for output filenames"),
             Line::from("  Supports: {filename}, {basename}, {profile}, {ext}"),
+            Line::from("  Source: {parent}, {relpath}, {height}, {width}, {hdr}"),
+            Line::from("  Encode: {codec}, {encoder}, {crf}, {quality}, {vmaf}, {date}"),
+            Line::from("  From the name: {title}, {year}, {season}, {episode}"),
+            Line::from("  Shown in red when it uses an unknown token"),
+            Line::from("  {date} renames outputs daily, so done files are re-encoded"),
             Line::from("  Default: {basename}"),
             Line::from(""),
             Line::from(vec![Span::styled(
@@ -867,6 +872,10 @@ impl HelpModal {
                 Span::styled("  R      ", Style::default().fg(Color::Yellow)),
                 Span::raw("- Rescan directory for new files"),
             ]),
+            Line::from(vec![
+                Span::styled("  A      ", Style::default().fg(Color::Yellow)),
+                Span::raw("- Add a directory or file to the queue"),
+            ]),
             Line::from(vec![
                 Span::styled("  D/Del  ", Style::default().fg(Color::Yellow)),
                 Span::raw("- Delete selected job"),
@@ -875,6 +884,18 @@ impl HelpModal {
                 Span::styled("  ↑/↓    ", Style::default().fg(Color::Yellow)),
                 Span::raw("- Navigate job list"),
             ]),
+            Line::from(vec![
+                Span::styled("  ⇧↑/⇧↓,KJ", Style::default().fg(Color::Yellow)),
+                Span::raw("- Move selected job up/down the queue"),
+            ]),
+            Line::from(vec![
+                Span::styled("  P      ", Style::default().fg(Color::Yellow)),
+                Span::raw("- Pin selected job to run next (again to unpin)"),
+            ]),
+            Line::from(vec![
+                Span::styled("  O      ", Style::default().fg(Color::Yellow)),
+                Span::raw("- Sort queue: name, size, duration, est. savings"),
+            ]),
             Line::from(vec![
                 Span::styled("  Tab    ", Style::default().fg(Color::Yellow)),
                 Span::raw("- Cycle foreground job"),
@@ -1,5 +1,6 @@
 // Terminal UI using Ratatui
 
+pub mod add_path_modal;
 pub mod components;
 pub mod config;
 pub mod constants;
@@ -13,6 +14,7 @@ pub mod state;
 pub mod stats;
 pub mod widgets;
 
+pub use add_path_modal::AddPathModal;
 pub use config::ConfigScreen;
 pub use dashboard::Dashboard;
 pub use events::{run_ui, run_ui_with_options};
@@ -137,6 +137,23 @@ pub struct QuitConfirmationState {
     pub running_count: usize,
 }
 
+/// State for the "add path" prompt on the dashboard
+#[derive(Debug, Clone, Default)]
+pub struct AddPathState {
+    pub input: String,
+    /// Why the last submitted path was rejected
+    pub error: Option<String>,
+}
+
+/// `--watch` directory and how its arrivals are handled
+#[derive(Debug, Clone)]
+pub struct WatchState {
+    pub dir: std::path::PathBuf,
+    pub settings: crate::engine::watch::WatchSettings,
+    /// Start encoding when files arrive while the queue is idle (--autostart)
+    pub autostart: bool,
+}
+
 pub struct AppState {
     pub current_screen: Screen,
     pub dashboard: DashboardState,
@@ -149,6 +166,12 @@ pub struct AppState {
     pub root_path: Option<std::path::PathBuf>,
     pub help_modal: Option<HelpModalState>,
     pub quit_confirmation: Option<QuitConfirmationState>, // Quit confirmation modal
+    pub add_path: Option<AddPathState>,                   // "Add path" prompt ('A')
+    pub sources: Vec<std::path::PathBuf>, // Paths queued into a session (empty for one directory)
+    pub session_name: String,             // Session the queue moves to once a path is added
+    pub requested_scan: Option<std::path::PathBuf>, // Added path waiting to be scanned
+    pub watch: Option<WatchState>,                  // --watch, started after the first scan
+    pub queue_lock: Option<crate::engine::QueueLock>, // Held on root_path while we own it
     pub app_version: String,
     pub ffmpeg_version: Option<String>,
     pub ffprobe_version: Option<String>,
@@ -156,6 +179,9 @@ pub struct AppState {
     pub huc_available: Option<bool>, // HuC firmware status (for VBR/CBR modes)
     pub scan_in_progress: bool,      // True while initial scan is running
     pub pending_autostart: bool,     // True if we should autostart after scan completes
+    pub event_sink: Option<crate::engine::events::EventSink>, // NDJSON side channel (--events)
+    pub scan_filter: crate::engine::ScanFilter, // [scan] config plus CLI overrides
+    pub job_policy: crate::engine::JobPolicy, // [skip] and [[rules]] config
 }
 
 impl Default for AppState {
@@ -172,6 +198,12 @@ impl Default for AppState {
             root_path: None,          // Set when user provides a directory to encode
             help_modal: None,         // Opened when 'H' key is pressed
             quit_confirmation: None,  // Opened when 'q' pressed with active encodes
+            add_path: None,           // Opened when 'a' is pressed on the dashboard
+            sources: Vec::new(),
+            session_name: crate::engine::DEFAULT_SESSION.to_string(),
+            requested_scan: None,
+            watch: None,
+            queue_lock: None,
             app_version: env!("CARGO_PKG_VERSION").to_string(),
             ffmpeg_version: None,      // Cached when help is first opened
             ffprobe_version: None,     // Cached when help is first opened
@@ -179,6 +211,9 @@ impl Default for AppState {
             huc_available: None,       // Checked when help is first opened
             scan_in_progress: false,
             pending_autostart: false,
+            event_sink: None,
+            scan_filter: crate::engine::ScanFilter::default(),
+            job_policy: crate::engine::JobPolicy::default(),
         }
     }
 }
@@ -197,6 +232,8 @@ pub struct DashboardState {
 
     // Job data (if available)
     pub jobs: Vec<crate::engine::VideoJob>,
+    pub queue_sort: Option<crate::engine::QueueSort>, // Last sort applied with 'O'
+    pub watching: Option<std::path::PathBuf>, // Directory new files are queued from
 
     // GPU monitoring
     pub gpu_data: VecDeque<u64>,     // GPU usage % ring buffer
@@ -224,6 +261,8 @@ impl Default for DashboardState {
             table_inner_area: None,
             hovered_row: None,
             jobs: Vec::new(),
+            queue_sort: None,
+            watching: None,
 
             // GPU monitoring
             gpu_data: VecDeque::with_capacity(240),
@@ -289,7 +328,7 @@ pub struct ConfigState {
     pub max_workers: u32, // Number of concurrent encoding jobs (1 = sequential)
 
     // Filename customization (template-based)
-    // Supports: {filename}, {basename}, {profile}, {ext}
+    // Supports the tokens in engine::PATTERN_TOKENS, e.g. {basename}, {profile}, {ext}
     pub filename_pattern: String,
     pub container_dropdown_state: ListState, // For selecting container extension
 